@@ -0,0 +1,435 @@
+// compare.rs - 两次录制的对齐与差值对比
+use crate::commands::analysis::recording::{interpolate, Recording, MOTION_STATE_CHANNEL};
+use serde::{Deserialize, Serialize};
+
+// 运动状态: 运动中
+const MOTION_STATE_MOVING: f64 = 1.0;
+// 判定起动的偏移阈值 (占通道量程的比例)
+const ONSET_THRESHOLD: f64 = 0.01;
+// 互相关计算时的最大采样点数
+const MAX_CORRELATION_POINTS: usize = 20000;
+
+/// 时间对齐方式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alignment {
+    Start,            // 按录制开始对齐
+    MotionOnset,      // 按运动起动时刻对齐
+    CrossCorrelation, // 按参考通道互相关对齐
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareParams {
+    pub alignment: Alignment,
+    #[serde(default)]
+    pub channels: Vec<String>, // 对比的通道, 为空时对比全部共有通道
+    #[serde(default)]
+    pub reference_channel: Option<String>, // 起动/互相关使用的参考通道
+    #[serde(default = "default_max_lag")]
+    pub max_lag: f64, // 互相关最大时移 s
+    #[serde(default = "default_max_points")]
+    pub max_points: usize, // 差值曲线最大点数
+}
+
+fn default_max_lag() -> f64 {
+    5.0
+}
+
+fn default_max_points() -> usize {
+    2000
+}
+
+impl Default for CompareParams {
+    fn default() -> Self {
+        Self {
+            alignment: Alignment::Start,
+            channels: vec![],
+            reference_channel: None,
+            max_lag: default_max_lag(),
+            max_points: default_max_points(),
+        }
+    }
+}
+
+/// 单通道差值 (B - A)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelDiff {
+    pub name: String,
+    pub rms_diff: f64,
+    pub max_diff: f64,      // 最大绝对差
+    pub max_diff_time: f64, // 最大差出现的时间 (A 时间轴) s
+    pub mean_diff: f64,
+    pub samples: usize,
+    pub time: Vec<f64>, // 抽稀后的差值曲线
+    pub diff: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareReport {
+    pub alignment: Alignment,
+    pub offset: f64, // A 的 t 时刻对应 B 的 t + offset s
+    pub duration_a: f64,
+    pub duration_b: f64,
+    pub duration_diff: f64, // duration_b - duration_a
+    pub channels: Vec<ChannelDiff>,
+    pub missing: Vec<String>, // 只存在于其中一个录制的通道
+}
+
+/// 对比两次录制, 差值为 B - A
+pub fn compare(
+    a: &Recording,
+    b: &Recording,
+    params: &CompareParams,
+) -> Result<CompareReport, String> {
+    if a.time.is_empty() || b.time.is_empty() {
+        return Err("Recording is empty".to_string());
+    }
+
+    let requested: Vec<String> = if params.channels.is_empty() {
+        a.channels
+            .iter()
+            .map(|c| c.name.clone())
+            .chain(b.channels.iter().map(|c| c.name.clone()))
+            .fold(vec![], |mut names, name| {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+                names
+            })
+    } else {
+        params.channels.clone()
+    };
+
+    let (common, missing): (Vec<String>, Vec<String>) = requested
+        .into_iter()
+        .partition(|name| a.channel(name).is_some() && b.channel(name).is_some());
+
+    if common.is_empty() {
+        return Err("No common channels to compare".to_string());
+    }
+
+    let offset = alignment_offset(a, b, &common, params)?;
+
+    let channels = common
+        .iter()
+        .filter_map(|name| channel_diff(a, b, name, offset, params.max_points))
+        .collect();
+
+    Ok(CompareReport {
+        alignment: params.alignment,
+        offset,
+        duration_a: a.duration(),
+        duration_b: b.duration(),
+        duration_diff: b.duration() - a.duration(),
+        channels,
+        missing,
+    })
+}
+
+/// 按对齐方式计算时移: A 的 t 时刻对应 B 的 t + offset
+pub fn alignment_offset(
+    a: &Recording,
+    b: &Recording,
+    common: &[String],
+    params: &CompareParams,
+) -> Result<f64, String> {
+    let offset = match params.alignment {
+        Alignment::Start => 0.0,
+        Alignment::MotionOnset => {
+            let onset_a = motion_onset(a, common, params.reference_channel.as_deref())
+                .ok_or_else(|| "No motion onset found in recording A".to_string())?;
+            let onset_b = motion_onset(b, common, params.reference_channel.as_deref())
+                .ok_or_else(|| "No motion onset found in recording B".to_string())?;
+            onset_b - onset_a
+        }
+        Alignment::CrossCorrelation => {
+            let reference = params
+                .reference_channel
+                .clone()
+                .or_else(|| default_reference(common))
+                .ok_or_else(|| "No reference channel for cross-correlation".to_string())?;
+            cross_correlation_offset(a, b, &reference, params.max_lag)?
+        }
+    };
+
+    Ok(offset)
+}
+
+/// 计算单通道差值及统计
+fn channel_diff(
+    a: &Recording,
+    b: &Recording,
+    name: &str,
+    offset: f64,
+    max_points: usize,
+) -> Option<ChannelDiff> {
+    let (time, diff) = diff_series(a, b, name, offset)?;
+
+    let samples = diff.len();
+    let mean_diff = diff.iter().sum::<f64>() / samples as f64;
+    let rms_diff = (diff.iter().map(|d| d * d).sum::<f64>() / samples as f64).sqrt();
+    let (max_idx, max_diff) = diff
+        .iter()
+        .map(|d| d.abs())
+        .enumerate()
+        .fold((0, 0.0), |acc, (i, d)| if d > acc.1 { (i, d) } else { acc });
+    let max_diff_time = time[max_idx];
+
+    // 抽稀用于绘图, 统计值基于全部数据
+    let stride = samples.div_ceil(max_points.max(1));
+    let time = time.into_iter().step_by(stride).collect();
+    let diff = diff.into_iter().step_by(stride).collect();
+
+    Some(ChannelDiff {
+        name: name.to_string(),
+        rms_diff,
+        max_diff,
+        max_diff_time,
+        mean_diff,
+        samples,
+        time,
+        diff,
+    })
+}
+
+/// 全分辨率差值曲线 (A 时间轴, B - A), 无重叠数据时返回 None
+pub fn diff_series(
+    a: &Recording,
+    b: &Recording,
+    name: &str,
+    offset: f64,
+) -> Option<(Vec<f64>, Vec<f64>)> {
+    let (time, diff): (Vec<f64>, Vec<f64>) = overlap_diff_series(a, b, name, offset)?
+        .into_iter()
+        .filter(|(_, d)| d.is_finite())
+        .unzip();
+
+    if diff.is_empty() {
+        return None;
+    }
+    Some((time, diff))
+}
+
+/// 重叠时间段内的 (时间, B - A), 保留缺失数据产生的非有限值; 无重叠时返回 None
+pub fn overlap_diff_series(
+    a: &Recording,
+    b: &Recording,
+    name: &str,
+    offset: f64,
+) -> Option<Vec<(f64, f64)>> {
+    let series_a = a.channel(name)?;
+    let series_b = b.channel(name)?;
+
+    let points: Vec<(f64, f64)> = a
+        .time
+        .iter()
+        .zip(series_a.values.iter())
+        .filter_map(|(t, va)| Some((*t, interpolate(&b.time, &series_b.values, t + offset)? - va)))
+        .collect();
+
+    if points.is_empty() {
+        return None;
+    }
+    Some(points)
+}
+
+/// 默认参考通道: 优先实际关节位置
+fn default_reference(common: &[String]) -> Option<String> {
+    common
+        .iter()
+        .find(|name| name.starts_with("actual_joint_positions"))
+        .or_else(|| common.first())
+        .cloned()
+}
+
+/// 运动起动时刻: 有运动状态时取第一次进入运动状态,
+/// 否则取参考 (或位置类) 通道第一次偏离初值超过量程 1% 的时刻
+pub fn motion_onset(
+    recording: &Recording,
+    channels: &[String],
+    reference: Option<&str>,
+) -> Option<f64> {
+    if reference.is_none() {
+        if let Some(state) = recording.channel(MOTION_STATE_CHANNEL) {
+            if let Some(idx) = state.values.iter().position(|v| *v == MOTION_STATE_MOVING) {
+                return Some(recording.time[idx]);
+            }
+        }
+    }
+
+    let candidates: Vec<&String> = match reference {
+        Some(name) => channels.iter().filter(|c| c.as_str() == name).collect(),
+        None => {
+            let positions: Vec<&String> = channels
+                .iter()
+                .filter(|c| c.contains("positions") || c.contains("pose"))
+                .collect();
+            if positions.is_empty() {
+                channels.iter().collect()
+            } else {
+                positions
+            }
+        }
+    };
+
+    candidates
+        .into_iter()
+        .filter_map(|name| {
+            let values = &recording.channel(name)?.values;
+            let v0 = *values.first()?;
+            let (min, max) = values
+                .iter()
+                .filter(|v| v.is_finite())
+                .fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+            let range = max - min;
+            if range <= f64::EPSILON {
+                return None;
+            }
+            let idx = values
+                .iter()
+                .position(|v| (v - v0).abs() > range * ONSET_THRESHOLD)?;
+            Some(recording.time[idx])
+        })
+        .fold(None, |acc: Option<f64>, t| {
+            Some(acc.map_or(t, |a| a.min(t)))
+        })
+}
+
+/// 互相关求时移: 在统一采样网格上计算去均值归一化相关系数, 取最大值
+fn cross_correlation_offset(
+    a: &Recording,
+    b: &Recording,
+    reference: &str,
+    max_lag: f64,
+) -> Result<f64, String> {
+    let series_a = a
+        .channel(reference)
+        .ok_or_else(|| format!("Reference channel {} not found", reference))?;
+    let series_b = b
+        .channel(reference)
+        .ok_or_else(|| format!("Reference channel {} not found", reference))?;
+
+    let span = a.duration().max(b.duration());
+    let dt = (median_step(&a.time)).max(span / MAX_CORRELATION_POINTS as f64);
+    if dt <= 0.0 {
+        return Err("Recording has no time span".to_string());
+    }
+
+    let resample = |rec: &Recording, values: &[f64]| -> Vec<f64> {
+        let n = (rec.duration() / dt).floor() as usize + 1;
+        (0..n)
+            .map(|i| interpolate(&rec.time, values, i as f64 * dt).unwrap_or(f64::NAN))
+            .collect()
+    };
+    let xa = resample(a, &series_a.values);
+    let xb = resample(b, &series_b.values);
+
+    let max_shift = (max_lag / dt).round() as i64;
+    let min_overlap = (xa.len().min(xb.len()) / 4).max(2);
+    let mut best = (0i64, f64::MIN);
+    for shift in -max_shift..=max_shift {
+        // 在重叠区间上计算皮尔逊相关系数
+        let (mut n, mut sa, mut sb, mut saa, mut sbb, mut sab) = (0usize, 0.0, 0.0, 0.0, 0.0, 0.0);
+        for (i, va) in xa.iter().enumerate() {
+            let j = i as i64 + shift;
+            if j < 0 || j >= xb.len() as i64 {
+                continue;
+            }
+            let vb = xb[j as usize];
+            if va.is_finite() && vb.is_finite() {
+                n += 1;
+                sa += va;
+                sb += vb;
+                saa += va * va;
+                sbb += vb * vb;
+                sab += va * vb;
+            }
+        }
+        // 重叠过少的时移不可信
+        if n < min_overlap {
+            continue;
+        }
+        let nf = n as f64;
+        let cov = sab - sa * sb / nf;
+        let var = (saa - sa * sa / nf) * (sbb - sb * sb / nf);
+        if var <= f64::EPSILON {
+            continue;
+        }
+        let score = cov / var.sqrt();
+        if score > best.1 {
+            best = (shift, score);
+        }
+    }
+
+    Ok(best.0 as f64 * dt)
+}
+
+fn median_step(time: &[f64]) -> f64 {
+    let mut steps: Vec<f64> = time
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|d| *d > 0.0)
+        .collect();
+    if steps.is_empty() {
+        return 0.0;
+    }
+    steps.sort_by(|x, y| x.total_cmp(y));
+    steps[steps.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::analysis::recording::Series;
+
+    /// 200Hz, 一段静止后做一次正弦运动
+    fn recording(delay: f64, gain: f64) -> Recording {
+        let mut rec = Recording {
+            time: vec![],
+            channels: vec![Series {
+                name: "actual_joint_positions_1".to_string(),
+                values: vec![],
+            }],
+        };
+        for i in 0..2000 {
+            let t = i as f64 * 0.005;
+            let phase = t - 1.0 - delay;
+            let v = if (0.0..4.0).contains(&phase) {
+                gain * (1.0 - (phase * std::f64::consts::PI / 2.0).cos())
+            } else {
+                0.0
+            };
+            rec.time.push(t);
+            rec.channels[0].values.push(v);
+        }
+        rec
+    }
+
+    #[test]
+    fn test_alignment_recovers_delay() {
+        let a = recording(0.0, 1.0);
+        let b = recording(0.5, 1.0);
+
+        for alignment in [Alignment::MotionOnset, Alignment::CrossCorrelation] {
+            let params = CompareParams {
+                alignment,
+                ..Default::default()
+            };
+            let report = compare(&a, &b, &params).unwrap();
+            assert!((report.offset - 0.5).abs() < 0.02, "{:?}", report.offset);
+            assert!(report.channels[0].rms_diff < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_difference_statistics() {
+        let a = recording(0.0, 1.0);
+        let b = recording(0.0, 1.1);
+        let report = compare(&a, &b, &CompareParams::default()).unwrap();
+
+        let diff = &report.channels[0];
+        assert!((diff.max_diff - 0.2).abs() < 1e-3);
+        assert!(diff.time.len() <= 2000);
+        assert_eq!(report.duration_diff, 0.0);
+    }
+}
@@ -0,0 +1,468 @@
+// cycles.rs - 重复程序的周期分段与逐周期对比
+use crate::commands::arm_service::robot_data::RobotDataPacket;
+use serde::{Deserialize, Serialize};
+
+// 运动状态: 运动中
+const MOTION_STATE_MOVING: u8 = 1;
+// 两帧间隔超过该值时视为数据中断, 不计入能量积分 s
+const MAX_SAMPLE_GAP_S: f64 = 1.0;
+// 实时追踪时保留的叠加曲线数量
+const MAX_OVERLAYS: usize = 100;
+// 计算漂移时首尾各取的周期数
+const DRIFT_WINDOW: usize = 5;
+
+/// 周期分段方式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CycleTrigger {
+    /// 回到起始位姿 (各关节偏差均在容差内, rad) 时分段, 未指定位姿时取第一帧
+    ReturnToPose {
+        tolerance: f32,
+        #[serde(default)]
+        pose: Option<Vec<f32>>,
+    },
+    /// 运动状态由静止变为运动时分段
+    MotionOnset,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleParams {
+    pub trigger: CycleTrigger,
+    pub axis: usize, // 关节数
+    #[serde(default = "default_min_duration")]
+    pub min_duration: f64, // 最短周期 s, 小于该时长的分段视为抖动
+    #[serde(default = "default_overlay_points")]
+    pub overlay_points: usize, // 叠加曲线重采样点数
+}
+
+fn default_min_duration() -> f64 {
+    0.5
+}
+
+fn default_overlay_points() -> usize {
+    200
+}
+
+/// 单个周期的指标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleMetrics {
+    pub index: usize,
+    pub start: f64,               // 相对第一帧的开始时间 s
+    pub duration: f64,            // 周期时长 s
+    pub peak_current: f32,        // 峰值电流 A (所有关节)
+    pub peak_currents: Vec<f32>,  // 各关节峰值电流 A
+    pub peak_tracking_error: f32, // 峰值跟踪误差 rad (实际 - 规划关节位置)
+    pub energy: f64,              // 机械能 J (∑|τ·ω| 积分)
+    pub samples: usize,
+}
+
+/// 按相位对齐的周期曲线, 用于多周期叠加对比
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleOverlay {
+    pub index: usize,
+    pub phase: Vec<f32>,          // 0..1
+    pub time: Vec<f64>,           // 相对周期开始时间 s
+    pub positions: Vec<Vec<f32>>, // [点][关节] 实际关节位置 rad
+}
+
+/// 单项指标随周期的漂移
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricDrift {
+    pub slope: f64,      // 每周期变化量 (线性拟合)
+    pub first: f64,      // 前几个周期均值
+    pub last: f64,       // 后几个周期均值
+    pub change_pct: f64, // (last - first) / first * 100
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CycleDrift {
+    pub duration: MetricDrift,
+    pub peak_current: MetricDrift,
+    pub peak_tracking_error: MetricDrift,
+    pub energy: MetricDrift,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleReport {
+    pub cycles: Vec<CycleMetrics>,
+    pub overlays: Vec<CycleOverlay>,
+    pub drift: CycleDrift,
+}
+
+/// 当前周期的累计数据
+#[derive(Debug)]
+struct CycleAccumulator {
+    start: f64,
+    last_t: f64,
+    peak_currents: Vec<f32>,
+    peak_tracking_error: f32,
+    energy: f64,
+    times: Vec<f64>,
+    positions: Vec<Vec<f32>>,
+}
+
+impl CycleAccumulator {
+    fn new(start: f64, axis: usize) -> Self {
+        Self {
+            start,
+            last_t: start,
+            peak_currents: vec![0.0; axis],
+            peak_tracking_error: 0.0,
+            energy: 0.0,
+            times: vec![],
+            positions: vec![],
+        }
+    }
+
+    fn push(&mut self, t: f64, packet: &RobotDataPacket, axis: usize) {
+        let dt = t - self.last_t;
+        let mut power = 0.0f64;
+
+        for j in 0..axis {
+            let current = packet.actual_joint_currents[j].abs();
+            if current > self.peak_currents[j] {
+                self.peak_currents[j] = current;
+            }
+
+            let error = (packet.actual_joint_positions[j] - packet.target_joint_positions[j]).abs();
+            if error > self.peak_tracking_error {
+                self.peak_tracking_error = error;
+            }
+
+            power +=
+                (packet.estimated_joint_torque[j] * packet.actual_joint_velocities[j]).abs() as f64;
+        }
+
+        if dt > 0.0 && dt < MAX_SAMPLE_GAP_S {
+            self.energy += power * dt;
+        }
+
+        self.last_t = t;
+        self.times.push(t - self.start);
+        self.positions
+            .push(packet.actual_joint_positions[..axis].to_vec());
+    }
+
+    fn duration(&self, end: f64) -> f64 {
+        end - self.start
+    }
+}
+
+/// 周期检测器: 逐帧输入, 可用于录制文件或实时数据
+#[derive(Debug)]
+pub struct CycleDetector {
+    params: CycleParams,
+    origin: Option<i64>,
+    reference: Option<Vec<f32>>,
+    armed: bool,      // 已离开起始位姿
+    was_moving: bool, // 上一帧是否运动中
+    current: Option<CycleAccumulator>,
+    cycles: Vec<CycleMetrics>,
+    overlays: Vec<CycleOverlay>,
+}
+
+impl CycleDetector {
+    pub fn new(mut params: CycleParams) -> Self {
+        params.axis = params.axis.clamp(1, 7);
+        params.overlay_points = params.overlay_points.max(2);
+
+        let reference = match &params.trigger {
+            CycleTrigger::ReturnToPose { pose, .. } => pose.clone(),
+            CycleTrigger::MotionOnset => None,
+        };
+
+        Self {
+            params,
+            origin: None,
+            reference,
+            armed: false,
+            was_moving: false,
+            current: None,
+            cycles: vec![],
+            overlays: vec![],
+        }
+    }
+
+    /// 输入一帧数据, 如有周期结束则返回该周期指标
+    pub fn push(&mut self, packet: &RobotDataPacket) -> Option<CycleMetrics> {
+        let axis = self.params.axis;
+        let origin = *self.origin.get_or_insert(packet.timestamp);
+        // 控制器时间戳单位 μs
+        let t = (packet.timestamp - origin) as f64 / 1_000_000.0;
+
+        let boundary = match &self.params.trigger {
+            CycleTrigger::ReturnToPose { tolerance, .. } => {
+                let positions = &packet.actual_joint_positions[..axis];
+                let reference = self
+                    .reference
+                    .get_or_insert_with(|| positions.to_vec())
+                    .clone();
+
+                let distance = positions
+                    .iter()
+                    .zip(reference.iter())
+                    .map(|(a, b)| (a - b).abs())
+                    .fold(0.0f32, f32::max);
+
+                if distance > *tolerance {
+                    self.armed = true;
+                    false
+                } else if self.armed || self.current.is_none() {
+                    self.armed = false;
+                    true
+                } else {
+                    false
+                }
+            }
+            CycleTrigger::MotionOnset => {
+                let moving = packet.motion_state() == MOTION_STATE_MOVING;
+                let onset = moving && !self.was_moving;
+                self.was_moving = moving;
+                onset
+            }
+        };
+
+        let mut finished = None;
+        if boundary {
+            match self.current.take() {
+                // 时长不足视为抖动, 继续累计当前周期
+                Some(acc) if acc.duration(t) < self.params.min_duration => {
+                    self.current = Some(acc);
+                }
+                Some(acc) => {
+                    finished = Some(self.close(acc, t));
+                    self.current = Some(CycleAccumulator::new(t, axis));
+                }
+                None => {
+                    self.current = Some(CycleAccumulator::new(t, axis));
+                }
+            }
+        }
+
+        if let Some(acc) = self.current.as_mut() {
+            acc.push(t, packet, axis);
+        }
+
+        finished
+    }
+
+    /// 结束一个周期并生成指标与叠加曲线
+    fn close(&mut self, acc: CycleAccumulator, end: f64) -> CycleMetrics {
+        let duration = acc.duration(end);
+        let metrics = CycleMetrics {
+            index: self.cycles.len(),
+            start: acc.start,
+            duration,
+            peak_current: acc.peak_currents.iter().cloned().fold(0.0f32, f32::max),
+            peak_currents: acc.peak_currents.clone(),
+            peak_tracking_error: acc.peak_tracking_error,
+            energy: acc.energy,
+            samples: acc.times.len(),
+        };
+
+        let overlay = resample_overlay(
+            metrics.index,
+            &acc.times,
+            &acc.positions,
+            duration,
+            self.params.overlay_points,
+        );
+        self.overlays.push(overlay);
+        if self.overlays.len() > MAX_OVERLAYS {
+            self.overlays.remove(0);
+        }
+
+        self.cycles.push(metrics.clone());
+        metrics
+    }
+
+    /// 已完成周期的报告 (不含未结束的周期)
+    pub fn report(&self) -> CycleReport {
+        CycleReport {
+            cycles: self.cycles.clone(),
+            overlays: self.overlays.clone(),
+            drift: compute_drift(&self.cycles),
+        }
+    }
+}
+
+/// 对一段录制数据做周期分析
+pub fn analyze_packets(packets: &[RobotDataPacket], params: CycleParams) -> CycleReport {
+    let mut detector = CycleDetector::new(params);
+    for packet in packets {
+        detector.push(packet);
+    }
+    detector.report()
+}
+
+/// 将周期曲线按相位等间隔重采样 (线性插值)
+fn resample_overlay(
+    index: usize,
+    times: &[f64],
+    positions: &[Vec<f32>],
+    duration: f64,
+    points: usize,
+) -> CycleOverlay {
+    let mut overlay = CycleOverlay {
+        index,
+        phase: Vec::with_capacity(points),
+        time: Vec::with_capacity(points),
+        positions: Vec::with_capacity(points),
+    };
+
+    if times.is_empty() {
+        return overlay;
+    }
+
+    let mut cursor = 0;
+    for i in 0..points {
+        let phase = i as f64 / (points - 1) as f64;
+        let t = phase * duration;
+
+        while cursor + 1 < times.len() && times[cursor + 1] < t {
+            cursor += 1;
+        }
+
+        let value = if cursor + 1 < times.len() && times[cursor] < t {
+            let (t0, t1) = (times[cursor], times[cursor + 1]);
+            let ratio = ((t - t0) / (t1 - t0)).clamp(0.0, 1.0) as f32;
+            positions[cursor]
+                .iter()
+                .zip(positions[cursor + 1].iter())
+                .map(|(a, b)| a + (b - a) * ratio)
+                .collect()
+        } else {
+            positions[cursor].clone()
+        };
+
+        overlay.phase.push(phase as f32);
+        overlay.time.push(t);
+        overlay.positions.push(value);
+    }
+
+    overlay
+}
+
+fn compute_drift(cycles: &[CycleMetrics]) -> CycleDrift {
+    CycleDrift {
+        duration: metric_drift(cycles.iter().map(|c| c.duration).collect()),
+        peak_current: metric_drift(cycles.iter().map(|c| c.peak_current as f64).collect()),
+        peak_tracking_error: metric_drift(
+            cycles
+                .iter()
+                .map(|c| c.peak_tracking_error as f64)
+                .collect(),
+        ),
+        energy: metric_drift(cycles.iter().map(|c| c.energy).collect()),
+    }
+}
+
+fn metric_drift(values: Vec<f64>) -> MetricDrift {
+    let n = values.len();
+    if n == 0 {
+        return MetricDrift::default();
+    }
+
+    let window = DRIFT_WINDOW.min(n.div_ceil(2));
+    let first = values[..window].iter().sum::<f64>() / window as f64;
+    let last = values[n - window..].iter().sum::<f64>() / window as f64;
+
+    // 最小二乘斜率
+    let mean_x = (n - 1) as f64 / 2.0;
+    let mean_y = values.iter().sum::<f64>() / n as f64;
+    let (mut num, mut den) = (0.0, 0.0);
+    for (i, y) in values.iter().enumerate() {
+        let dx = i as f64 - mean_x;
+        num += dx * (y - mean_y);
+        den += dx * dx;
+    }
+    let slope = if den > 0.0 { num / den } else { 0.0 };
+
+    let change_pct = if first.abs() > f64::EPSILON {
+        (last - first) / first * 100.0
+    } else {
+        0.0
+    };
+
+    MetricDrift {
+        slope,
+        first,
+        last,
+        change_pct,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(timestamp_us: i64, j1: f32, state: u8) -> RobotDataPacket {
+        let mut p = RobotDataPacket::from_bytes(&[0u8; 784]).unwrap();
+        p.timestamp = timestamp_us;
+        p.motion_state_and_mode = state;
+        p.actual_joint_positions[0] = j1;
+        p.target_joint_positions[0] = j1 * 0.99;
+        p.actual_joint_currents[0] = j1.abs();
+        p
+    }
+
+    /// 生成 n 个周期, 每个周期 2s: 前1s从0运动到1rad, 后1s返回
+    fn cycles(n: usize, stretch: f64) -> Vec<RobotDataPacket> {
+        let mut packets = vec![];
+        let mut t = 0i64;
+        for c in 0..n {
+            let period_us = (2_000_000.0 * (1.0 + stretch * c as f64)) as i64;
+            let steps = 100;
+            for s in 0..steps {
+                let phase = s as f32 / steps as f32;
+                let j1 = if phase < 0.5 {
+                    phase * 2.0
+                } else {
+                    (1.0 - phase) * 2.0
+                };
+                let state = if s == 0 { 2 } else { 1 };
+                packets.push(packet(t + period_us * s / steps, j1, state));
+            }
+            t += period_us;
+        }
+        packets.push(packet(t, 0.0, 2));
+        packets
+    }
+
+    #[test]
+    fn test_return_to_pose_cycles() {
+        let params = CycleParams {
+            trigger: CycleTrigger::ReturnToPose {
+                tolerance: 0.01,
+                pose: None,
+            },
+            axis: 6,
+            min_duration: 0.5,
+            overlay_points: 50,
+        };
+        let report = analyze_packets(&cycles(4, 0.0), params);
+
+        assert_eq!(report.cycles.len(), 4);
+        for c in &report.cycles {
+            assert!((c.duration - 2.0).abs() < 0.05, "duration {}", c.duration);
+            assert!((c.peak_current - 1.0).abs() < 0.05);
+        }
+        assert_eq!(report.overlays[0].positions.len(), 50);
+    }
+
+    #[test]
+    fn test_motion_onset_and_drift() {
+        let params = CycleParams {
+            trigger: CycleTrigger::MotionOnset,
+            axis: 6,
+            min_duration: 0.5,
+            overlay_points: 20,
+        };
+        let report = analyze_packets(&cycles(6, 0.1), params);
+
+        // 最后一个周期没有下一次起动, 不计入
+        assert_eq!(report.cycles.len(), 5);
+        assert!(report.drift.duration.slope > 0.15);
+        assert!(report.drift.duration.change_pct > 0.0);
+    }
+}
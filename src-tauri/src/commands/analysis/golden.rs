@@ -0,0 +1,503 @@
+// golden.rs - 标准录制 (golden run) 回归检查
+use crate::{
+    commands::analysis::{
+        compare::{alignment_offset, overlap_diff_series, Alignment, CompareParams},
+        recording::Recording,
+    },
+    utils::i18n::{self, Language},
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{self},
+    path::{Path, PathBuf},
+};
+
+/// 检查通过
+pub const EXIT_PASS: i32 = 0;
+/// 检查未通过
+pub const EXIT_FAIL: i32 = 1;
+/// 录制或检查过程出错
+pub const EXIT_ERROR: i32 = 2;
+
+/// 通道容差带
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToleranceBand {
+    pub channel: String, // 通道名, 以 * 结尾时按前缀匹配 (如 actual_joint_positions_*)
+    pub tolerance: f64,  // 允许的最大绝对差
+}
+
+/// 标准录制配置, 与录制文件一起保存在 golden 目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenRun {
+    pub program: String,
+    pub created: String,
+    pub recording: String, // 录制文件名 (相对配置文件所在目录)
+    pub alignment: Alignment,
+    pub tolerances: Vec<ToleranceBand>,
+    #[serde(default)]
+    pub duration_tolerance: Option<f64>, // 允许的时长差 s
+    #[serde(default)]
+    pub min_violation: f64, // 短于该时长的超差忽略 s
+}
+
+/// 超差时间段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeWindow {
+    pub start: f64,
+    pub end: f64,
+    pub max_diff: f64,
+}
+
+/// 单通道超差
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Violation {
+    pub channel: String,
+    pub tolerance: f64,
+    pub max_diff: f64,
+    pub windows: Vec<TimeWindow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenReport {
+    pub program: String,
+    pub passed: bool,
+    pub offset: f64,
+    pub duration_diff: f64,
+    pub duration_ok: bool,
+    pub checked_channels: usize,
+    pub missing_channels: Vec<String>, // 有容差但新录制中缺失或对齐后没有重叠的通道
+    pub violations: Vec<Violation>,
+    #[serde(default)]
+    pub summary: String, // 界面语言的检查结论
+}
+
+impl GoldenReport {
+    pub fn exit_code(&self) -> i32 {
+        if self.passed {
+            EXIT_PASS
+        } else {
+            EXIT_FAIL
+        }
+    }
+
+    /// 指定语言的检查结论
+    pub fn summary_in(&self, language: Language) -> String {
+        let mut args = vec![
+            ("program", self.program.clone()),
+            ("offset", format!("{:.3}", self.offset)),
+            ("duration_diff", format!("{:.3}", self.duration_diff)),
+        ];
+        if self.passed {
+            args.push(("checked", self.checked_channels.to_string()));
+            i18n::t_args_in(language, "golden.passed", &args)
+        } else {
+            args.push(("violations", self.violations.len().to_string()));
+            args.push(("missing", self.missing_channels.len().to_string()));
+            i18n::t_args_in(language, "golden.failed", &args)
+        }
+    }
+}
+
+impl ToleranceBand {
+    fn matches(&self, channel: &str) -> bool {
+        match self.channel.strip_suffix('*') {
+            Some(prefix) => channel.starts_with(prefix),
+            None => channel == self.channel,
+        }
+    }
+}
+
+impl GoldenRun {
+    /// 通道的容差, 精确匹配优先于前缀匹配
+    pub fn tolerance_for(&self, channel: &str) -> Option<f64> {
+        self.tolerances
+            .iter()
+            .find(|band| band.channel == channel)
+            .or_else(|| self.tolerances.iter().find(|band| band.matches(channel)))
+            .map(|band| band.tolerance)
+    }
+
+    /// 读取配置文件
+    pub fn load(config_path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(config_path)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// 录制文件的完整路径; 录制只能是配置文件所在目录下的文件名
+    pub fn recording_path(&self, config_path: &Path) -> io::Result<PathBuf> {
+        let recording = Path::new(&self.recording);
+        if self.recording.contains(['/', '\\'])
+            || recording.file_name() != Some(recording.as_os_str())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid recording file name: {}", self.recording),
+            ));
+        }
+
+        Ok(config_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(recording))
+    }
+
+    /// 读取配置与录制后, 对新录制进行检查
+    ///
+    /// 新录制存在与标准录制相同格式的同名文件 (如 .raw) 时优先使用, 保证通道与单位一致
+    pub fn check_file(config_path: &Path, run_path: &Path) -> Result<GoldenReport, String> {
+        let golden = Self::load(config_path).map_err(|e| {
+            format!(
+                "Failed to load golden run {}: {:?}",
+                config_path.display(),
+                e
+            )
+        })?;
+        let golden_path = golden
+            .recording_path(config_path)
+            .map_err(|e| format!("Invalid golden run {}: {}", config_path.display(), e))?;
+
+        let same_format = golden_path
+            .extension()
+            .map(|ext| run_path.with_extension(ext))
+            .filter(|path| path.exists());
+        let run_path = same_format.as_deref().unwrap_or(run_path);
+
+        let reference = Recording::load(&golden_path).map_err(|e| {
+            format!(
+                "Failed to load golden recording {}: {:?}",
+                golden_path.display(),
+                e
+            )
+        })?;
+        let run = Recording::load(run_path)
+            .map_err(|e| format!("Failed to load recording {}: {:?}", run_path.display(), e))?;
+
+        evaluate(&golden, &reference, &run)
+    }
+}
+
+/// 检查程序名; 程序名用作文件名, 不能为空或包含路径分隔符与 '.'
+pub fn validate_program(program: &str) -> io::Result<()> {
+    if program.is_empty() || program.contains(['/', '\\', '.']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid program name: {}", program),
+        ));
+    }
+    Ok(())
+}
+
+/// 将录制保存为程序的标准录制, 返回配置文件路径
+///
+/// 目录结构: {golden_dir}/{program}.json + {program}.{raw|csv}
+pub fn save_golden_run(
+    golden_dir: &Path,
+    program: &str,
+    source: &Path,
+    alignment: Alignment,
+    tolerances: Vec<ToleranceBand>,
+    duration_tolerance: Option<f64>,
+) -> io::Result<PathBuf> {
+    validate_program(program)?;
+
+    std::fs::create_dir_all(golden_dir)?;
+
+    let extension = source.extension().and_then(|e| e.to_str()).unwrap_or("csv");
+    let recording = format!("{}.{}", program, extension);
+    std::fs::copy(source, golden_dir.join(&recording))?;
+
+    let golden = GoldenRun {
+        program: program.to_string(),
+        created: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        recording,
+        alignment,
+        tolerances,
+        duration_tolerance,
+        min_violation: 0.0,
+    };
+
+    let config_path = golden_dir.join(format!("{}.json", program));
+    let content = serde_json::to_string_pretty(&golden)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    std::fs::write(&config_path, content)?;
+
+    Ok(config_path)
+}
+
+/// 列出目录下所有标准录制
+pub fn list_golden_runs(golden_dir: &Path) -> io::Result<Vec<GoldenRun>> {
+    let mut runs = vec![];
+    if !golden_dir.exists() {
+        return Ok(runs);
+    }
+
+    for entry in std::fs::read_dir(golden_dir)?.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) == Some("json") {
+            match GoldenRun::load(&path) {
+                Ok(run) => runs.push(run),
+                Err(e) => eprintln!("Failed to load golden run {}: {:?}", path.display(), e),
+            }
+        }
+    }
+    runs.sort_by(|a, b| a.program.cmp(&b.program));
+
+    Ok(runs)
+}
+
+/// 删除标准录制 (配置与录制文件)
+pub fn delete_golden_run(golden_dir: &Path, program: &str) -> io::Result<()> {
+    validate_program(program)?;
+    let config_path = golden_dir.join(format!("{}.json", program));
+    let golden = GoldenRun::load(&config_path)?;
+
+    let recording_path = golden.recording_path(&config_path)?;
+    if recording_path.exists() {
+        std::fs::remove_file(recording_path)?;
+    }
+    std::fs::remove_file(config_path)
+}
+
+/// 用容差带检查新录制
+pub fn evaluate(
+    golden: &GoldenRun,
+    reference: &Recording,
+    run: &Recording,
+) -> Result<GoldenReport, String> {
+    if reference.time.is_empty() || run.time.is_empty() {
+        return Err("Recording is empty".to_string());
+    }
+
+    let checked: Vec<(String, f64)> = reference
+        .channels
+        .iter()
+        .filter_map(|c| Some((c.name.clone(), golden.tolerance_for(&c.name)?)))
+        .collect();
+
+    if checked.is_empty() {
+        return Err("No channel in the golden recording matches a tolerance band".to_string());
+    }
+
+    let (present, mut missing): (Vec<_>, Vec<_>) = checked
+        .into_iter()
+        .partition(|(name, _)| run.channel(name).is_some());
+
+    let names: Vec<String> = present.iter().map(|(name, _)| name.clone()).collect();
+    let params = CompareParams {
+        alignment: golden.alignment,
+        ..Default::default()
+    };
+    let offset = if names.is_empty() {
+        0.0
+    } else {
+        alignment_offset(reference, run, &names, &params)?
+    };
+
+    let mut violations = vec![];
+    let mut checked_channels = 0;
+    for (name, tolerance) in present {
+        // 对齐后与标准录制没有重叠的通道按缺失处理
+        let Some(points) = overlap_diff_series(reference, run, &name, offset) else {
+            missing.push((name, tolerance));
+            continue;
+        };
+        checked_channels += 1;
+        let windows = exceed_windows(&points, tolerance, golden.min_violation);
+        if !windows.is_empty() {
+            violations.push(Violation {
+                channel: name,
+                tolerance,
+                max_diff: windows.iter().map(|w| w.max_diff).fold(0.0, f64::max),
+                windows,
+            });
+        }
+    }
+
+    let duration_diff = run.duration() - reference.duration();
+    let duration_ok = golden
+        .duration_tolerance
+        .is_none_or(|tolerance| duration_diff.abs() <= tolerance);
+
+    let mut report = GoldenReport {
+        program: golden.program.clone(),
+        passed: violations.is_empty() && missing.is_empty() && duration_ok,
+        offset,
+        duration_diff,
+        duration_ok,
+        checked_channels,
+        missing_channels: missing.into_iter().map(|(name, _)| name).collect(),
+        violations,
+        summary: String::new(),
+    };
+    report.summary = report.summary_in(i18n::language());
+    Ok(report)
+}
+
+/// 找出差值超出容差的连续时间段, 缺失数据 (非有限值) 也视为超差, 不计入最大差值
+fn exceed_windows(points: &[(f64, f64)], tolerance: f64, min_duration: f64) -> Vec<TimeWindow> {
+    let mut windows = vec![];
+    let mut current: Option<TimeWindow> = None;
+
+    for &(t, d) in points {
+        let abs = d.abs();
+        if !d.is_finite() || abs > tolerance {
+            let window = current.get_or_insert(TimeWindow {
+                start: t,
+                end: t,
+                max_diff: 0.0,
+            });
+            window.end = t;
+            if abs.is_finite() {
+                window.max_diff = window.max_diff.max(abs);
+            }
+        } else if let Some(window) = current.take() {
+            windows.push(window);
+        }
+    }
+    windows.extend(current);
+
+    windows
+        .into_iter()
+        .filter(|w| w.end - w.start >= min_duration)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::analysis::recording::Series;
+
+    fn recording(bump: f64) -> Recording {
+        let mut rec = Recording {
+            time: vec![],
+            channels: vec![
+                Series {
+                    name: "actual_joint_positions_1".to_string(),
+                    values: vec![],
+                },
+                Series {
+                    name: "actual_joint_currents_1".to_string(),
+                    values: vec![],
+                },
+            ],
+        };
+        for i in 0..1000 {
+            let t = i as f64 * 0.01;
+            rec.time.push(t);
+            rec.channels[0].values.push((t * 0.5).sin());
+            // 4~5s 之间叠加一个电流尖峰
+            let extra = if (4.0..5.0).contains(&t) { bump } else { 0.0 };
+            rec.channels[1].values.push(1.0 + extra);
+        }
+        rec
+    }
+
+    fn golden() -> GoldenRun {
+        GoldenRun {
+            program: "pick_place".to_string(),
+            created: String::new(),
+            recording: "pick_place.raw".to_string(),
+            alignment: Alignment::Start,
+            tolerances: vec![
+                ToleranceBand {
+                    channel: "actual_joint_*".to_string(),
+                    tolerance: 0.1,
+                },
+                ToleranceBand {
+                    channel: "actual_joint_currents_1".to_string(),
+                    tolerance: 0.5,
+                },
+            ],
+            duration_tolerance: Some(0.5),
+            min_violation: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_pass_within_tolerance() {
+        let report = evaluate(&golden(), &recording(0.0), &recording(0.3)).unwrap();
+        assert!(report.passed);
+        assert_eq!(report.checked_channels, 2);
+        assert_eq!(report.exit_code(), EXIT_PASS);
+    }
+
+    #[test]
+    fn test_fail_reports_channel_and_window() {
+        let report = evaluate(&golden(), &recording(0.0), &recording(0.8)).unwrap();
+        assert!(!report.passed);
+        assert_eq!(report.exit_code(), EXIT_FAIL);
+        assert_eq!(report.violations.len(), 1);
+        assert!(report.summary_in(Language::ZhCn).contains("1 个通道超差"));
+        assert!(report
+            .summary_in(Language::En)
+            .starts_with("pick_place failed: 1 channels out of tolerance"));
+
+        let violation = &report.violations[0];
+        assert_eq!(violation.channel, "actual_joint_currents_1");
+        assert_eq!(violation.windows.len(), 1);
+        assert!((violation.windows[0].start - 4.0).abs() < 0.02);
+        assert!((violation.windows[0].end - 5.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_nothing_checked_does_not_pass() {
+        // 没有通道匹配容差带
+        let run = GoldenRun {
+            tolerances: vec![],
+            ..golden()
+        };
+        assert!(evaluate(&run, &recording(0.0), &recording(0.0)).is_err());
+
+        // 新录制与标准录制没有重叠
+        let mut late = recording(0.0);
+        late.time.iter_mut().for_each(|t| *t += 100.0);
+        let run = GoldenRun {
+            duration_tolerance: None,
+            ..golden()
+        };
+        let report = evaluate(&run, &recording(0.0), &late).unwrap();
+        assert!(!report.passed);
+        assert_eq!(report.checked_channels, 0);
+        assert_eq!(report.missing_channels.len(), 2);
+    }
+
+    #[test]
+    fn test_missing_cells_are_violations() {
+        let mut run = recording(0.0);
+        run.channels[0].values[500..520].fill(f64::NAN);
+
+        let report = evaluate(&golden(), &recording(0.0), &run).unwrap();
+        assert!(!report.passed);
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(report.violations[0].channel, "actual_joint_positions_1");
+        assert!((report.violations[0].windows[0].start - 5.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_reject_paths_outside_golden_dir() {
+        assert!(validate_program("pick_place").is_ok());
+        for program in ["", "../pick_place", "a/b", "a\\b", "pick.place"] {
+            assert!(validate_program(program).is_err(), "{}", program);
+        }
+
+        let config_path = Path::new("golden/pick_place.json");
+        assert_eq!(
+            golden().recording_path(config_path).unwrap(),
+            Path::new("golden/pick_place.raw")
+        );
+        for recording in [
+            "../secret.raw",
+            "/etc/passwd",
+            "sub/pick_place.raw",
+            "..",
+            "",
+        ] {
+            let run = GoldenRun {
+                recording: recording.to_string(),
+                ..golden()
+            };
+            assert!(run.recording_path(config_path).is_err(), "{}", recording);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+// imported.rs - 导入外部 CSV 为内存中的录制, 供图表/统计/对比使用
+use crate::commands::{
+    analysis::recording::{Recording, Series},
+    arm_service::structs::{ChartData, ObserveType},
+};
+use chrono::Local;
+use serde::Serialize;
+use std::{
+    io::{self},
+    path::Path,
+};
+
+/// 对比等命令中以 "imported:{id}" 代替文件路径引用导入的录制
+pub const IMPORTED_PREFIX: &str = "imported:";
+
+/// 图表数据默认最大点数
+pub const DEFAULT_MAX_POINTS: usize = 5000;
+
+/// 由表头推断的通道信息, 表头为 {类型}_{序号} 时可识别观测类型
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedChannel {
+    pub name: String,
+    pub observe_type: Option<ObserveType>,
+    pub index: Option<usize>, // 从 1 开始
+}
+
+/// 导入的录制 (数据只保存在内存中)
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedRecording {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub imported_at: String,
+    pub rows: usize,
+    pub duration: f64, // s
+    pub channels: Vec<ImportedChannel>,
+    #[serde(skip)]
+    pub recording: Recording,
+}
+
+/// 降采样后的通道数据
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedSeries {
+    pub time: Vec<f64>,
+    pub channels: Vec<Series>,
+}
+
+/// 与实时数据 (ROBOT_TCP_DATA) 相同结构的一帧, 只包含可识别观测类型的通道
+#[derive(Debug, Serialize)]
+pub struct ImportedFrame {
+    pub time: f64,
+    pub data: Vec<ChartData>,
+}
+
+/// 通道统计 (忽略缺失值)
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelStats {
+    pub name: String,
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std: f64,
+    pub rms: f64,
+}
+
+/// 通道名拆分为观测类型与序号
+fn infer_channel(name: &str) -> ImportedChannel {
+    let (observe_type, index) = match name.rsplit_once('_') {
+        Some((prefix, index)) => match (ObserveType::from_name(prefix), index.parse().ok()) {
+            (Some(ot), Some(index)) => (Some(ot), Some(index)),
+            _ => (None, None),
+        },
+        None => (None, None),
+    };
+
+    ImportedChannel {
+        name: name.to_string(),
+        observe_type,
+        index,
+    }
+}
+
+/// 降采样步长
+fn stride(rows: usize, max_points: usize) -> usize {
+    rows.div_ceil(max_points.max(1)).max(1)
+}
+
+impl ImportedRecording {
+    /// 解析 CsvExporter 导出的文件或带表头的通用CSV
+    pub fn import(id: String, path: &Path) -> io::Result<Self> {
+        let recording = Recording::from_csv(path)?;
+        if recording.time.is_empty() || recording.channels.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "No numeric data found in CSV",
+            ));
+        }
+
+        Ok(Self {
+            id,
+            name: path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string(),
+            path: path.display().to_string(),
+            imported_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            rows: recording.time.len(),
+            duration: recording.duration(),
+            channels: recording
+                .channels
+                .iter()
+                .map(|c| infer_channel(&c.name))
+                .collect(),
+            recording,
+        })
+    }
+
+    /// 指定通道的降采样数据, `channels` 为空时返回全部通道
+    pub fn series(&self, channels: &[String], max_points: usize) -> ImportedSeries {
+        let step = stride(self.rows, max_points);
+        ImportedSeries {
+            time: self.recording.time.iter().step_by(step).copied().collect(),
+            channels: self
+                .recording
+                .channels
+                .iter()
+                .filter(|c| channels.is_empty() || channels.contains(&c.name))
+                .map(|c| Series {
+                    name: c.name.clone(),
+                    values: c.values.iter().step_by(step).copied().collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// 按观测类型组帧的降采样数据, 可直接用于实时图表
+    pub fn frames(&self, max_points: usize) -> Vec<ImportedFrame> {
+        // (观测类型, 该类型各分量的通道索引)
+        let mut groups: Vec<(ObserveType, Vec<usize>)> = vec![];
+        for (i, channel) in self.channels.iter().enumerate() {
+            let Some(ot) = channel.observe_type else {
+                continue;
+            };
+            match groups.iter_mut().find(|(t, _)| *t == ot) {
+                Some((_, columns)) => columns.push(i),
+                None => groups.push((ot, vec![i])),
+            }
+        }
+
+        let step = stride(self.rows, max_points);
+        (0..self.rows)
+            .step_by(step)
+            .map(|row| ImportedFrame {
+                time: self.recording.time[row],
+                data: groups
+                    .iter()
+                    .map(|(ot, columns)| ChartData {
+                        data_type: *ot,
+                        value: columns
+                            .iter()
+                            .map(|c| self.recording.channels[*c].values[row] as f32)
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// 各通道统计
+    pub fn statistics(&self) -> Vec<ChannelStats> {
+        self.recording
+            .channels
+            .iter()
+            .map(|c| channel_stats(&c.name, &c.values))
+            .collect()
+    }
+}
+
+pub fn channel_stats(name: &str, values: &[f64]) -> ChannelStats {
+    let valid: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let count = valid.len();
+    if count == 0 {
+        return ChannelStats {
+            name: name.to_string(),
+            count,
+            min: f64::NAN,
+            max: f64::NAN,
+            mean: f64::NAN,
+            std: f64::NAN,
+            rms: f64::NAN,
+        };
+    }
+
+    let n = count as f64;
+    let mean = valid.iter().sum::<f64>() / n;
+    let variance = valid.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    ChannelStats {
+        name: name.to_string(),
+        count,
+        min: valid.iter().copied().fold(f64::INFINITY, f64::min),
+        max: valid.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        mean,
+        std: variance.sqrt(),
+        rms: (valid.iter().map(|v| v * v).sum::<f64>() / n).sqrt(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::arm_service::csv_exporter::copy_localized;
+
+    #[test]
+    fn test_import_exporter_and_generic_csv() {
+        let dir = std::env::temp_dir().join(format!(
+            "import_test_{}",
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // CsvExporter 格式: 毫秒时间戳 + {类型}_{序号}
+        let path = dir.join("robot_data.csv");
+        std::fs::write(
+            &path,
+            "timestamp,actual_joint_positions_1,actual_joint_positions_2\n\
+             1736222400000,1,2\n1736222400500,3,4\n1736222401000,5,6\n",
+        )
+        .unwrap();
+        let imported = ImportedRecording::import("1".to_string(), &path).unwrap();
+        assert_eq!(imported.rows, 3);
+        assert_eq!(imported.duration, 1.0);
+        assert_eq!(
+            imported.channels[1].observe_type,
+            Some(ObserveType::ActualJointPositions)
+        );
+        assert_eq!(imported.channels[1].index, Some(2));
+
+        let frames = imported.frames(2);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].time, 1.0);
+        assert_eq!(frames[1].data[0].value, vec![5.0, 6.0]);
+
+        let stats = imported.statistics();
+        assert_eq!(stats[0].mean, 3.0);
+        assert_eq!(stats[0].max, 5.0);
+
+        // 通用CSV: 无时间列, 非数值按缺失值处理
+        let path = dir.join("generic.csv");
+        std::fs::write(&path, "force,speed\n1.5,2\n2.5,n/a\n").unwrap();
+        let imported = ImportedRecording::import("2".to_string(), &path).unwrap();
+        assert_eq!(imported.recording.time, vec![0.0, 1.0]);
+        assert!(imported.channels[0].observe_type.is_none());
+        assert_eq!(imported.statistics()[1].count, 1);
+
+        // 导出的CSV表头为界面语言的通道名称, 导入时还原为通道名
+        let exported = dir.join("exported.csv");
+        copy_localized(&dir.join("robot_data.csv"), &exported).unwrap();
+        let imported = ImportedRecording::import("3".to_string(), &exported).unwrap();
+        assert_eq!(imported.duration, 1.0);
+        assert_eq!(imported.channels[1].name, "actual_joint_positions_2");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,331 @@
+pub mod compare;
+pub mod cycles;
+pub mod golden;
+pub mod imported;
+pub mod recording;
+
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+use crate::{
+    commands::{
+        analysis::{
+            compare::{compare, Alignment, CompareParams, CompareReport},
+            cycles::{analyze_packets, CycleDetector, CycleParams, CycleReport},
+            golden::{self, GoldenReport, GoldenRun, ToleranceBand},
+            imported::{
+                ChannelStats, ImportedFrame, ImportedRecording, ImportedSeries, DEFAULT_MAX_POINTS,
+                IMPORTED_PREFIX,
+            },
+            recording::Recording,
+        },
+        arm_service::csv_exporter::read_raw_packets,
+        sessions::library::unique_id,
+    },
+    state::app_state::AppState,
+    utils::{error::AppError, response::Response},
+};
+
+/// 对已保存的录制做周期分析 (读取与 CSV 同名的 .raw 原始数据文件)
+#[tauri::command(async)]
+pub async fn analyze_recording_cycles(
+    path: &str,
+    params: CycleParams,
+) -> Result<Response<CycleReport>, Response<String>> {
+    let raw_path = PathBuf::from(path).with_extension("raw");
+
+    let packets = match read_raw_packets(&raw_path) {
+        Ok(packets) => packets,
+        Err(e) => {
+            return Ok(AppError::Io {
+                path: raw_path.display().to_string(),
+                reason: e.to_string(),
+            }
+            .into())
+        }
+    };
+
+    Ok(Response::success(analyze_packets(&packets, params)))
+}
+
+/// 读取录制文件, "imported:{id}" 为已导入的录制
+fn load_recording(state: &AppState, path: &str) -> Result<Recording, AppError> {
+    match path.strip_prefix(IMPORTED_PREFIX) {
+        Some(id) => state
+            .imported_recordings
+            .lock()
+            .map_err(|_| AppError::LockPoisoned("imported_recordings"))?
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| r.recording.clone())
+            .ok_or_else(|| AppError::ImportedNotFound(id.to_string())),
+        None => Recording::load(Path::new(path)).map_err(|e| AppError::Io {
+            path: path.to_string(),
+            reason: e.to_string(),
+        }),
+    }
+}
+
+/// 对比两次录制 (CSV, .raw 或已导入的录制), 返回逐通道差值 (B - A) 与统计
+#[tauri::command(async)]
+pub async fn compare_recordings(
+    state: tauri::State<'_, AppState>,
+    path_a: &str,
+    path_b: &str,
+    params: CompareParams,
+) -> Result<Response<CompareReport>, Response<String>> {
+    let load = |path: &str| load_recording(&state, path);
+
+    let result = load(path_a).and_then(|a| {
+        let b = load(path_b)?;
+        compare(&a, &b, &params).map_err(AppError::from)
+    });
+
+    Ok(result.into())
+}
+
+/// 将录制保存为程序的标准录制
+#[tauri::command]
+pub fn save_golden_run(
+    state: tauri::State<AppState>,
+    program: &str,
+    path: &str,
+    alignment: Alignment,
+    tolerances: Vec<ToleranceBand>,
+    duration_tolerance: Option<f64>,
+) -> Response<String> {
+    // 优先保存原始数据 (包含全部通道)
+    let source = PathBuf::from(path);
+    let raw_path = source.with_extension("raw");
+    let source = if raw_path.exists() { raw_path } else { source };
+
+    golden::save_golden_run(
+        &state.user_data_paths.golden,
+        program,
+        &source,
+        alignment,
+        tolerances,
+        duration_tolerance,
+    )
+    .map(|config_path| config_path.display().to_string())
+    .map_err(|e| format!("Failed to save golden run: {:?}", e))
+    .into()
+}
+
+/// 列出所有标准录制
+#[tauri::command]
+pub fn list_golden_runs(state: tauri::State<AppState>) -> Response<Vec<GoldenRun>> {
+    golden::list_golden_runs(&state.user_data_paths.golden)
+        .map_err(|e| format!("Failed to list golden runs: {:?}", e))
+        .into()
+}
+
+/// 删除标准录制
+#[tauri::command]
+pub fn delete_golden_run(state: tauri::State<AppState>, program: &str) -> Response<String> {
+    golden::delete_golden_run(&state.user_data_paths.golden, program)
+        .map(|_| "Golden run deleted".to_string())
+        .map_err(|e| format!("Failed to delete golden run: {:?}", e))
+        .into()
+}
+
+/// 用标准录制检查新录制, 返回通过/未通过报告
+#[tauri::command(async)]
+pub async fn evaluate_golden_run(
+    state: tauri::State<'_, AppState>,
+    program: &str,
+    path: &str,
+) -> Result<Response<GoldenReport>, Response<String>> {
+    if let Err(e) = golden::validate_program(program) {
+        return Ok(AppError::GoldenEvaluate(e.to_string()).into());
+    }
+    let config_path = state
+        .user_data_paths
+        .golden
+        .join(format!("{}.json", program));
+
+    Ok(GoldenRun::check_file(&config_path, Path::new(path))
+        .map_err(AppError::GoldenEvaluate)
+        .into())
+}
+
+/// 开始实时周期追踪, 每完成一个周期推送 ROBOT_CYCLE 事件
+#[tauri::command]
+pub fn start_cycle_tracking(
+    state: tauri::State<AppState>,
+    params: CycleParams,
+    robot_id: Option<String>,
+) -> Response<String> {
+    let robot = match state.robot(robot_id.as_deref()) {
+        Ok(robot) => robot,
+        Err(e) => return e.into(),
+    };
+    let cycle_detector_arc = match robot.robot_server.read() {
+        Ok(lock) => lock.cycle_detector.clone(),
+        Err(_) => return AppError::LockPoisoned("robot_server").into(),
+    };
+
+    match cycle_detector_arc.write() {
+        Ok(mut detector) => *detector = Some(CycleDetector::new(params)),
+        Err(_) => return AppError::LockPoisoned("cycle_detector").into(),
+    }
+
+    Response::success("Cycle tracking started".to_string())
+}
+
+/// 停止实时周期追踪并返回最终报告
+#[tauri::command]
+pub fn stop_cycle_tracking(
+    state: tauri::State<AppState>,
+    robot_id: Option<String>,
+) -> Response<CycleReport> {
+    let robot = match state.robot(robot_id.as_deref()) {
+        Ok(robot) => robot,
+        Err(e) => return e.into(),
+    };
+    let cycle_detector_arc = match robot.robot_server.read() {
+        Ok(lock) => lock.cycle_detector.clone(),
+        Err(_) => return AppError::LockPoisoned("robot_server").into(),
+    };
+
+    let detector = match cycle_detector_arc.write() {
+        Ok(mut detector) => detector.take(),
+        Err(_) => return AppError::LockPoisoned("cycle_detector").into(),
+    };
+
+    match detector {
+        Some(detector) => Response::success(detector.report()),
+        None => AppError::CycleTrackingNotRunning.into(),
+    }
+}
+
+/// 获取实时周期追踪的当前报告
+#[tauri::command]
+pub fn get_cycle_report(
+    state: tauri::State<AppState>,
+    robot_id: Option<String>,
+) -> Response<CycleReport> {
+    let robot = match state.robot(robot_id.as_deref()) {
+        Ok(robot) => robot,
+        Err(e) => return e.into(),
+    };
+    let cycle_detector_arc = match robot.robot_server.read() {
+        Ok(lock) => lock.cycle_detector.clone(),
+        Err(_) => return AppError::LockPoisoned("robot_server").into(),
+    };
+
+    let report = match cycle_detector_arc.read() {
+        Ok(detector) => detector.as_ref().map(|d| d.report()),
+        Err(_) => return AppError::LockPoisoned("cycle_detector").into(),
+    };
+
+    match report {
+        Some(report) => Response::success(report),
+        None => AppError::CycleTrackingNotRunning.into(),
+    }
+}
+
+/// 导入 CSV (CsvExporter 导出的文件或带表头的通用CSV) 为内存中的录制
+#[tauri::command(async)]
+pub async fn import_csv(
+    state: tauri::State<'_, AppState>,
+    path: &str,
+) -> Result<Response<ImportedRecording>, Response<String>> {
+    let id = Local::now().format("%Y%m%d%H%M%S%3f").to_string();
+    let mut imported = match ImportedRecording::import(id, Path::new(path)) {
+        Ok(imported) => imported,
+        Err(e) => {
+            return Ok(AppError::ImportFailed {
+                path: path.to_string(),
+                reason: e.to_string(),
+            }
+            .into())
+        }
+    };
+
+    match state.imported_recordings.lock() {
+        Ok(mut recordings) => {
+            // 同一毫秒导入多个文件时追加序号
+            imported.id = unique_id(&imported.id, |id| recordings.iter().any(|r| r.id == id));
+            recordings.push(imported.clone());
+            Ok(Response::success(imported))
+        }
+        Err(_) => Ok(AppError::LockPoisoned("imported_recordings").into()),
+    }
+}
+
+#[tauri::command]
+pub fn list_imported_recordings(state: tauri::State<AppState>) -> Response<Vec<ImportedRecording>> {
+    match state.imported_recordings.lock() {
+        Ok(recordings) => Response::success(recordings.clone()),
+        Err(_) => AppError::LockPoisoned("imported_recordings").into(),
+    }
+}
+
+#[tauri::command]
+pub fn remove_imported_recording(state: tauri::State<AppState>, id: &str) -> Response<String> {
+    match state.imported_recordings.lock() {
+        Ok(mut recordings) => {
+            let before = recordings.len();
+            recordings.retain(|r| r.id != id);
+            if recordings.len() == before {
+                return AppError::ImportedNotFound(id.to_string()).into();
+            }
+            Response::success("Imported recording removed".to_string())
+        }
+        Err(_) => AppError::LockPoisoned("imported_recordings").into(),
+    }
+}
+
+/// 在导入的录制上执行操作
+fn with_imported<T>(
+    state: &AppState,
+    id: &str,
+    f: impl FnOnce(&ImportedRecording) -> T,
+) -> Response<T> {
+    match state.imported_recordings.lock() {
+        Ok(recordings) => match recordings.iter().find(|r| r.id == id) {
+            Some(recording) => Response::success(f(recording)),
+            None => AppError::ImportedNotFound(id.to_string()).into(),
+        },
+        Err(_) => AppError::LockPoisoned("imported_recordings").into(),
+    }
+}
+
+/// 导入录制的通道数据 (降采样), `channels` 为空时返回全部通道
+#[tauri::command]
+pub fn get_imported_series(
+    state: tauri::State<AppState>,
+    id: &str,
+    channels: Option<Vec<String>>,
+    max_points: Option<usize>,
+) -> Response<ImportedSeries> {
+    with_imported(&state, id, |r| {
+        r.series(
+            &channels.unwrap_or_default(),
+            max_points.unwrap_or(DEFAULT_MAX_POINTS),
+        )
+    })
+}
+
+/// 导入录制按实时数据格式组帧 (降采样), 供实时图表回放
+#[tauri::command]
+pub fn get_imported_frames(
+    state: tauri::State<AppState>,
+    id: &str,
+    max_points: Option<usize>,
+) -> Response<Vec<ImportedFrame>> {
+    with_imported(&state, id, |r| {
+        r.frames(max_points.unwrap_or(DEFAULT_MAX_POINTS))
+    })
+}
+
+/// 导入录制的各通道统计
+#[tauri::command]
+pub fn get_imported_statistics(
+    state: tauri::State<AppState>,
+    id: &str,
+) -> Response<Vec<ChannelStats>> {
+    with_imported(&state, id, |r| r.statistics())
+}
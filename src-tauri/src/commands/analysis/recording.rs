@@ -0,0 +1,314 @@
+// recording.rs - 读取本程序保存的录制文件 (CSV / 原始数据)
+use crate::{
+    commands::arm_service::{
+        csv_exporter::read_raw_packets,
+        robot_data::RobotDataPacket,
+        structs::{ObserveType, Unit, SHOW_RAD_TYPE},
+    },
+    utils::i18n,
+};
+use serde::Serialize;
+use std::{
+    io::{self},
+    path::Path,
+};
+
+/// 原始数据中可用的通道
+pub const RAW_CHANNELS: &[ObserveType] = &[
+    ObserveType::TargetJointPositions,
+    ObserveType::TargetJointVelocities,
+    ObserveType::TargetJointAccelerations,
+    ObserveType::ActualJointPositions,
+    ObserveType::ActualJointVelocities,
+    ObserveType::ActualJointAccelerations,
+    ObserveType::ActualJointCurrents,
+    ObserveType::EstimatedJointTorque,
+    ObserveType::TargetTcpPose,
+    ObserveType::ActualTcpPose,
+    ObserveType::TargetTcpVelocity,
+    ObserveType::ActualTcpVelocity,
+    ObserveType::EstimatedTcpTorque,
+    ObserveType::TargetTcpAccelerations,
+    ObserveType::ActualTcpAccelerations,
+    ObserveType::DataTorqueSensor,
+    ObserveType::FilteredDataTorqueSensor,
+];
+
+/// CSV 中识别为时间列的表头 (不区分大小写)
+const TIME_COLUMNS: &[&str] = &["timestamp", "time", "t"];
+
+/// 运动状态通道名称 (仅原始数据)
+pub const MOTION_STATE_CHANNEL: &str = "motion_state";
+
+/// 单个标量通道
+#[derive(Debug, Clone, Serialize)]
+pub struct Series {
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+/// 内存中的录制数据, 所有通道共享同一时间轴
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Recording {
+    pub time: Vec<f64>, // 相对第一帧的时间 s
+    pub channels: Vec<Series>,
+}
+
+/// 原始数据包中某一类型的数值
+pub fn packet_values(packet: &RobotDataPacket, ot: ObserveType) -> &[f32] {
+    match ot {
+        ObserveType::TargetJointPositions => &packet.target_joint_positions,
+        ObserveType::TargetJointVelocities => &packet.target_joint_velocities,
+        ObserveType::TargetJointAccelerations => &packet.target_joint_accelerations,
+        ObserveType::ActualJointPositions => &packet.actual_joint_positions,
+        ObserveType::ActualJointVelocities => &packet.actual_joint_velocities,
+        ObserveType::ActualJointAccelerations => &packet.actual_joint_accelerations,
+        ObserveType::ActualJointCurrents => &packet.actual_joint_currents,
+        ObserveType::EstimatedJointTorque => &packet.estimated_joint_torque,
+        ObserveType::TargetTcpPose => &packet.target_tcp_pose,
+        ObserveType::ActualTcpPose => &packet.actual_tcp_pose,
+        ObserveType::TargetTcpVelocity => &packet.target_tcp_velocity,
+        ObserveType::ActualTcpVelocity => &packet.actual_tcp_velocity,
+        ObserveType::EstimatedTcpTorque => &packet.estimated_tcp_torque,
+        ObserveType::TargetTcpAccelerations => &packet.target_tcp_accelerations,
+        ObserveType::ActualTcpAccelerations => &packet.actual_tcp_accelerations,
+        ObserveType::DataTorqueSensor => &packet.data_torque_sensor,
+        ObserveType::FilteredDataTorqueSensor => &packet.filtered_data_torque_sensor,
+        _ => &[],
+    }
+}
+
+/// 通道单位, 通道名为 {类型}_{序号}
+///
+/// `angle_unit` 为录制时的角度单位, 仅影响关节类型 (与界面显示一致); 原始数据均为弧度
+pub fn channel_unit(name: &str, angle_unit: Unit) -> &'static str {
+    let Some((type_name, index)) = name.rsplit_once('_') else {
+        return "";
+    };
+    let Ok(index) = index.parse::<usize>() else {
+        return "";
+    };
+    let Some(ot) = RAW_CHANNELS.iter().find(|ot| ot.name() == type_name) else {
+        return "";
+    };
+
+    let degree = angle_unit == Unit::Angle && SHOW_RAD_TYPE.contains(ot);
+    // TCP 类型前3个为平移分量, 后3个为旋转分量
+    let linear = index <= 3;
+    match ot {
+        ObserveType::TargetJointPositions | ObserveType::ActualJointPositions => {
+            if degree {
+                "deg"
+            } else {
+                "rad"
+            }
+        }
+        ObserveType::TargetJointVelocities | ObserveType::ActualJointVelocities => {
+            if degree {
+                "deg/s"
+            } else {
+                "rad/s"
+            }
+        }
+        ObserveType::TargetJointAccelerations | ObserveType::ActualJointAccelerations => {
+            if degree {
+                "deg/s^2"
+            } else {
+                "rad/s^2"
+            }
+        }
+        ObserveType::ActualJointCurrents => "A",
+        ObserveType::EstimatedJointTorque => "N*m",
+        ObserveType::TargetTcpPose | ObserveType::ActualTcpPose => {
+            if linear {
+                "mm"
+            } else {
+                "rad"
+            }
+        }
+        ObserveType::TargetTcpVelocity | ObserveType::ActualTcpVelocity => {
+            if linear {
+                "mm/s"
+            } else {
+                "rad/s"
+            }
+        }
+        ObserveType::TargetTcpAccelerations | ObserveType::ActualTcpAccelerations => {
+            if linear {
+                "mm/s^2"
+            } else {
+                "rad/s^2"
+            }
+        }
+        ObserveType::EstimatedTcpTorque
+        | ObserveType::DataTorqueSensor
+        | ObserveType::FilteredDataTorqueSensor => {
+            if linear {
+                "N"
+            } else {
+                "N*m"
+            }
+        }
+        _ => "",
+    }
+}
+
+impl Recording {
+    /// 按扩展名读取录制文件: .raw 原始数据, 其他按CSV解析
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("raw") => Ok(Self::from_packets(&read_raw_packets(path)?)),
+            _ => Self::from_csv(path),
+        }
+    }
+
+    /// 由原始数据包生成, 通道名与CSV表头一致 ({类型}_{序号})
+    pub fn from_packets(packets: &[RobotDataPacket]) -> Self {
+        let mut recording = Recording::default();
+        let Some(first) = packets.first() else {
+            return recording;
+        };
+
+        for ot in RAW_CHANNELS {
+            for i in 1..=packet_values(first, *ot).len() {
+                recording.channels.push(Series {
+                    name: format!("{}_{}", ot.name(), i),
+                    values: Vec::with_capacity(packets.len()),
+                });
+            }
+        }
+        recording.channels.push(Series {
+            name: MOTION_STATE_CHANNEL.to_string(),
+            values: Vec::with_capacity(packets.len()),
+        });
+
+        for packet in packets {
+            // 控制器时间戳单位 μs
+            recording
+                .time
+                .push((packet.timestamp - first.timestamp) as f64 / 1_000_000.0);
+
+            let mut channel = recording.channels.iter_mut();
+            for ot in RAW_CHANNELS {
+                for v in packet_values(packet, *ot) {
+                    if let Some(series) = channel.next() {
+                        series.values.push(*v as f64);
+                    }
+                }
+            }
+            if let Some(series) = channel.next() {
+                series.values.push(packet.motion_state() as f64);
+            }
+        }
+
+        recording
+    }
+
+    /// 解析CSV: 时间列为表头中的 timestamp (毫秒时间戳) 或 time/t (秒), 没有时间列的
+    /// 通用CSV以采样序号作为时间; 没有表头的旧文件第一列为 timestamp, 其余按 col_{序号} 命名
+    pub fn from_csv(path: &Path) -> io::Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)?;
+
+        let mut rows = reader.records();
+        let first = match rows.next() {
+            Some(record) => record?,
+            None => return Ok(Recording::default()),
+        };
+
+        let has_header = first.iter().any(|f| f.trim().parse::<f64>().is_err());
+        let header: Vec<String> = if has_header {
+            // 导出的CSV表头为界面语言的通道名称 (可能带 BOM), 还原为通道名
+            first
+                .iter()
+                .map(|f| {
+                    let f = f.trim_start_matches('\u{feff}').trim();
+                    i18n::channel_name(f).unwrap_or_else(|| f.to_string())
+                })
+                .collect()
+        } else {
+            std::iter::once("timestamp".to_string())
+                .chain((1..first.len()).map(|i| format!("col_{}", i)))
+                .collect()
+        };
+
+        let time_col = header
+            .iter()
+            .position(|name| TIME_COLUMNS.contains(&name.to_lowercase().as_str()));
+        // 本程序导出的 timestamp 列为毫秒
+        let time_scale = match time_col {
+            Some(col) if header[col].eq_ignore_ascii_case("timestamp") => 0.001,
+            _ => 1.0,
+        };
+
+        let columns: Vec<usize> = (0..header.len()).filter(|i| Some(*i) != time_col).collect();
+        let mut recording = Recording {
+            time: vec![],
+            channels: columns
+                .iter()
+                .map(|i| Series {
+                    name: header[*i].clone(),
+                    values: vec![],
+                })
+                .collect(),
+        };
+
+        let data_rows = if has_header { None } else { Some(first) };
+        let mut origin = None;
+        for record in data_rows.into_iter().map(Ok).chain(rows) {
+            let record = record?;
+            let t = match time_col {
+                Some(col) => match record.get(col).and_then(|f| f.trim().parse::<f64>().ok()) {
+                    Some(t) => t,
+                    None => continue,
+                },
+                None => recording.time.len() as f64,
+            };
+            let origin = *origin.get_or_insert(t);
+            recording.time.push((t - origin) * time_scale);
+
+            for (col, series) in columns.iter().zip(recording.channels.iter_mut()) {
+                let value = record
+                    .get(*col)
+                    .and_then(|f| f.trim().parse::<f64>().ok())
+                    .unwrap_or(f64::NAN);
+                series.values.push(value);
+            }
+        }
+
+        Ok(recording)
+    }
+
+    pub fn channel(&self, name: &str) -> Option<&Series> {
+        self.channels.iter().find(|c| c.name == name)
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.time.last().copied().unwrap_or(0.0)
+    }
+}
+
+/// 线性插值, 超出时间范围返回 None
+pub fn interpolate(time: &[f64], values: &[f64], t: f64) -> Option<f64> {
+    let (first, last) = (*time.first()?, *time.last()?);
+    if t < first || t > last {
+        return None;
+    }
+
+    let idx = time.partition_point(|x| *x < t);
+    if idx == 0 {
+        return values.first().copied();
+    }
+    if idx >= time.len() {
+        return values.last().copied();
+    }
+
+    let (t0, t1) = (time[idx - 1], time[idx]);
+    let (v0, v1) = (values[idx - 1], values[idx]);
+    if t1 <= t0 {
+        return Some(v1);
+    }
+    Some(v0 + (v1 - v0) * (t - t0) / (t1 - t0))
+}
@@ -1,6 +1,9 @@
-use std::io::{self, Read};
-use std::net::TcpStream;
+use crate::utils::i18n;
+use std::io;
 use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
 
 /// 机器人连接管理
 #[derive(Debug)]
@@ -9,29 +12,38 @@ pub struct RobotConnection {
 }
 
 impl RobotConnection {
-    /// 连接到机器人
-    pub fn connect(ip_addr: String, read_timeout: u64) -> io::Result<Self> {
-        let stream = TcpStream::connect(&ip_addr)?;
-        stream.set_read_timeout(Some(Duration::from_secs(read_timeout)))?;
+    /// 连接到机器人, 超过 `connect_timeout` 返回 TimedOut
+    pub async fn connect(ip_addr: &str, connect_timeout: Duration) -> io::Result<Self> {
+        let stream = timeout(connect_timeout, TcpStream::connect(ip_addr))
+            .await
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    i18n::t_args("robot.connect_timeout", &[("ip", ip_addr.to_string())]),
+                )
+            })??;
+        stream.set_nodelay(true)?;
         println!("已成功连接到机器人: {}", ip_addr);
         Ok(Self { stream })
     }
 
-    /// 从机器人读取数据
-    pub fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
-        self.stream.read(buffer)
+    /// 从机器人读取数据, `idle_timeout` 内没有收到任何数据返回 TimedOut
+    pub async fn read(&mut self, buffer: &mut [u8], idle_timeout: Duration) -> io::Result<usize> {
+        timeout(idle_timeout, self.stream.read(buffer))
+            .await
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    i18n::t_args(
+                        "robot.read_timeout",
+                        &[("ms", idle_timeout.as_millis().to_string())],
+                    ),
+                )
+            })?
     }
 
     // 关闭连接
-    pub fn close(&mut self) -> io::Result<()> {
-        self.stream.shutdown(std::net::Shutdown::Both)
-    }
-}
-
-// 实现 Drop trait，在连接对象被销毁时关闭TCP流
-impl Drop for RobotConnection {
-    fn drop(&mut self) {
-        println!("关闭TCP连接...");
-        // TcpStream的析构函数会自动关闭连接
+    pub async fn close(&mut self) -> io::Result<()> {
+        self.stream.shutdown().await
     }
 }
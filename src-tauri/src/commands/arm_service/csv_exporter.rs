@@ -1,18 +1,63 @@
 // csv_exporter.rs
-use crate::commands::arm_service::structs::ResponseChartData;
-use chrono::Local;
+use crate::{
+    commands::arm_service::{
+        parser::Parser, robot_data::RobotDataPacket, structs::ResponseChartData,
+    },
+    utils::i18n,
+};
+use chrono::{DateTime, Local};
 use csv::Writer;
+use serde::{Deserialize, Serialize};
 use std::{
-    io::{self},
-    path::PathBuf,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
+// 定期 fsync 的间隔, 异常断电时最多丢失该时长的数据
+const SYNC_INTERVAL: Duration = Duration::from_secs(2);
+
+// 导出的CSV带 BOM, Excel 才能正确识别中文表头
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct CsvExporter {
     writer: Writer<std::fs::File>,
     temp_path: PathBuf,
+    // 原始数据包 (与CSV同名, 扩展名 .raw), 保存完整的 RobotDataPacket 供离线分析
+    raw_writer: BufWriter<File>,
+    raw_path: PathBuf,
+    // 表头在第一次写入时根据数据类型生成
+    header_written: bool,
+    // 本次录制的开始时间与已写入行数
+    started_at: DateTime<Local>,
+    samples: usize,
+    // 已归档到会话库, 避免重复归档
+    archived: bool,
+    last_sync: Instant,
     csv_temp_dir: PathBuf, // 用户数据目录中的CSV临时目录
+    robot_tag: String,     // 文件名中的机械臂标识, 避免多台机械臂同时录制时文件冲突
+    // 录制中的事件标记 (如控制器报错)
+    markers: Vec<RecordingMarker>,
+}
+
+/// 录制标记
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingMarker {
+    pub timestamp: i64, // ms, 与CSV时间戳列相同
+    pub offset: f64,    // 相对录制开始 s
+    pub sample: usize,  // 标记时已写入的行数
+    pub label: String,
+    #[serde(default)]
+    pub code: Option<i64>, // 控制器错误/警告码
+}
+
+/// 临时文件路径: robot_data_{时间}_{机械臂}.csv
+fn temp_file_path(csv_temp_dir: &Path, robot_tag: &str) -> PathBuf {
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S");
+    csv_temp_dir.join(format!("robot_data_{timestamp}_{robot_tag}.csv"))
 }
 
 impl CsvExporter {
@@ -20,18 +65,33 @@ impl CsvExporter {
     ///
     /// # 参数
     /// * `csv_temp_dir` - CSV临时文件目录 (来自 UserDataPaths.csv_temp)
-    pub fn new(csv_temp_dir: PathBuf) -> io::Result<Self> {
+    /// * `robot_id` - 机械臂标识, 用于区分临时文件
+    pub fn new(csv_temp_dir: PathBuf, robot_id: &str) -> io::Result<Self> {
         // 确保目录存在
         std::fs::create_dir_all(&csv_temp_dir)?;
 
-        let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-        let temp_path = csv_temp_dir.join(format!("robot_data_{timestamp}.csv"));
+        let robot_tag: String = robot_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let temp_path = temp_file_path(&csv_temp_dir, &robot_tag);
         let writer = Writer::from_path(&temp_path)?;
+        let raw_path = temp_path.with_extension("raw");
+        let raw_writer = BufWriter::new(File::create(&raw_path)?);
 
         Ok(Self {
             writer,
             temp_path,
+            raw_writer,
+            raw_path,
+            header_written: false,
+            started_at: Local::now(),
+            samples: 0,
+            archived: false,
+            last_sync: Instant::now(),
             csv_temp_dir,
+            robot_tag,
+            markers: vec![],
         })
     }
 
@@ -40,12 +100,20 @@ impl CsvExporter {
         // 先尝试删除旧文件
         let _ = self.delete();
 
-        let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-        let temp_path = self.csv_temp_dir.join(format!("robot_data_{timestamp}.csv"));
+        let temp_path = temp_file_path(&self.csv_temp_dir, &self.robot_tag);
         let writer = Writer::from_path(&temp_path)?;
+        let raw_path = temp_path.with_extension("raw");
+        let raw_writer = BufWriter::new(File::create(&raw_path)?);
 
         self.temp_path = temp_path;
         self.writer = writer;
+        self.raw_path = raw_path;
+        self.raw_writer = raw_writer;
+        self.header_written = false;
+        self.started_at = Local::now();
+        self.samples = 0;
+        self.archived = false;
+        self.markers.clear();
 
         Ok(())
     }
@@ -56,6 +124,9 @@ impl CsvExporter {
         if self.temp_path.exists() {
             std::fs::remove_file(&self.temp_path)?;
         }
+        if self.raw_path.exists() {
+            std::fs::remove_file(&self.raw_path)?;
+        }
         Ok(())
     }
 
@@ -63,6 +134,11 @@ impl CsvExporter {
     pub fn write_packet(&mut self, packet: &ResponseChartData) -> io::Result<()> {
         self.writer.flush()?;
 
+        if !self.header_written {
+            self.writer.write_record(csv_header(packet))?;
+            self.header_written = true;
+        }
+
         let mut record: Vec<String> = vec![];
 
         // 写入时间戳 当前时间
@@ -78,19 +154,31 @@ impl CsvExporter {
         // 写入 CSV
         self.writer.write_record(&record)?;
         self.writer.flush()?;
+        self.samples += 1;
+        self.sync_if_due()?;
 
         // 强制换行
         Ok(())
     }
 
+    /// 写入原始数据包
+    pub fn write_raw(&mut self, packet: &RobotDataPacket) -> io::Result<()> {
+        self.raw_writer.write_all(&packet.to_bytes())
+    }
+
     /// 保存CSV文件到指定路径
     pub fn save_to(&mut self, dest_path: &PathBuf) -> std::io::Result<()> {
         // 关闭写入器
         self.writer.flush()?;
 
         // 复制临时文件到目标路径
-        std::fs::copy(&self.temp_path, dest_path)?;
+        copy_localized(&self.temp_path, dest_path)
+    }
 
+    /// 保存原始数据文件到指定路径
+    pub fn save_raw_to(&mut self, dest_path: &PathBuf) -> std::io::Result<()> {
+        self.raw_writer.flush()?;
+        std::fs::copy(&self.raw_path, dest_path)?;
         Ok(())
     }
 
@@ -111,4 +199,171 @@ impl CsvExporter {
     pub fn temp_path(&self) -> &PathBuf {
         &self.temp_path
     }
+
+    /// 将缓冲数据写入临时文件
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.raw_writer.flush()
+    }
+
+    /// 距上次同步超过 SYNC_INTERVAL 时将数据落盘
+    fn sync_if_due(&mut self) -> io::Result<()> {
+        if self.last_sync.elapsed() < SYNC_INTERVAL {
+            return Ok(());
+        }
+
+        self.flush()?;
+        self.writer.get_ref().sync_data()?;
+        self.raw_writer.get_ref().sync_data()?;
+        self.last_sync = Instant::now();
+
+        Ok(())
+    }
+
+    /// 本次录制的开始时间
+    pub fn started_at(&self) -> DateTime<Local> {
+        self.started_at
+    }
+
+    /// 本次录制已写入的行数
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// 是否有尚未归档到会话库的数据
+    pub fn needs_archive(&self) -> bool {
+        self.samples > 0 && !self.archived
+    }
+
+    /// 标记已归档
+    pub fn mark_archived(&mut self) {
+        self.archived = true;
+    }
+
+    /// 在当前位置添加标记
+    pub fn add_marker(&mut self, label: &str, code: Option<i64>) -> RecordingMarker {
+        let now = Local::now();
+        let marker = RecordingMarker {
+            timestamp: now.timestamp_millis(),
+            offset: (now - self.started_at).num_milliseconds() as f64 / 1000.0,
+            sample: self.samples,
+            label: label.to_string(),
+            code,
+        };
+        self.markers.push(marker.clone());
+        marker
+    }
+
+    /// 本次录制的标记
+    pub fn markers(&self) -> &[RecordingMarker] {
+        &self.markers
+    }
+
+    /// 获取原始数据文件路径
+    #[allow(dead_code)]
+    pub fn raw_path(&self) -> &PathBuf {
+        &self.raw_path
+    }
+}
+
+/// 生成CSV表头: timestamp, {类型}_{序号}...
+pub fn csv_header(packet: &ResponseChartData) -> Vec<String> {
+    let mut header = vec!["timestamp".to_string()];
+    for cd in &packet.data {
+        for i in 1..=cd.value.len() {
+            header.push(format!("{}_{}", cd.data_type.name(), i));
+        }
+    }
+    header
+}
+
+/// 复制CSV文件, 表头换成当前界面语言的通道名称; 临时文件与会话库中的表头保持通道名不变
+pub fn copy_localized(src: &Path, dest: &Path) -> io::Result<()> {
+    let mut reader = BufReader::new(File::open(src)?);
+    let mut writer = BufWriter::new(File::create(dest)?);
+
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    let names = header.trim_end_matches(['\r', '\n']);
+    if !names.is_empty() {
+        let language = i18n::language();
+        let labels: Vec<String> = names
+            .split(',')
+            .map(|name| i18n::channel_label(language, name))
+            .collect();
+        writer.write_all(UTF8_BOM)?;
+        writer.write_all(labels.join(",").as_bytes())?;
+        writer.write_all(&header.as_bytes()[names.len()..])?;
+    }
+
+    io::copy(&mut reader, &mut writer)?;
+    writer.flush()
+}
+
+/// 读取原始数据文件中的全部数据包
+pub fn read_raw_packets(path: &Path) -> io::Result<Vec<RobotDataPacket>> {
+    let mut packets = Vec::new();
+    for_each_raw_packet(path, |packet| {
+        packets.push(packet.clone());
+        Ok(())
+    })?;
+    Ok(packets)
+}
+
+/// 分块读取原始数据文件, 依次处理每个数据包, 返回数据包数量;
+/// 遇到无法识别的数据时停止读取
+pub fn for_each_raw_packet<F>(path: &Path, mut handler: F) -> io::Result<usize>
+where
+    F: FnMut(&RobotDataPacket) -> io::Result<()>,
+{
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    let parser = Parser::new();
+    let mut reader = File::open(path)?;
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut buffer = Vec::with_capacity(CHUNK_SIZE * 2);
+    let mut count = 0;
+
+    loop {
+        let bytes_read = reader.read(&mut chunk)?;
+        if bytes_read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..bytes_read]);
+        count += parser.process_packets(&mut buffer, &mut handler)?;
+
+        // 单个数据包最大 4KB, 剩余数据超过一个块说明文件已损坏
+        if buffer.len() > CHUNK_SIZE {
+            eprintln!(
+                "Invalid data in raw recording {}, stopped after {} packets",
+                path.display(),
+                count
+            );
+            break;
+        }
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_SIZE: usize = 784;
+
+    #[test]
+    fn test_read_raw_packets_across_chunks() {
+        let path = std::env::temp_dir().join(format!("ufactory_raw_{}.raw", std::process::id()));
+        let mut frame = vec![0u8; FRAME_SIZE];
+        frame[0..4].copy_from_slice(&(FRAME_SIZE as u32).to_le_bytes());
+        // 数据包跨越多个读取块, 末尾附带不完整的数据包
+        let mut data = frame.repeat(200);
+        data.extend_from_slice(&frame[0..100]);
+        std::fs::write(&path, &data).unwrap();
+
+        let packets = read_raw_packets(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(packets.len(), 200);
+    }
 }
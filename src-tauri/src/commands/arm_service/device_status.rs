@@ -0,0 +1,275 @@
+// device_status.rs - 连接期间持续订阅 18333 WebSocket 的设备状态
+//
+// 控制器先上报 devices_status_keys_report (键名), 之后持续上报
+// devices_status_report (与键名一一对应的值); 断线后按退避间隔重连
+use crate::commands::arm_service::{
+    error_codes::{error_info, warning_info, CodeInfo},
+    ws_get::{status_url, StatusReportParser, WSSdkData},
+};
+use futures::StreamExt;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::{
+    sync::watch,
+    time::{sleep, timeout},
+};
+use tokio_tungstenite::connect_async;
+use url::Url;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(10);
+
+// 设备状态中的键名, 旧固件可能缺少部分键
+const KEY_TYPE: &str = "xarm_type";
+const KEY_VERSION: &str = "version";
+const KEY_STATE: &str = "xarm_state";
+const KEY_MODE: &str = "xarm_mode";
+const KEY_ERROR: &str = "xarm_error_code";
+const KEY_WARN: &str = "xarm_warn_code";
+const KEY_GRIPPER: &str = "gripper";
+
+/// 设备状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DeviceStatus {
+    pub axis: i32,
+    pub robot_type: Option<i64>,
+    pub version: Option<String>, // 固件版本
+    pub state: Option<i64>,      // 1 运动中, 2 待机, 3 暂停, 4 停止
+    pub mode: Option<i64>,       // 0 位置, 1 伺服, 2 关节示教, 4 关节速度, 5 笛卡尔速度 ...
+    pub error: Option<CodeInfo>,
+    pub warning: Option<CodeInfo>,
+    pub ft_sensor: bool,
+    pub gripper: Option<Value>,
+}
+
+/// 设备状态及控制器上报的全部键值
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceStatusReport {
+    #[serde(flatten)]
+    pub status: DeviceStatus,
+    pub device_info: Map<String, Value>,
+}
+
+// 整数字段, 兼容以字符串上报的固件
+fn int(status: &Map<String, Value>, key: &str) -> Option<i64> {
+    let value = status.get(key)?;
+    value
+        .as_i64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+impl DeviceStatus {
+    pub fn from_status(status: &Map<String, Value>) -> Self {
+        let sdk_data = WSSdkData::from_status(status);
+        Self {
+            axis: sdk_data.axis,
+            robot_type: int(status, KEY_TYPE),
+            version: status.get(KEY_VERSION).map(|version| match version {
+                Value::String(version) => version.clone(),
+                version => version.to_string(),
+            }),
+            state: int(status, KEY_STATE),
+            mode: int(status, KEY_MODE),
+            error: int(status, KEY_ERROR).and_then(error_info),
+            warning: int(status, KEY_WARN).and_then(warning_info),
+            ft_sensor: sdk_data.ft_sensor,
+            gripper: status.get(KEY_GRIPPER).cloned(),
+        }
+    }
+}
+
+/// 运行中的设备状态订阅, 停止或释放时断开
+#[derive(Debug)]
+pub struct DeviceStatusMonitor {
+    shutdown: watch::Sender<bool>,
+    latest: Arc<RwLock<Option<DeviceStatusReport>>>,
+}
+
+impl DeviceStatusMonitor {
+    /// 在当前 tokio 运行时中开始订阅, 状态变化时调用 `on_change`
+    pub fn start<F>(ip: &str, port: u16, lang: &str, on_change: F) -> Result<Self, String>
+    where
+        F: Fn(&DeviceStatusReport) + Send + Sync + 'static,
+    {
+        let url =
+            status_url(ip, port, lang).map_err(|e| format!("Invalid address {}: {}", ip, e))?;
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let latest = Arc::new(RwLock::new(None));
+
+        tokio::spawn(monitor_loop(url, shutdown_rx, latest.clone(), on_change));
+        Ok(Self { shutdown, latest })
+    }
+
+    /// 最近一次上报的设备状态
+    pub fn latest(&self) -> Option<DeviceStatusReport> {
+        self.latest.read().ok().and_then(|latest| latest.clone())
+    }
+
+    pub fn stop(self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+impl Drop for DeviceStatusMonitor {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+async fn monitor_loop<F>(
+    url: Url,
+    mut shutdown: watch::Receiver<bool>,
+    latest: Arc<RwLock<Option<DeviceStatusReport>>>,
+    on_change: F,
+) where
+    F: Fn(&DeviceStatusReport) + Send + Sync + 'static,
+{
+    let mut backoff = RECONNECT_MIN;
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            reports = subscribe(&url, &latest, &on_change) => {
+                if reports > 0 {
+                    backoff = RECONNECT_MIN;
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            _ = sleep(backoff) => {}
+        }
+        backoff = (backoff * 2).min(RECONNECT_MAX);
+    }
+}
+
+/// 连接并读取设备状态直到断开, 返回收到的状态数
+async fn subscribe<F>(
+    url: &Url,
+    latest: &RwLock<Option<DeviceStatusReport>>,
+    on_change: &F,
+) -> usize
+where
+    F: Fn(&DeviceStatusReport),
+{
+    let mut ws_stream = match timeout(CONNECT_TIMEOUT, connect_async(url.as_str())).await {
+        Ok(Ok((ws_stream, _))) => ws_stream,
+        Ok(Err(e)) => {
+            eprintln!("Device status websocket connect failed: {}", e);
+            return 0;
+        }
+        Err(_) => {
+            eprintln!("Device status websocket connect timed out");
+            return 0;
+        }
+    };
+
+    let mut parser = StatusReportParser::default();
+    let mut device_info = Map::new();
+    let mut reports = 0;
+    while let Some(msg) = ws_stream.next().await {
+        let msg = match msg {
+            Ok(msg) => msg,
+            Err(e) => {
+                eprintln!("Device status websocket error: {}", e);
+                break;
+            }
+        };
+        let Ok(text) = msg.to_text() else {
+            continue;
+        };
+        let Some(values) = parser.parse(text) else {
+            continue;
+        };
+        reports += 1;
+
+        // 按键合并, 本次未上报的键保留上次的值
+        device_info.extend(values);
+        let status = DeviceStatus::from_status(&device_info);
+        let changed = latest
+            .read()
+            .map(|latest| latest.as_ref().map(|report| &report.status) != Some(&status))
+            .unwrap_or(true);
+
+        let report = DeviceStatusReport {
+            status,
+            device_info: device_info.clone(),
+        };
+        if let Ok(mut latest) = latest.write() {
+            *latest = Some(report.clone());
+        }
+        if changed {
+            on_change(&report);
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::SinkExt;
+    use serde_json::json;
+    use std::sync::Mutex;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[tokio::test]
+    async fn test_monitor_reports_changes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let keys = json!({
+                "cmd": "devices_status_keys_report",
+                "data": ["xarm_axis", "xarm_state", "xarm_error_code", "xarm_warn_code"],
+            });
+            let _ = ws.send(Message::Text(keys.to_string())).await;
+            for data in [
+                json!([6, 2, 0, 0]),
+                json!([6, 2, 0, 0]),
+                json!([6, 4, 22, "11"]),
+            ] {
+                let report = json!({ "cmd": "devices_status_report", "data": data });
+                let _ = ws.send(Message::Text(report.to_string())).await;
+            }
+            // 保持连接直到客户端断开
+            while ws.next().await.is_some() {}
+        });
+
+        let changes = Arc::new(Mutex::new(vec![]));
+        let recorded = changes.clone();
+        let monitor = DeviceStatusMonitor::start("127.0.0.1", port, "cn", move |report| {
+            recorded.lock().unwrap().push(report.status.clone());
+        })
+        .unwrap();
+
+        for _ in 0..50 {
+            if changes.lock().unwrap().len() >= 2 {
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+
+        // 重复的状态不推送
+        let changes = changes.lock().unwrap().clone();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].axis, 6);
+        assert_eq!(changes[0].error, None);
+        assert_eq!(changes[1].state, Some(4));
+        assert_eq!(changes[1].error.unwrap().code, 22);
+        assert_eq!(changes[1].warning.unwrap().code, 11);
+        // 旧固件缺少的键
+        assert_eq!(changes[1].version, None);
+
+        let latest = monitor.latest().unwrap();
+        assert_eq!(latest.device_info["xarm_error_code"], 22);
+        monitor.stop();
+    }
+}
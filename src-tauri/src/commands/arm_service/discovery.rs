@@ -0,0 +1,247 @@
+// discovery.rs - 局域网内 xArm 控制器发现
+//
+// 并行探测网段内每个主机: TCP 30000 (实时数据端口) 可连接, 且 18333 WebSocket
+// 返回设备状态时视为控制器, 设备状态中包含轴数等信息
+use crate::commands::arm_service::{
+    ws_get::{ws_get_status, WSSdkData, WS_PORT},
+    ROBOT_PORT,
+};
+use futures::{stream, StreamExt};
+use ipnet::{IpNet, Ipv4Net};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeSet,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+use tokio::{net::TcpStream, time::timeout};
+
+// 单次扫描的最大主机数 (/20), 防止误填大网段
+const MAX_HOSTS: usize = 4096;
+// 未指定网段时, 本机网段大于 /24 的按本机所在的 /24 扫描
+const DEFAULT_PREFIX: u8 = 24;
+
+/// 发现参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoveryParams {
+    pub cidrs: Vec<String>,     // 如 "192.168.1.0/24", 为空时扫描本机所在的网段
+    pub timeout_ms: u64,        // 单个主机 TCP 连接超时
+    pub status_timeout_ms: u64, // 读取设备状态超时
+    pub concurrency: usize,
+    pub robot_port: u16,
+    pub ws_port: u16,
+    pub lang: String, // 控制器消息语言, 由命令按用户设置填写
+}
+
+impl Default for DiscoveryParams {
+    fn default() -> Self {
+        Self {
+            cidrs: vec![],
+            timeout_ms: 300,
+            status_timeout_ms: 3000,
+            concurrency: 64,
+            robot_port: ROBOT_PORT,
+            ws_port: WS_PORT,
+            lang: "cn".to_string(),
+        }
+    }
+}
+
+/// 发现的控制器
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredRobot {
+    pub ip: String,
+    pub axis: i32,
+    pub ft_sensor: bool,
+    pub device_info: Map<String, Value>, // WebSocket 返回的完整设备状态
+}
+
+/// 本机 IPv4 网卡所在的网段 (不含回环地址)
+pub fn local_networks() -> Vec<IpNet> {
+    let Ok(interfaces) = if_addrs::get_if_addrs() else {
+        return vec![];
+    };
+
+    let networks: BTreeSet<Ipv4Net> = interfaces
+        .iter()
+        .filter(|iface| !iface.is_loopback())
+        .filter_map(|iface| match &iface.addr {
+            if_addrs::IfAddr::V4(v4) => Ipv4Net::with_netmask(v4.ip, v4.netmask).ok(),
+            _ => None,
+        })
+        .filter_map(|net| {
+            let prefix = net.prefix_len().max(DEFAULT_PREFIX);
+            Ipv4Net::new(net.addr(), prefix).ok().map(|n| n.trunc())
+        })
+        .collect();
+    networks.into_iter().map(IpNet::V4).collect()
+}
+
+/// 解析网段并展开为主机地址
+fn hosts(params: &DiscoveryParams) -> Result<Vec<IpAddr>, String> {
+    let networks = if params.cidrs.is_empty() {
+        local_networks()
+    } else {
+        params
+            .cidrs
+            .iter()
+            .map(|cidr| {
+                cidr.trim()
+                    .parse::<IpNet>()
+                    // 单个地址视为 /32
+                    .or_else(|_| cidr.trim().parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| format!("Invalid CIDR: {}", cidr))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    if networks.is_empty() {
+        return Err("No network to scan".to_string());
+    }
+
+    let mut hosts = BTreeSet::new();
+    for network in &networks {
+        for host in network.hosts() {
+            hosts.insert(host);
+            if hosts.len() > MAX_HOSTS {
+                return Err(format!("Too many hosts to scan (max {})", MAX_HOSTS));
+            }
+        }
+    }
+    Ok(hosts.into_iter().collect())
+}
+
+/// 探测单个主机, 不是控制器时返回 None
+async fn probe(ip: IpAddr, params: &DiscoveryParams) -> Option<DiscoveredRobot> {
+    let connect_timeout = Duration::from_millis(params.timeout_ms);
+    let addr = SocketAddr::new(ip, params.robot_port);
+    timeout(connect_timeout, TcpStream::connect(addr))
+        .await
+        .ok()?
+        .ok()?;
+
+    let status_timeout = Duration::from_millis(params.status_timeout_ms);
+    let status = timeout(
+        status_timeout,
+        ws_get_status(&ip.to_string(), params.ws_port, &params.lang),
+    )
+    .await
+    .ok()?
+    .ok()?;
+
+    let sdk_data = WSSdkData::from_status(&status);
+    Some(DiscoveredRobot {
+        ip: ip.to_string(),
+        axis: sdk_data.axis,
+        ft_sensor: sdk_data.ft_sensor,
+        device_info: status,
+    })
+}
+
+/// 并行扫描网段, 按 IP 排序返回发现的控制器
+pub async fn discover(params: &DiscoveryParams) -> Result<Vec<DiscoveredRobot>, String> {
+    let hosts = hosts(params)?;
+    eprintln!("Scanning {} hosts for xArm controllers", hosts.len());
+
+    let mut robots: Vec<DiscoveredRobot> = stream::iter(hosts)
+        .map(|ip| probe(ip, params))
+        .buffer_unordered(params.concurrency.max(1))
+        .filter_map(|robot| async move { robot })
+        .collect()
+        .await;
+    robots.sort_by_key(|robot| robot.ip.parse::<IpAddr>().ok());
+
+    Ok(robots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::SinkExt;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// 模拟控制器: 数据端口只接受连接, WebSocket 连接后推送设备状态
+    async fn mock_controller() -> (u16, u16) {
+        let robot = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ports = (
+            robot.local_addr().unwrap().port(),
+            ws.local_addr().unwrap().port(),
+        );
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = robot.accept().await {
+                drop(stream);
+            }
+        });
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = ws.accept().await {
+                let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+                    continue;
+                };
+                let keys = json!({ "cmd": "devices_status_keys_report", "data": ["xarm_axis", "ft_sensor", "version"] });
+                let report = json!({
+                    "cmd": "devices_status_report",
+                    "data": [6, { "axis": [0, 1], "mode": 1 }, "2.5.0"],
+                });
+                let _ = ws.send(Message::Text(keys.to_string())).await;
+                let _ = ws.send(Message::Text(report.to_string())).await;
+            }
+        });
+        ports
+    }
+
+    #[test]
+    fn test_hosts() {
+        let params = DiscoveryParams {
+            cidrs: vec!["192.168.1.0/30".to_string(), "192.168.1.1".to_string()],
+            ..Default::default()
+        };
+        let scanned = hosts(&params).unwrap();
+        let expected: Vec<IpAddr> = vec![
+            "192.168.1.1".parse().unwrap(),
+            "192.168.1.2".parse().unwrap(),
+        ];
+        assert_eq!(scanned, expected);
+
+        let params = DiscoveryParams {
+            cidrs: vec!["10.0.0.0/8".to_string()],
+            ..Default::default()
+        };
+        assert!(hosts(&params).is_err());
+        let params = DiscoveryParams {
+            cidrs: vec!["not an address".to_string()],
+            ..Default::default()
+        };
+        assert!(hosts(&params).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_discover_mock_controller() {
+        let (robot_port, ws_port) = mock_controller().await;
+        let params = DiscoveryParams {
+            cidrs: vec!["127.0.0.1/32".to_string()],
+            robot_port,
+            ws_port,
+            ..Default::default()
+        };
+
+        let robots = discover(&params).await.unwrap();
+        assert_eq!(robots.len(), 1);
+        assert_eq!(robots[0].ip, "127.0.0.1");
+        assert_eq!(robots[0].axis, 6);
+        assert!(robots[0].ft_sensor);
+        assert_eq!(robots[0].device_info["version"], "2.5.0");
+
+        // WebSocket 无响应的主机不是控制器
+        let params = DiscoveryParams {
+            robot_port: ws_port,
+            ws_port: robot_port,
+            ..params
+        };
+        assert!(discover(&params).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,434 @@
+// error_codes.rs - xArm 控制器错误码与警告码目录 (说明与处理建议), 跟随界面语言
+use crate::utils::i18n::{self, Language};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeKind {
+    Error,
+    Warning,
+}
+
+/// 错误/警告码及其说明
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CodeInfo {
+    pub kind: CodeKind,
+    pub code: i64,
+    pub description: &'static str,
+    pub action: &'static str, // 建议的处理方法
+}
+
+const UNKNOWN_ACTION: &str = "查阅控制器手册或联系技术支持";
+const UNKNOWN_ACTION_EN: &str = "See the controller manual or contact support";
+
+// (错误码, 说明, 处理建议), 英文表 (_EN) 与中文表的错误码一一对应
+const ERRORS: &[(i64, &str, &str)] = &[
+    (
+        1,
+        "急停按钮被按下",
+        "确认安全后松开急停按钮, 清除错误并重新使能机械臂",
+    ),
+    (
+        2,
+        "控制器急停 IO 被触发",
+        "检查控制器急停 IO 接线与外部急停设备, 恢复后清除错误",
+    ),
+    (3, "三态开关急停被触发", "松开或复位三态开关后清除错误"),
+    (
+        10,
+        "伺服电机错误",
+        "查看具体关节伺服错误码, 清除错误后重新使能; 反复出现时联系技术支持",
+    ),
+    (
+        11,
+        "1 号关节伺服错误",
+        "清除错误并重新使能; 反复出现时检查负载与关节状态",
+    ),
+    (
+        12,
+        "2 号关节伺服错误",
+        "清除错误并重新使能; 反复出现时检查负载与关节状态",
+    ),
+    (
+        13,
+        "3 号关节伺服错误",
+        "清除错误并重新使能; 反复出现时检查负载与关节状态",
+    ),
+    (
+        14,
+        "4 号关节伺服错误",
+        "清除错误并重新使能; 反复出现时检查负载与关节状态",
+    ),
+    (
+        15,
+        "5 号关节伺服错误",
+        "清除错误并重新使能; 反复出现时检查负载与关节状态",
+    ),
+    (
+        16,
+        "6 号关节伺服错误",
+        "清除错误并重新使能; 反复出现时检查负载与关节状态",
+    ),
+    (
+        17,
+        "7 号关节伺服错误",
+        "清除错误并重新使能; 反复出现时检查负载与关节状态",
+    ),
+    (
+        18,
+        "力矩传感器通信错误",
+        "检查力矩传感器连接线, 重新上电后清除错误",
+    ),
+    (
+        19,
+        "末端模块通信错误",
+        "检查末端工具 (如夹爪) 接线与波特率设置",
+    ),
+    (21, "运动学错误", "检查目标位姿是否可达, 调整轨迹后重新运行"),
+    (
+        22,
+        "自碰撞错误",
+        "检查轨迹与自碰撞模型设置, 手动将机械臂移出碰撞位置",
+    ),
+    (
+        23,
+        "关节角度超出限位",
+        "在示教模式下将关节移回限位范围内, 检查目标关节角度",
+    ),
+    (24, "速度超出限制", "降低运动速度或加速度后重新运行"),
+    (25, "规划错误", "检查运动指令参数与轨迹连续性"),
+    (
+        26,
+        "Linux 实时系统错误",
+        "重启控制器; 反复出现时联系技术支持",
+    ),
+    (27, "指令回复错误", "检查通信是否稳定, 重新连接后重试"),
+    (28, "末端模块通信错误", "检查末端工具接线与通信设置"),
+    (29, "其他错误", "清除错误后重试; 反复出现时联系技术支持"),
+    (30, "反馈速度超出限制", "降低运动速度, 检查负载设置是否正确"),
+    (
+        31,
+        "碰撞导致电流异常",
+        "检查机械臂周围是否有障碍物, 确认负载与碰撞灵敏度设置",
+    ),
+    (32, "三点画圆计算错误", "检查圆弧的三个点是否共线或过于接近"),
+    (33, "机械臂电流异常", "检查负载与 TCP 设置, 清除错误后重试"),
+    (34, "录制超时", "缩短轨迹录制时长后重新录制"),
+    (35, "超出安全边界", "将机械臂移回安全边界内, 检查边界设置"),
+    (36, "延时指令数量超出限制", "减少缓存的延时指令数量"),
+    (37, "手动模式下运动异常", "退出手动模式并清除错误后重试"),
+    (38, "关节角度异常", "检查关节零点与编码器状态, 联系技术支持"),
+    (39, "电源板间通信异常", "重启控制器; 反复出现时联系技术支持"),
+    (
+        50,
+        "六维力矩传感器读取错误",
+        "检查力矩传感器连接, 重新使能传感器",
+    ),
+    (
+        51,
+        "六维力矩传感器模式设置错误",
+        "检查传感器模式参数后重新设置",
+    ),
+    (
+        52,
+        "六维力矩传感器零点设置错误",
+        "保持传感器空载静止后重新设置零点",
+    ),
+    (
+        53,
+        "六维力矩传感器过载",
+        "减小末端受力, 检查负载是否超出传感器量程",
+    ),
+    (
+        110,
+        "机械臂底座板通信错误",
+        "检查机械臂与控制器之间的连接线, 重新上电",
+    ),
+    (
+        111,
+        "控制器外部 485 设备通信错误",
+        "检查外部 485 设备接线与通信参数",
+    ),
+];
+
+const ERRORS_EN: &[(i64, &str, &str)] = &[
+    (
+        1,
+        "Emergency stop button pressed",
+        "Release the emergency stop button once it is safe, clear the error and re-enable the arm",
+    ),
+    (
+        2,
+        "Controller emergency stop IO triggered",
+        "Check the emergency stop IO wiring and external E-stop devices, then clear the error",
+    ),
+    (
+        3,
+        "Enabling switch emergency stop triggered",
+        "Release or reset the enabling switch, then clear the error",
+    ),
+    (
+        10,
+        "Servo motor error",
+        "Check the joint servo error code, clear the error and re-enable; contact support if it recurs",
+    ),
+    (
+        11,
+        "Joint 1 servo error",
+        "Clear the error and re-enable; check the payload and joint if it recurs",
+    ),
+    (
+        12,
+        "Joint 2 servo error",
+        "Clear the error and re-enable; check the payload and joint if it recurs",
+    ),
+    (
+        13,
+        "Joint 3 servo error",
+        "Clear the error and re-enable; check the payload and joint if it recurs",
+    ),
+    (
+        14,
+        "Joint 4 servo error",
+        "Clear the error and re-enable; check the payload and joint if it recurs",
+    ),
+    (
+        15,
+        "Joint 5 servo error",
+        "Clear the error and re-enable; check the payload and joint if it recurs",
+    ),
+    (
+        16,
+        "Joint 6 servo error",
+        "Clear the error and re-enable; check the payload and joint if it recurs",
+    ),
+    (
+        17,
+        "Joint 7 servo error",
+        "Clear the error and re-enable; check the payload and joint if it recurs",
+    ),
+    (
+        18,
+        "Force torque sensor communication error",
+        "Check the sensor cable, power cycle and clear the error",
+    ),
+    (
+        19,
+        "End module communication error",
+        "Check the end tool (e.g. gripper) wiring and baud rate",
+    ),
+    (
+        21,
+        "Kinematics error",
+        "Check that the target pose is reachable, adjust the trajectory and run again",
+    ),
+    (
+        22,
+        "Self-collision error",
+        "Check the trajectory and self-collision model, then move the arm out of collision manually",
+    ),
+    (
+        23,
+        "Joint angle out of limit",
+        "Move the joint back within its limits in teach mode and check the target joint angles",
+    ),
+    (
+        24,
+        "Speed exceeds limit",
+        "Reduce the speed or acceleration and run again",
+    ),
+    (
+        25,
+        "Planning error",
+        "Check the motion command parameters and trajectory continuity",
+    ),
+    (
+        26,
+        "Linux real-time system error",
+        "Restart the controller; contact support if it recurs",
+    ),
+    (
+        27,
+        "Command reply error",
+        "Check that communication is stable, reconnect and retry",
+    ),
+    (
+        28,
+        "End module communication error",
+        "Check the end tool wiring and communication settings",
+    ),
+    (
+        29,
+        "Other error",
+        "Clear the error and retry; contact support if it recurs",
+    ),
+    (
+        30,
+        "Feedback speed exceeds limit",
+        "Reduce the speed and check the payload settings",
+    ),
+    (
+        31,
+        "Abnormal current caused by collision",
+        "Check for obstacles around the arm and verify the payload and collision sensitivity",
+    ),
+    (
+        32,
+        "Three-point circle calculation error",
+        "Check that the three arc points are not collinear or too close",
+    ),
+    (
+        33,
+        "Abnormal arm current",
+        "Check the payload and TCP settings, clear the error and retry",
+    ),
+    (
+        34,
+        "Recording timeout",
+        "Shorten the trajectory recording and record again",
+    ),
+    (
+        35,
+        "Safety boundary exceeded",
+        "Move the arm back inside the safety boundary and check the boundary settings",
+    ),
+    (
+        36,
+        "Too many delayed commands",
+        "Reduce the number of buffered delayed commands",
+    ),
+    (
+        37,
+        "Abnormal motion in manual mode",
+        "Leave manual mode, clear the error and retry",
+    ),
+    (
+        38,
+        "Abnormal joint angle",
+        "Check the joint zero point and encoder status; contact support",
+    ),
+    (
+        39,
+        "Power board communication error",
+        "Restart the controller; contact support if it recurs",
+    ),
+    (
+        50,
+        "Six-axis force torque sensor read error",
+        "Check the sensor connection and re-enable the sensor",
+    ),
+    (
+        51,
+        "Six-axis force torque sensor mode setting error",
+        "Check the sensor mode parameters and set them again",
+    ),
+    (
+        52,
+        "Six-axis force torque sensor zero setting error",
+        "Keep the sensor unloaded and still, then set the zero point again",
+    ),
+    (
+        53,
+        "Six-axis force torque sensor overload",
+        "Reduce the end load and check it is within the sensor range",
+    ),
+    (
+        110,
+        "Arm base board communication error",
+        "Check the cable between the arm and controller, then power cycle",
+    ),
+    (
+        111,
+        "Controller external RS485 device communication error",
+        "Check the external RS485 device wiring and communication parameters",
+    ),
+];
+
+const WARNINGS: &[(i64, &str, &str)] = &[
+    (
+        11,
+        "指令缓存已满",
+        "降低指令发送频率或等待缓存中的指令执行完成",
+    ),
+    (12, "指令参数异常", "检查指令参数是否在允许范围内"),
+    (13, "未知指令", "检查 SDK 与控制器固件版本是否匹配"),
+    (14, "指令无解", "检查目标位姿是否可达"),
+];
+
+const WARNINGS_EN: &[(i64, &str, &str)] = &[
+    (
+        11,
+        "Command buffer full",
+        "Send commands less often or wait for buffered commands to finish",
+    ),
+    (
+        12,
+        "Invalid command parameter",
+        "Check that the command parameters are within range",
+    ),
+    (
+        13,
+        "Unknown command",
+        "Check that the SDK and controller firmware versions match",
+    ),
+    (
+        14,
+        "No solution for command",
+        "Check that the target pose is reachable",
+    ),
+];
+
+fn lookup(kind: CodeKind, code: i64) -> Option<CodeInfo> {
+    if code == 0 {
+        return None;
+    }
+    let (table, unknown, unknown_action) = match (kind, i18n::language()) {
+        (CodeKind::Error, Language::ZhCn) => (ERRORS, "未知错误", UNKNOWN_ACTION),
+        (CodeKind::Warning, Language::ZhCn) => (WARNINGS, "未知警告", UNKNOWN_ACTION),
+        (CodeKind::Error, Language::En) => (ERRORS_EN, "Unknown error", UNKNOWN_ACTION_EN),
+        (CodeKind::Warning, Language::En) => (WARNINGS_EN, "Unknown warning", UNKNOWN_ACTION_EN),
+    };
+    let (description, action) = table
+        .iter()
+        .find(|(c, _, _)| *c == code)
+        .map(|(_, description, action)| (*description, *action))
+        .unwrap_or((unknown, unknown_action));
+    Some(CodeInfo {
+        kind,
+        code,
+        description,
+        action,
+    })
+}
+
+/// 控制器错误码说明, 0 (无错误) 返回 None
+pub fn error_info(code: i64) -> Option<CodeInfo> {
+    lookup(CodeKind::Error, code)
+}
+
+/// 控制器警告码说明, 0 (无警告) 返回 None
+pub fn warning_info(code: i64) -> Option<CodeInfo> {
+    lookup(CodeKind::Warning, code)
+}
+
+/// 全部已知的错误码与警告码
+pub fn catalogue() -> Vec<CodeInfo> {
+    let errors = ERRORS.iter().filter_map(|(code, _, _)| error_info(*code));
+    let warnings = WARNINGS
+        .iter()
+        .filter_map(|(code, _, _)| warning_info(*code));
+    errors.chain(warnings).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_have_same_codes() {
+        let codes =
+            |table: &[(i64, &str, &str)]| table.iter().map(|(c, _, _)| *c).collect::<Vec<_>>();
+        assert_eq!(codes(ERRORS), codes(ERRORS_EN));
+        assert_eq!(codes(WARNINGS), codes(WARNINGS_EN));
+    }
+}
@@ -0,0 +1,146 @@
+// error_history.rs - 连接期间的错误/警告历史, 由设备状态的变化生成
+use crate::{
+    commands::arm_service::{
+        device_status::DeviceStatus,
+        error_codes::{CodeInfo, CodeKind},
+    },
+    utils::i18n,
+};
+use chrono::Local;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+// 每台机械臂保留的历史条数
+const MAX_RECORDS: usize = 500;
+
+/// 错误/警告的出现或清除
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorRecord {
+    pub timestamp: String, // 本地时间 %Y-%m-%d %H:%M:%S%.3f
+    #[serde(flatten)]
+    pub info: CodeInfo,
+    pub cleared: bool,      // false: 出现, true: 清除
+    pub state: Option<i64>, // 发生时的机械臂状态与模式
+    pub mode: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+pub struct ErrorHistory {
+    records: VecDeque<ErrorRecord>,
+    error: Option<CodeInfo>,
+    warning: Option<CodeInfo>,
+}
+
+impl ErrorHistory {
+    /// 对比上次的设备状态, 记录并返回新出现或被清除的错误/警告
+    pub fn update(&mut self, status: &DeviceStatus) -> Vec<ErrorRecord> {
+        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string();
+        let mut changes = vec![];
+        for (previous, current) in [
+            (&mut self.error, status.error),
+            (&mut self.warning, status.warning),
+        ] {
+            if *previous == current {
+                continue;
+            }
+            if let Some(info) = previous.take() {
+                changes.push((info, true));
+            }
+            if let Some(info) = current {
+                changes.push((info, false));
+            }
+            *previous = current;
+        }
+
+        let records: Vec<ErrorRecord> = changes
+            .into_iter()
+            .map(|(info, cleared)| ErrorRecord {
+                timestamp: timestamp.clone(),
+                info,
+                cleared,
+                state: status.state,
+                mode: status.mode,
+            })
+            .collect();
+        for record in &records {
+            if self.records.len() == MAX_RECORDS {
+                self.records.pop_front();
+            }
+            self.records.push_back(record.clone());
+        }
+        records
+    }
+
+    /// 全部历史, 按时间先后
+    pub fn records(&self) -> Vec<ErrorRecord> {
+        self.records.iter().cloned().collect()
+    }
+
+    /// 清空历史, 保留当前的错误状态以免重复记录
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+}
+
+impl ErrorRecord {
+    /// 录制标记的文字 (当前界面语言)
+    pub fn label(&self) -> String {
+        let key = match (self.info.kind, self.cleared) {
+            (CodeKind::Error, false) => "device.error",
+            (CodeKind::Error, true) => "device.error_cleared",
+            (CodeKind::Warning, false) => "device.warning",
+            (CodeKind::Warning, true) => "device.warning_cleared",
+        };
+        i18n::t_args(
+            key,
+            &[
+                ("code", self.info.code.to_string()),
+                ("description", self.info.description.to_string()),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::arm_service::error_codes::{error_info, warning_info};
+
+    #[test]
+    fn test_error_transitions() {
+        let mut history = ErrorHistory::default();
+        let mut status = DeviceStatus {
+            state: Some(2),
+            ..Default::default()
+        };
+        assert!(history.update(&status).is_empty());
+
+        status.state = Some(4);
+        status.error = error_info(22);
+        status.warning = warning_info(11);
+        let records = history.update(&status);
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| !r.cleared && r.state == Some(4)));
+        assert_eq!(records[0].info.code, 22);
+        assert_eq!(records[0].info.kind, CodeKind::Error);
+        assert_eq!(records[0].label(), "错误 22: 自碰撞错误");
+        // 状态不变不重复记录
+        assert!(history.update(&status).is_empty());
+
+        // 错误码直接切换时记录旧错误清除与新错误出现
+        status.error = error_info(31);
+        let records = history.update(&status);
+        assert_eq!(records.len(), 2);
+        assert!(records[0].cleared && records[0].info.code == 22);
+        assert!(!records[1].cleared && records[1].info.code == 31);
+
+        status.error = None;
+        status.warning = None;
+        assert_eq!(history.update(&status).len(), 2);
+        assert_eq!(history.records().len(), 6);
+
+        history.clear();
+        assert!(history.records().is_empty());
+        assert!(history.update(&status).is_empty());
+    }
+}
@@ -0,0 +1,721 @@
+// lifecycle.rs - 机械臂连接生命周期状态机
+//
+// 每台机械臂一个 actor 线程, 独占采集客户端与录制的生命周期, 按顺序处理
+// 连接/断开/开始观测/停止观测等命令; 观测超时与断线重连也由该线程处理,
+// 不再由多个线程分别修改连接状态
+//
+// Disconnected -> Connecting -> Connected <-> Observing
+//                     |             |             |
+//                     v             v  (断线)      v
+//                   Error <- Reconnecting <-------+
+use crate::{
+    commands::{arm_service::structs::ObserveParams, presets::runtime::PresetRuntime},
+    utils::error::AppError,
+};
+use serde::Serialize;
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+
+/// 连接状态
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Observing,
+    Reconnecting { attempt: u32 },
+    Error { message: String },
+}
+
+impl ConnectionState {
+    /// 已连接 (含观测中)
+    pub fn is_connected(&self) -> bool {
+        matches!(
+            self,
+            ConnectionState::Connected | ConnectionState::Observing
+        )
+    }
+}
+
+/// 连接与采集的具体实现, 只在 actor 线程中调用
+pub trait RobotBackend: Send + 'static {
+    /// 建立连接并启动采集, 采集异常结束时调用 `lost.notify`
+    fn connect(&mut self, ip: &str, lost: LostSignal) -> Result<(), String>;
+    /// 停止采集并断开, 清理录制
+    fn disconnect(&mut self);
+    /// 开始观测/录制
+    fn start_observe(
+        &mut self,
+        params: ObserveParams,
+        preset: Option<PresetRuntime>,
+    ) -> Result<(), String>;
+    /// 停止观测
+    fn stop_observe(&mut self);
+    /// 归档本次录制, 在回复停止观测的命令之后调用
+    fn archive(&mut self);
+}
+
+#[derive(Debug, Clone)]
+pub struct LifecycleConfig {
+    pub reconnect_interval: Duration,
+    pub max_reconnect_attempts: u32, // 为 0 时断线后直接进入 Error
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        Self {
+            reconnect_interval: Duration::from_secs(2),
+            max_reconnect_attempts: 5,
+        }
+    }
+}
+
+/// 观测超时的上限 (s)
+pub const MAX_OBSERVE_TIMEOUT: u64 = 30 * 24 * 3600;
+
+type Reply = oneshot::Sender<Result<(), AppError>>;
+
+#[derive(Debug)]
+enum Command {
+    Connect {
+        ip: String,
+        reply: Reply,
+    },
+    Disconnect {
+        reply: Reply,
+    },
+    StartObserve {
+        params: ObserveParams,
+        preset: Option<PresetRuntime>,
+        reply: Reply,
+    },
+    StopObserve {
+        reply: Reply,
+    },
+    ConnectionLost {
+        generation: u64,
+        message: String,
+    },
+    Shutdown,
+}
+
+/// 采集线程通知连接断开; 已被新连接取代的通知会被忽略
+#[derive(Debug, Clone)]
+pub struct LostSignal {
+    tx: mpsc::Sender<Command>,
+    generation: u64,
+}
+
+impl LostSignal {
+    pub fn notify(&self, message: &str) {
+        let _ = self.tx.send(Command::ConnectionLost {
+            generation: self.generation,
+            message: message.to_string(),
+        });
+    }
+}
+
+/// 等待中的命令结果
+#[derive(Debug)]
+pub struct Pending(oneshot::Receiver<Result<(), AppError>>);
+
+impl Pending {
+    /// 同步等待, 不能在异步运行时中调用
+    pub fn wait(self) -> Result<(), AppError> {
+        self.0
+            .blocking_recv()
+            .unwrap_or(Err(AppError::ConnectionActorStopped))
+    }
+
+    pub async fn result(self) -> Result<(), AppError> {
+        self.0
+            .await
+            .unwrap_or(Err(AppError::ConnectionActorStopped))
+    }
+}
+
+/// 连接 actor 的句柄, 释放时断开连接并结束线程
+#[derive(Debug)]
+pub struct ConnectionActor {
+    tx: mpsc::Sender<Command>,
+    state: Arc<RwLock<ConnectionState>>,
+}
+
+impl ConnectionActor {
+    /// 启动 actor 线程, 每次状态变化调用 `on_transition`
+    pub fn spawn<B, F>(backend: B, config: LifecycleConfig, on_transition: F) -> Self
+    where
+        B: RobotBackend,
+        F: Fn(&ConnectionState) + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let state = Arc::new(RwLock::new(ConnectionState::Disconnected));
+        let actor = Actor {
+            backend,
+            config,
+            on_transition,
+            state: state.clone(),
+            tx: tx.clone(),
+            ip: String::new(),
+            generation: 0,
+            observe_deadline: None,
+            reconnect_at: None,
+        };
+        thread::spawn(move || actor.run(rx));
+        Self { tx, state }
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+            .read()
+            .map(|state| state.clone())
+            .unwrap_or(ConnectionState::Disconnected)
+    }
+
+    /// 连接; 已连接时返回 AlreadyConnected
+    pub fn connect(&self, ip: &str) -> Pending {
+        let ip = ip.to_string();
+        self.request(|reply| Command::Connect { ip, reply })
+    }
+
+    pub fn disconnect(&self) -> Pending {
+        self.request(|reply| Command::Disconnect { reply })
+    }
+
+    /// 开始观测, `params.timeout` 秒后自动停止
+    pub fn start_observe(&self, params: ObserveParams, preset: Option<PresetRuntime>) -> Pending {
+        self.request(|reply| Command::StartObserve {
+            params,
+            preset,
+            reply,
+        })
+    }
+
+    pub fn stop_observe(&self) -> Pending {
+        self.request(|reply| Command::StopObserve { reply })
+    }
+
+    fn request(&self, command: impl FnOnce(Reply) -> Command) -> Pending {
+        let (reply, rx) = oneshot::channel();
+        // 发送失败时 reply 随命令一起释放, 等待方得到 ConnectionActorStopped
+        let _ = self.tx.send(command(reply));
+        Pending(rx)
+    }
+}
+
+impl Drop for ConnectionActor {
+    fn drop(&mut self) {
+        let _ = self.tx.send(Command::Shutdown);
+    }
+}
+
+struct Actor<B, F> {
+    backend: B,
+    config: LifecycleConfig,
+    on_transition: F,
+    state: Arc<RwLock<ConnectionState>>,
+    tx: mpsc::Sender<Command>,
+    ip: String,
+    generation: u64, // 每次建立连接加一, 用于识别过期的断线通知
+    observe_deadline: Option<Instant>,
+    reconnect_at: Option<Instant>,
+}
+
+impl<B, F> Actor<B, F>
+where
+    B: RobotBackend,
+    F: Fn(&ConnectionState) + Send + 'static,
+{
+    fn run(mut self, rx: mpsc::Receiver<Command>) {
+        loop {
+            let deadline = [self.observe_deadline, self.reconnect_at]
+                .into_iter()
+                .flatten()
+                .min();
+            let command = match deadline {
+                Some(deadline) => {
+                    match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                        Ok(command) => Some(command),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                None => match rx.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => break,
+                },
+            };
+
+            match command {
+                Some(Command::Connect { ip, reply }) => {
+                    let _ = reply.send(self.connect(&ip));
+                }
+                Some(Command::Disconnect { reply }) => {
+                    let _ = reply.send(self.disconnect());
+                }
+                Some(Command::StartObserve {
+                    params,
+                    preset,
+                    reply,
+                }) => {
+                    let _ = reply.send(self.start_observe(params, preset));
+                }
+                Some(Command::StopObserve { reply }) => {
+                    // 先回复再归档, 调用方不等待大文件的归档
+                    let result = self.stop_observe();
+                    let stopped = result.is_ok();
+                    let _ = reply.send(result);
+                    if stopped {
+                        self.backend.archive();
+                    }
+                }
+                Some(Command::ConnectionLost {
+                    generation,
+                    message,
+                }) => self.connection_lost(generation, &message),
+                Some(Command::Shutdown) => break,
+                None => self.on_timer(),
+            }
+        }
+
+        if self.current() != ConnectionState::Disconnected {
+            let _ = self.disconnect();
+        }
+    }
+
+    fn current(&self) -> ConnectionState {
+        self.state
+            .read()
+            .map(|state| state.clone())
+            .unwrap_or(ConnectionState::Disconnected)
+    }
+
+    fn set_state(&mut self, state: ConnectionState) {
+        if self.current() == state {
+            return;
+        }
+        if let Ok(mut current) = self.state.write() {
+            *current = state.clone();
+        }
+        (self.on_transition)(&state);
+    }
+
+    fn open(&mut self) -> Result<(), AppError> {
+        self.generation += 1;
+        let lost = LostSignal {
+            tx: self.tx.clone(),
+            generation: self.generation,
+        };
+        self.backend
+            .connect(&self.ip, lost)
+            .map_err(|reason| AppError::ConnectFailed {
+                ip: self.ip.clone(),
+                reason,
+            })
+    }
+
+    fn connect(&mut self, ip: &str) -> Result<(), AppError> {
+        match self.current() {
+            ConnectionState::Connected | ConnectionState::Observing => {
+                return Err(AppError::AlreadyConnected(self.ip.clone()))
+            }
+            // 重连中收到连接命令时以新命令为准
+            _ => self.reconnect_at = None,
+        }
+
+        self.ip = ip.to_string();
+        self.set_state(ConnectionState::Connecting);
+        match self.open() {
+            Ok(()) => {
+                self.set_state(ConnectionState::Connected);
+                Ok(())
+            }
+            Err(e) => {
+                self.set_state(ConnectionState::Error {
+                    message: e.to_string(),
+                });
+                Err(e)
+            }
+        }
+    }
+
+    fn disconnect(&mut self) -> Result<(), AppError> {
+        match self.current() {
+            ConnectionState::Disconnected => return Err(AppError::RobotNotConnected),
+            ConnectionState::Observing => {
+                self.backend.stop_observe();
+                self.backend.disconnect();
+            }
+            ConnectionState::Connected => self.backend.disconnect(),
+            // 重连中或出错时采集已经停止
+            _ => {}
+        }
+
+        self.observe_deadline = None;
+        self.reconnect_at = None;
+        self.set_state(ConnectionState::Disconnected);
+        Ok(())
+    }
+
+    fn start_observe(
+        &mut self,
+        params: ObserveParams,
+        preset: Option<PresetRuntime>,
+    ) -> Result<(), AppError> {
+        match self.current() {
+            ConnectionState::Connected => {}
+            ConnectionState::Observing => return Err(AppError::ObserverRunning),
+            _ => return Err(AppError::RobotNotConnected),
+        }
+
+        // 超时来自前端与 REST 接口, 溢出会使 actor 线程 panic
+        let deadline = Some(params.timeout)
+            .filter(|timeout| *timeout <= MAX_OBSERVE_TIMEOUT)
+            .and_then(|timeout| Instant::now().checked_add(Duration::from_secs(timeout)))
+            .ok_or_else(|| AppError::InvalidArgument {
+                name: "timeout".to_string(),
+                reason: format!("must be at most {} s", MAX_OBSERVE_TIMEOUT),
+            })?;
+        self.backend.start_observe(params, preset)?;
+        self.observe_deadline = Some(deadline);
+        self.set_state(ConnectionState::Observing);
+        Ok(())
+    }
+
+    fn stop_observe(&mut self) -> Result<(), AppError> {
+        match self.current() {
+            ConnectionState::Observing => {}
+            ConnectionState::Connected => return Err(AppError::ObserverNotRunning),
+            _ => return Err(AppError::RobotNotConnected),
+        }
+
+        self.backend.stop_observe();
+        self.observe_deadline = None;
+        self.set_state(ConnectionState::Connected);
+        Ok(())
+    }
+
+    fn connection_lost(&mut self, generation: u64, message: &str) {
+        let state = self.current();
+        if generation != self.generation || !state.is_connected() {
+            return;
+        }
+
+        if state == ConnectionState::Observing {
+            self.backend.stop_observe();
+        }
+        self.backend.disconnect();
+        self.observe_deadline = None;
+
+        if self.config.max_reconnect_attempts == 0 {
+            self.set_state(ConnectionState::Error {
+                message: message.to_string(),
+            });
+            return;
+        }
+        self.reconnect_at = Some(Instant::now() + self.config.reconnect_interval);
+        self.set_state(ConnectionState::Reconnecting { attempt: 1 });
+    }
+
+    fn on_timer(&mut self) {
+        let now = Instant::now();
+        if self
+            .observe_deadline
+            .is_some_and(|deadline| deadline <= now)
+            && self.stop_observe().is_ok()
+        {
+            self.backend.archive();
+        }
+        if self.reconnect_at.is_some_and(|at| at <= now) {
+            self.reconnect();
+        }
+    }
+
+    fn reconnect(&mut self) {
+        self.reconnect_at = None;
+        let ConnectionState::Reconnecting { attempt } = self.current() else {
+            return;
+        };
+
+        match self.open() {
+            Ok(()) => self.set_state(ConnectionState::Connected),
+            Err(e) if attempt >= self.config.max_reconnect_attempts => {
+                self.set_state(ConnectionState::Error {
+                    message: format!("Reconnect failed after {} attempts: {}", attempt, e),
+                });
+            }
+            Err(_) => {
+                self.reconnect_at = Some(Instant::now() + self.config.reconnect_interval);
+                self.set_state(ConnectionState::Reconnecting {
+                    attempt: attempt + 1,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    /// 记录调用顺序, 重复连接/断开等非法调用记为 "invalid"
+    #[derive(Clone, Default)]
+    struct FakeBackend {
+        calls: Arc<Mutex<Vec<&'static str>>>,
+        open: Arc<AtomicBool>,
+        observing: Arc<AtomicBool>,
+        connect_failures: Arc<AtomicUsize>,
+        slow_archive: Arc<AtomicBool>,
+        lost: Arc<Mutex<Option<LostSignal>>>,
+    }
+
+    impl FakeBackend {
+        fn record(&self, call: &'static str, valid: bool) {
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(if valid { call } else { "invalid" });
+        }
+
+        fn calls(&self) -> Vec<&'static str> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl RobotBackend for FakeBackend {
+        fn connect(&mut self, _ip: &str, lost: LostSignal) -> Result<(), String> {
+            thread::sleep(Duration::from_millis(2));
+            if self.connect_failures.load(Ordering::SeqCst) > 0 {
+                self.connect_failures.fetch_sub(1, Ordering::SeqCst);
+                self.record("connect_failed", !self.open.load(Ordering::SeqCst));
+                return Err("connection refused".to_string());
+            }
+            self.record("connect", !self.open.swap(true, Ordering::SeqCst));
+            *self.lost.lock().unwrap() = Some(lost);
+            Ok(())
+        }
+
+        fn disconnect(&mut self) {
+            let valid = !self.observing.load(Ordering::SeqCst);
+            self.record(
+                "disconnect",
+                self.open.swap(false, Ordering::SeqCst) && valid,
+            );
+        }
+
+        fn start_observe(
+            &mut self,
+            _params: ObserveParams,
+            _preset: Option<PresetRuntime>,
+        ) -> Result<(), String> {
+            let valid = self.open.load(Ordering::SeqCst);
+            self.record(
+                "start",
+                !self.observing.swap(true, Ordering::SeqCst) && valid,
+            );
+            Ok(())
+        }
+
+        fn stop_observe(&mut self) {
+            self.record("stop", self.observing.swap(false, Ordering::SeqCst));
+        }
+
+        fn archive(&mut self) {
+            if self.slow_archive.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(500));
+            }
+            self.record("archive", !self.observing.load(Ordering::SeqCst));
+        }
+    }
+
+    fn spawn(backend: &FakeBackend, config: LifecycleConfig) -> Arc<ConnectionActor> {
+        Arc::new(ConnectionActor::spawn(backend.clone(), config, |_| {}))
+    }
+
+    fn params(timeout: u64) -> ObserveParams {
+        ObserveParams {
+            timeout,
+            ..Default::default()
+        }
+    }
+
+    fn wait_for(actor: &ConnectionActor, expected: ConnectionState) {
+        for _ in 0..200 {
+            if actor.state() == expected {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("expected {:?}, got {:?}", expected, actor.state());
+    }
+
+    #[test]
+    fn test_concurrent_commands() {
+        let backend = FakeBackend::default();
+        let actor = spawn(&backend, LifecycleConfig::default());
+        actor.connect("127.0.0.1").wait().unwrap();
+        assert!(matches!(
+            actor.connect("127.0.0.1").wait(),
+            Err(AppError::AlreadyConnected(_))
+        ));
+
+        // 同时开始观测只有一个成功
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let actor = actor.clone();
+                thread::spawn(move || actor.start_observe(params(60), None).wait())
+            })
+            .collect();
+        let started = handles
+            .into_iter()
+            .filter_map(|h| h.join().unwrap().ok())
+            .count();
+        assert_eq!(started, 1);
+        assert_eq!(actor.state(), ConnectionState::Observing);
+
+        // 多个线程随机执行命令, 后端收到的调用顺序始终合法
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let actor = actor.clone();
+                thread::spawn(move || {
+                    for j in 0..25 {
+                        let _ = match (i + j) % 4 {
+                            0 => actor.connect("127.0.0.1").wait(),
+                            1 => actor.start_observe(params(60), None).wait(),
+                            2 => actor.stop_observe().wait(),
+                            _ => actor.disconnect().wait(),
+                        };
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let _ = actor.disconnect().wait();
+        assert_eq!(actor.state(), ConnectionState::Disconnected);
+        let calls = backend.calls();
+        assert!(!calls.contains(&"invalid"), "{:?}", calls);
+        assert_eq!(
+            calls.iter().filter(|c| **c == "connect").count(),
+            calls.iter().filter(|c| **c == "disconnect").count()
+        );
+    }
+
+    #[test]
+    fn test_observe_timeout_does_not_outlive_restart() {
+        let backend = FakeBackend::default();
+        let actor = spawn(&backend, LifecycleConfig::default());
+        actor.connect("127.0.0.1").wait().unwrap();
+
+        actor.start_observe(params(1), None).wait().unwrap();
+        thread::sleep(Duration::from_millis(500));
+        actor.stop_observe().wait().unwrap();
+        actor.start_observe(params(1), None).wait().unwrap();
+
+        // 第一次观测的超时时间已过, 第二次观测不受影响
+        thread::sleep(Duration::from_millis(700));
+        assert_eq!(actor.state(), ConnectionState::Observing);
+        wait_for(&actor, ConnectionState::Connected);
+        assert_eq!(
+            backend.calls(),
+            ["connect", "start", "stop", "archive", "start", "stop", "archive"]
+        );
+    }
+
+    #[test]
+    fn test_reject_out_of_range_timeout() {
+        let backend = FakeBackend::default();
+        let actor = spawn(&backend, LifecycleConfig::default());
+        actor.connect("127.0.0.1").wait().unwrap();
+
+        assert!(matches!(
+            actor.start_observe(params(u64::MAX), None).wait(),
+            Err(AppError::InvalidArgument { .. })
+        ));
+        assert_eq!(actor.state(), ConnectionState::Connected);
+
+        // actor 线程仍在运行
+        actor.start_observe(params(60), None).wait().unwrap();
+        assert_eq!(backend.calls(), ["connect", "start"]);
+    }
+
+    #[test]
+    fn test_stop_replies_before_archive() {
+        let backend = FakeBackend::default();
+        backend.slow_archive.store(true, Ordering::SeqCst);
+        let actor = spawn(&backend, LifecycleConfig::default());
+        actor.connect("127.0.0.1").wait().unwrap();
+        actor.start_observe(params(60), None).wait().unwrap();
+
+        let started = Instant::now();
+        actor.stop_observe().wait().unwrap();
+        assert!(started.elapsed() < Duration::from_millis(250));
+        assert_eq!(actor.state(), ConnectionState::Connected);
+
+        // 归档完成前的命令排在归档之后执行
+        actor.start_observe(params(60), None).wait().unwrap();
+        assert_eq!(
+            backend.calls(),
+            ["connect", "start", "stop", "archive", "start"]
+        );
+    }
+
+    #[test]
+    fn test_reconnect_after_connection_lost() {
+        let backend = FakeBackend::default();
+        let transitions = Arc::new(Mutex::new(vec![]));
+        let recorded = transitions.clone();
+        let config = LifecycleConfig {
+            reconnect_interval: Duration::from_millis(20),
+            max_reconnect_attempts: 2,
+        };
+        let actor = ConnectionActor::spawn(backend.clone(), config, move |state| {
+            recorded.lock().unwrap().push(state.clone());
+        });
+        actor.connect("127.0.0.1").wait().unwrap();
+        actor.start_observe(params(60), None).wait().unwrap();
+
+        let first = backend.lost.lock().unwrap().clone().unwrap();
+        first.notify("connection reset");
+        wait_for(&actor, ConnectionState::Connected);
+        assert_eq!(
+            backend.calls(),
+            ["connect", "start", "stop", "disconnect", "connect"]
+        );
+
+        // 旧连接的断线通知被忽略
+        first.notify("connection reset");
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(actor.state(), ConnectionState::Connected);
+
+        // 重连次数用完后进入 Error, 之后仍可重新连接
+        backend.connect_failures.store(2, Ordering::SeqCst);
+        let second = backend.lost.lock().unwrap().clone().unwrap();
+        second.notify("connection reset");
+        for _ in 0..200 {
+            if matches!(actor.state(), ConnectionState::Error { .. }) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(matches!(actor.state(), ConnectionState::Error { .. }));
+        actor.connect("127.0.0.1").wait().unwrap();
+
+        let transitions = transitions.lock().unwrap().clone();
+        assert_eq!(transitions[0], ConnectionState::Connecting);
+        assert!(transitions.contains(&ConnectionState::Reconnecting { attempt: 2 }));
+        assert_eq!(transitions.last(), Some(&ConnectionState::Connected));
+    }
+}
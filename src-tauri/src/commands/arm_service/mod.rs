@@ -1,547 +1,544 @@
 mod connection;
 pub mod csv_exporter;
-mod parser;
+pub mod device_status;
+pub mod discovery;
+pub mod error_codes;
+pub mod error_history;
+pub mod lifecycle;
+pub mod parser;
 pub mod robot_client;
-mod robot_data;
+pub mod robot_data;
+pub mod session_backend;
 pub mod structs;
 pub mod ws_get;
 
 use std::{
     path::PathBuf,
-    sync::{atomic::Ordering, Arc, Mutex},
-    thread,
-    time::Duration,
+    sync::{atomic::Ordering, Arc},
 };
 
-use robot_client::RobotClient;
+use serde::Serialize;
 use serde_json::Value;
 use tauri::{Emitter, Manager};
 
 use crate::{
     commands::arm_service::{
-        csv_exporter::CsvExporter,
-        ws_get::{ws_connect_state, ws_get_data},
+        device_status::{DeviceStatusMonitor, DeviceStatusReport},
+        discovery::{discover, DiscoveredRobot, DiscoveryParams},
+        error_codes::CodeInfo,
+        error_history::{ErrorHistory, ErrorRecord},
+        lifecycle::ConnectionState,
+        ws_get::{ws_connect_state, ws_get_data, WS_PORT},
     },
+    commands::presets::runtime::PresetRuntime,
+    commands::sessions::{
+        current_session_meta,
+        export::{export_files, ExportFormat, ExportMeta},
+    },
+    commands::streaming::update_connection_metric,
     result_response,
-    state::app_state::{AppState, SharedState},
-    utils::response::Response,
+    state::app_state::{AppState, RobotEvent, RobotSession, SharedState},
+    utils::{error::AppError, response::Response},
 };
 
 // 机械臂TCP端口
-const ROBOT_PORT: u16 = 30000;
-// 断开连接延迟时间 s
-const DISCONNECT_DELAY_MS: u64 = 100;
-// 观察者检查间隔时间
-const OBSERVER_CHECK_INTERVAL_MS: u64 = 200;
+pub const ROBOT_PORT: u16 = 30000;
+
+/// 机械臂列表项
+#[derive(Debug, Clone, Serialize)]
+pub struct RobotSummary {
+    pub robot_id: String,
+    pub ip: String,
+    pub connected: bool,
+    pub connection_state: ConnectionState,
+    pub shared_state: SharedState,
+}
+
+/// 已连接的机械臂
+#[tauri::command]
+pub fn list_robots(state: tauri::State<AppState>) -> Response<Vec<RobotSummary>> {
+    let robots = state
+        .robot_sessions()
+        .iter()
+        .map(|robot| RobotSummary {
+            robot_id: robot.id.clone(),
+            ip: robot
+                .robot_server
+                .read()
+                .map(|lock| lock.ip.clone())
+                .unwrap_or_default(),
+            connected: robot.connected(),
+            connection_state: robot.lifecycle.state(),
+            shared_state: robot
+                .shared_state
+                .read()
+                .map(|s| s.clone())
+                .unwrap_or_default(),
+        })
+        .collect();
+    Response::success(robots)
+}
 
-// 连接机械臂服务器
+/// 扫描局域网内的控制器, 参数省略时扫描本机所在网段
+#[tauri::command(async)]
+pub async fn discover_robots(
+    state: tauri::State<'_, AppState>,
+    params: Option<DiscoveryParams>,
+) -> Result<Response<Vec<DiscoveredRobot>>, Response<String>> {
+    let params = DiscoveryParams {
+        lang: state.language().ws_lang().to_string(),
+        ..params.unwrap_or_default()
+    };
+    Ok(discover(&params).await.into())
+}
+
+// 连接机械臂服务器, `robot_id` 省略时以 IP 作为机械臂 ID
 #[tauri::command(async)]
 pub async fn connect_robot_server<R: tauri::Runtime>(
     app: tauri::AppHandle<R>,
     state: tauri::State<'_, AppState>,
     ip_addr: &str,
+    robot_id: Option<String>,
 ) -> Result<Response<String>, Response<String>> {
     if !ws_connect_state(ip_addr).await {
-        return Ok(Response::error("ws连接失败"));
+        return Ok(AppError::ControllerUnreachable(ip_addr.to_string()).into());
     }
 
-    let result = async || {
-        // 首先检查连接状态
-        {
-            let robot_lock = state
-                .robot_server
-                .read()
-                .map_err(|e| format!("Failed to acquire robot server read lock: {:?}", e))?;
+    let result = async || -> Result<String, AppError> {
+        let robot_id = robot_id.clone().unwrap_or_else(|| ip_addr.to_string());
+        let robot = state.robot_entry(&robot_id)?;
 
-            if robot_lock.connected {
+        /********* 由连接状态机建立 socket 连接并启动采集线程 *********/
+        // 状态机按顺序处理连接命令, 并发连接时只有第一个成功
+        match robot.lifecycle.connect(ip_addr).result().await {
+            Ok(()) => {}
+            Err(AppError::AlreadyConnected(ip)) if ip == ip_addr => {
                 return Ok("Server is already running".to_string());
             }
+            Err(e @ AppError::AlreadyConnected(_)) => return Err(e),
+            Err(e) => {
+                state.remove_robot(&robot.id)?;
+                return Err(e);
+            }
         }
 
-        // 更新 ws_ip (单独获取锁)
-        *state
-            .ws_ip
-            .write()
-            .map_err(|e| format!("Failed to acquire ws_ip lock: {:?}", e))? = ip_addr.to_string();
-
-        /********* socket 读取并推送到前端 *********/
-        // 配置参数
-        let robot_ip = format!("{}:{}", ip_addr, ROBOT_PORT);
-        let client = RobotClient::new(robot_ip)
-            .map_err(|e| format!("Failed to create RobotClient: {:?}", e))?;
-
-        /*************************************** 初始化csv导出器 *********************/
-        // 先获取 robot_lock 来访问 csv_exporter
-        let csv_exporter_arc = {
-            let robot_lock = state
-                .robot_server
-                .read()
-                .map_err(|e| format!("Failed to acquire robot server read lock: {:?}", e))?;
-            robot_lock.csv_exporter.clone()
-        };
+        // 任一步骤失败时断开并移除会话, 不留下初始化了一半的机械臂
+        if let Err(e) = init_connected_robot(&app, &state, &robot, ip_addr).await {
+            let _ = robot.lifecycle.disconnect().result().await;
+            state.remove_robot(&robot.id)?;
+            update_connection_metric(&state);
+            return Err(e);
+        }
 
-        // 单独初始化 csv_exporter (避免嵌套锁)
-        {
-            let mut csv_exporter = csv_exporter_arc
-                .write()
-                .map_err(|e| format!("Failed to acquire csv_exporter lock: {:?}", e))?;
+        update_connection_metric(&state);
+        if let Err(e) = state.update_settings(|s| s.remember_robot(ip_addr)) {
+            eprintln!("Failed to save connection history: {}", e);
+        }
 
-            // 使用用户数据目录
-            let csv_temp_dir = state.user_data_paths.csv_temp.clone();
-            let exporter = CsvExporter::new(csv_temp_dir)
-                .map_err(|e| format!("Failed to create CSV exporter: {:?}", e))?;
+        Ok("Robot server connected successfully".to_string())
+    };
 
-            *csv_exporter = Some(exporter);
-        }
+    result_response!(result().await)
+}
 
-        // 获取所有需要的 Arc 克隆
-        let (stop_flag, observer_running, observe_params, csv_exporter) = {
-            let robot_lock = state
-                .robot_server
-                .read()
-                .map_err(|e| format!("Failed to acquire robot server read lock: {:?}", e))?;
+/// 连接建立后读取轴数等状态并订阅设备状态
+async fn init_connected_robot<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    state: &AppState,
+    robot: &Arc<RobotSession>,
+    ip_addr: &str,
+) -> Result<(), AppError> {
+    /*************************** 读取并更新shared_state *************************** */
+    let wd = ws_get_data(ip_addr, state.language().ws_lang())
+        .await
+        .map_err(|e| AppError::DeviceStatusUnavailable(e.to_string()))?;
+
+    let mut shared_state = robot
+        .shared_state
+        .read()
+        .map_err(|_| AppError::LockPoisoned("shared_state"))?
+        .clone();
+
+    shared_state.axis = wd.axis;
+    shared_state.ft_sensor = wd.ft_sensor;
+
+    robot.set_shared_state(shared_state)?;
+    robot.push_shared_state()?;
+
+    /******************** 连接期间订阅设备状态, 变化时推送到前端 ******************** */
+    let error_history = robot
+        .robot_server
+        .read()
+        .map_err(|_| AppError::LockPoisoned("robot_server"))?
+        .error_history
+        .clone();
+    *error_history
+        .write()
+        .map_err(|_| AppError::LockPoisoned("error_history"))? = ErrorHistory::default();
+
+    let session = Arc::downgrade(robot);
+    let ah = app.app_handle().clone();
+    let monitor = DeviceStatusMonitor::start(
+        ip_addr,
+        WS_PORT,
+        state.language().ws_lang(),
+        move |report| {
+            let Some(robot) = session.upgrade() else {
+                return;
+            };
+            let _ = ah.emit(
+                "ROBOT_DEVICE_STATUS",
+                RobotEvent {
+                    robot_id: &robot.id,
+                    payload: report,
+                },
+            );
+
+            record_errors(&ah, &robot, report);
+
+            // 轴数与力传感器状态可能在连接后变化 (如开启力传感器)
+            let Ok(mut shared_state) = robot.shared_state.read().map(|s| s.clone()) else {
+                return;
+            };
+            let axis = match report.status.axis {
+                0 => shared_state.axis,
+                axis => axis,
+            };
+            if shared_state.axis != axis || shared_state.ft_sensor != report.status.ft_sensor {
+                shared_state.axis = axis;
+                shared_state.ft_sensor = report.status.ft_sensor;
+                let _ = robot.set_shared_state(shared_state);
+                let _ = robot.push_shared_state();
+            }
+        },
+    )?;
+    robot
+        .robot_server
+        .write()
+        .map_err(|_| AppError::LockPoisoned("robot_server"))?
+        .status_monitor = Some(monitor);
+
+    Ok(())
+}
 
+/// 记录错误/警告的变化, 推送 ROBOT_ERROR 事件, 录制中时在录制里添加标记
+fn record_errors<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    robot: &RobotSession,
+    report: &DeviceStatusReport,
+) {
+    let Ok((error_history, observer_running, csv_exporter)) =
+        robot.robot_server.read().map(|robot_lock| {
             (
-                robot_lock.stop_flag.clone(),
+                robot_lock.error_history.clone(),
                 robot_lock.observer_running.clone(),
-                robot_lock.observe_params.clone(),
                 robot_lock.csv_exporter.clone(),
             )
-        };
-
-        // 使用 Arc<Mutex<RobotClient>> 实现线程安全的共享
-        let client_arc = Arc::new(Mutex::new(client));
-        let client_clone = client_arc.clone();
-
-        stop_flag.store(false, Ordering::Relaxed);
-
-        let ah = app.app_handle().clone();
-        let handler = thread::spawn(move || {
-            // 从 Arc 中获取客户端
-            let mut client = client_clone
-                .lock()
-                .expect("Failed to lock RobotClient in data collection thread");
-            let result = client.collect_data(stop_flag, observer_running, observe_params, |rp| {
-                // 发送事件
-                if let Ok(packet) = rp {
-                    let _ = ah.emit("ROBOT_TCP_DATA", &packet.data);
-                    // 写入csv文件
-                    if packet.csv {
-                        if let Ok(mut csv_exporter_guard) = csv_exporter.write() {
-                            if let Some(csv_exporter) = csv_exporter_guard.as_mut() {
-                                if let Err(e) = csv_exporter.write_packet(&packet.data) {
-                                    eprintln!("Failed to write packet to CSV: {:?}", e);
-                                }
-                            }
-                        }
-                    }
-                } else if let Err(e) = rp {
-                    eprintln!("Failed to collect data: {:?}", e);
-                }
-                Ok(())
-            });
-
-            // 如果数据采集因错误退出，发送断开连接事件到前端
-            if let Err(ref e) = result {
-                eprintln!("数据采集线程异常退出: {}", e);
-                // 机械臂通信断开，请检查机械臂是否正常连接 英文
-                let _ = ah.emit("ROBOT_CONNECTION_LOST", e.to_string());
-            } else {
-                println!("数据采集线程正常结束");
-            }
-
-            result
-        });
-
-        // 最后更新 robot_lock 状态
-        {
-            let mut robot_lock = state
-                .robot_server
-                .write()
-                .map_err(|e| format!("Failed to acquire robot server write lock: {:?}", e))?;
-
-            robot_lock.ip = ip_addr.to_string();
-            robot_lock.connected = true;
-            robot_lock.socket = Some(client_arc);
-            robot_lock.handle = Some(handler);
-        }
-
-        /*************************** 读取并更新shared_state *************************** */
-        let wd = ws_get_data(ip_addr)
-            .await
-            .map_err(|e| format!("Failed to get ws data: {:?}", e))?;
-
-        let mut shared_state = state
-            .shared_state
-            .read()
-            .map_err(|e| format!("Failed to acquire shared_state read lock: {:?}", e))?
-            .clone();
-
-        shared_state.arm_conn = true;
-        shared_state.axis = wd.axis;
-        shared_state.ft_sensor = wd.ft_sensor;
-        shared_state.observering = false;
-
-        state
-            .set_shared_state(shared_state)
-            .map_err(|e| format!("Failed to update shared state: {:?}", e))?;
-
-        state
-            .push_shared_state()
-            .map_err(|e| format!("Failed to push shared state: {:?}", e))?;
+        })
+    else {
+        return;
+    };
 
-        Ok("Robot server connected successfully".to_string())
+    let records = match error_history.write() {
+        Ok(mut history) => history.update(&report.status),
+        Err(_) => return,
     };
 
-    result_response!(result().await)
+    for record in records {
+        let _ = app.emit(
+            "ROBOT_ERROR",
+            RobotEvent {
+                robot_id: &robot.id,
+                payload: &record,
+            },
+        );
+
+        if !observer_running.load(Ordering::Relaxed) {
+            continue;
+        }
+        if let Ok(mut csv_exporter_guard) = csv_exporter.write() {
+            if let Some(csv_exporter) = csv_exporter_guard.as_mut() {
+                csv_exporter.add_marker(&record.label(), Some(record.info.code));
+            }
+        }
+    }
 }
 
 #[tauri::command(async)]
 pub async fn disconnect_robot_server(
     state: tauri::State<'_, AppState>,
+    robot_id: Option<String>,
 ) -> Result<Response<String>, Response<String>> {
-    let result = async || {
-        // 先检查连接状态
-        {
-            let robot_lock = state
-                .robot_server
-                .read()
-                .map_err(|e| format!("Failed to acquire robot server read lock: {:?}", e))?;
-
-            if !robot_lock.connected {
-                return Err("Server is not running".to_string());
-            }
-        }
+    let result = async || -> Result<String, AppError> {
+        let robot = state.robot(robot_id.as_deref())?;
 
-        // 设置停止标志
-        {
-            let robot_lock = state
-                .robot_server
-                .read()
-                .map_err(|e| format!("Failed to acquire robot server read lock: {:?}", e))?;
-
-            robot_lock.stop_flag.store(true, Ordering::Relaxed);
-            robot_lock.observer_running.store(false, Ordering::Relaxed);
-        }
+        // 停止采集, 归档录制并清理临时文件 (由连接状态机完成)
+        robot.lifecycle.disconnect().result().await?;
 
-        // 短暂等待线程响应停止信号
-        thread::sleep(Duration::from_millis(DISCONNECT_DELAY_MS));
-
-        // 断开连接并清理资源
-        let mut robot_lock = state
+        robot
             .robot_server
             .write()
-            .map_err(|e| format!("Failed to acquire robot server write lock: {:?}", e))?;
-
-        let client_arc = robot_lock
-            .socket
-            .take()
-            .ok_or_else(|| "No active client connection".to_string())?;
-
-        let mut client = client_arc
-            .lock()
-            .map_err(|e| format!("Failed to acquire client lock: {:?}", e))?;
+            .map_err(|_| AppError::LockPoisoned("robot_server"))?
+            .status_monitor = None;
 
-        client
-            .disconnect()
-            .map_err(|e| format!("Failed to disconnect: {}", e))?;
+        state.remove_robot(&robot.id)?;
 
-        // 等待数据收集线程结束
-        if let Some(handler) = robot_lock.handle.take() {
-            drop(client); // 释放 client 锁,避免死锁
-            drop(robot_lock); // 释放 robot_lock,避免死锁
-
-            // 等待线程结束
-            let _ = handler
-                .join()
-                .map_err(|_| "Failed to join data collection thread".to_string())?;
-
-            // 重新获取锁以更新状态
-            robot_lock = state
-                .robot_server
-                .write()
-                .map_err(|e| format!("Failed to reacquire robot server write lock: {:?}", e))?;
-        }
-
-        robot_lock.socket = None;
-        robot_lock.connected = false;
-
-        // 清理 csv_exporter (避免在持有 robot_lock 时获取嵌套锁)
-        let csv_exporter_arc = robot_lock.csv_exporter.clone();
-        drop(robot_lock); // 释放 robot_lock
+        Ok("Robot server disconnected successfully".to_string())
+    };
 
-        {
-            let mut csv_exporter_rw = csv_exporter_arc
-                .write()
-                .map_err(|e| format!("Failed to acquire csv_exporter lock: {:?}", e))?;
+    result_response!(result().await)
+}
 
-            if let Some(csv_exporter) = csv_exporter_rw.as_mut() {
-                csv_exporter
-                    .delete()
-                    .map_err(|e| format!("Failed to clear temp file: {:?}", e))?;
-            }
-            *csv_exporter_rw = None;
-        }
+#[tauri::command(async)]
+pub async fn start_assistant(
+    state: tauri::State<'_, AppState>,
+    params: structs::ObserveParams,
+    robot_id: Option<String>,
+) -> Result<Response<String>, Response<String>> {
+    result_response!(start_observer(&state, params, robot_id.as_deref(), None).await)
+}
 
-        /*************************** 读取并更新shared_state *************************** */
-        let shared_state = SharedState::default();
-        state
-            .set_shared_state(shared_state)
-            .map_err(|e| format!("Failed to update shared state: {:?}", e))?;
+/// 开始观测/录制, `preset` 为本次录制使用的预设 (滤波与报警), 为空时清除上次的预设;
+/// `params.timeout` 秒后由连接状态机自动停止
+pub(crate) async fn start_observer(
+    state: &AppState,
+    params: structs::ObserveParams,
+    robot_id: Option<&str>,
+    preset: Option<PresetRuntime>,
+) -> Result<String, AppError> {
+    let robot = state.robot(robot_id)?;
+    robot
+        .lifecycle
+        .start_observe(params, preset)
+        .result()
+        .await?;
+    Ok("Assistant started successfully".to_string())
+}
 
-        state
-            .push_shared_state()
-            .map_err(|e| format!("Failed to push shared state: {:?}", e))?;
+#[tauri::command(async)]
+pub async fn stop_assistant(
+    state: tauri::State<'_, AppState>,
+    robot_id: Option<String>,
+) -> Result<Response<String>, Response<String>> {
+    let result = async || -> Result<String, AppError> {
+        let robot = state.robot(robot_id.as_deref())?;
 
-        Ok("Robot server disconnected successfully".to_string())
+        // 状态机先回复再归档录制
+        robot.lifecycle.stop_observe().result().await?;
+        Ok("Assistant stopped successfully".to_string())
     };
 
     result_response!(result().await)
 }
 
+/// 连接状态 (断开, 连接中, 已连接, 观测中, 重连中, 错误)
 #[tauri::command]
-pub fn start_assistant<R: tauri::Runtime>(
-    app: tauri::AppHandle<R>,
+pub fn get_connection_state(
     state: tauri::State<AppState>,
-    params: structs::ObserveParams,
-) -> Response<String> {
-    // 先检查连接状态和运行状态
-    {
-        let robot_lock = match state.robot_server.read() {
-            Ok(lock) => lock,
-            Err(e) => {
-                return Response::error(format!(
-                    "Failed to acquire robot server read lock: {:?}",
-                    e
-                ))
-            }
-        };
-
-        if !robot_lock.connected {
-            return Response::error("Server is not running");
-        }
-
-        if robot_lock.observer_running.load(Ordering::Relaxed) {
-            return Response::error("Assistant is already running");
-        }
+    robot_id: Option<String>,
+) -> Response<ConnectionState> {
+    match state.robot(robot_id.as_deref()) {
+        Ok(robot) => Response::success(robot.lifecycle.state()),
+        Err(e) => e.into(),
     }
+}
 
-    // 初始化 csv_exporter (避免嵌套锁)
-    let csv_exporter_arc = {
-        let robot_lock = match state.robot_server.read() {
-            Ok(lock) => lock,
-            Err(e) => {
-                return Response::error(format!(
-                    "Failed to acquire robot server read lock: {:?}",
-                    e
-                ))
-            }
-        };
-        robot_lock.csv_exporter.clone()
+/// 保存当前录制, 默认CSV (原始数据同名保存), 也可导出为其他格式;
+/// `channels` 未指定时使用录制预设的导出通道, 为空时导出全部通道
+#[tauri::command]
+pub fn save_csv(
+    state: tauri::State<AppState>,
+    path: &str,
+    format: Option<ExportFormat>,
+    channels: Option<Vec<structs::ObserveType>>,
+    robot_id: Option<String>,
+) -> Response<String> {
+    let robot = match state.robot(robot_id.as_deref()) {
+        Ok(robot) => robot,
+        Err(e) => return e.into(),
     };
 
-    {
-        let mut csv_exporter_rw = match csv_exporter_arc.write() {
-            Ok(guard) => guard,
-            Err(e) => {
-                return Response::error(format!("Failed to acquire csv_exporter lock: {:?}", e))
-            }
+    // 获取 csv_exporter_arc (避免持有 robot_lock)
+    let (csv_exporter_arc, preset_arc) = {
+        let robot_lock = match robot.robot_server.read() {
+            Ok(lock) => lock,
+            Err(_) => return AppError::LockPoisoned("robot_server").into(),
         };
 
-        if let Some(csv_exporter) = csv_exporter_rw.as_mut() {
-            if let Err(e) = csv_exporter.create() {
-                return Response::error(format!("Failed to create temp file: {:?}", e));
-            }
+        // 检查连接状态
+        if !robot_lock.connected {
+            return AppError::RobotNotConnected.into();
         }
-    }
 
-    // 更新 observer_running 和 observe_params
-    let observer_running = {
-        let robot_lock = match state.robot_server.read() {
-            Ok(lock) => lock,
-            Err(e) => {
-                return Response::error(format!(
-                    "Failed to acquire robot server read lock: {:?}",
-                    e
-                ))
-            }
-        };
-
-        robot_lock.observer_running.store(true, Ordering::Relaxed);
+        (robot_lock.csv_exporter.clone(), robot_lock.preset.clone())
+    };
 
-        let mut params_write = match robot_lock.observe_params.write() {
-            Ok(write) => write,
-            Err(e) => {
-                return Response::error(format!("Failed to acquire observe_params lock: {:?}", e))
-            }
-        };
+    // 未指定通道时使用录制预设中的导出通道
+    let channels = channels.unwrap_or_else(|| {
+        preset_arc
+            .read()
+            .ok()
+            .and_then(|preset| preset.as_ref().map(|p| p.channels.clone()))
+            .unwrap_or_default()
+    });
 
-        *params_write = params.clone();
+    let format = format.unwrap_or_default();
+    let meta = match format {
+        ExportFormat::Csv => None,
+        _ => match current_session_meta(&robot) {
+            Ok(meta) => Some(meta),
+            Err(e) => return e.into(),
+        },
+    };
 
-        robot_lock.observer_running.clone()
+    // 保存 CSV 文件
+    let mut csv_exporter_guard = match csv_exporter_arc.write() {
+        Ok(guard) => guard,
+        Err(_) => return AppError::LockPoisoned("csv_exporter").into(),
     };
 
-    /*************************** 读取并更新shared_state *************************** */
-    {
-        let mut shared_state = match state.shared_state.read() {
-            Ok(guard) => guard.clone(),
-            Err(e) => {
-                return Response::error(format!(
-                    "Failed to acquire shared_state read lock: {:?}",
-                    e
-                ))
+    match csv_exporter_guard.as_mut() {
+        Some(csv_exporter) => {
+            let dest_path = PathBuf::from(path);
+            if let Some(meta) = meta {
+                // 其他格式由临时文件转换生成
+                if let Err(e) = csv_exporter.flush() {
+                    return AppError::Io {
+                        path: csv_exporter.temp_path().display().to_string(),
+                        reason: e.to_string(),
+                    }
+                    .into();
+                }
+                let meta = ExportMeta::current(csv_exporter, meta);
+                if let Err(e) = export_files(
+                    csv_exporter.temp_path(),
+                    Some(csv_exporter.raw_path().as_path()),
+                    &dest_path,
+                    format,
+                    &channels,
+                    &meta,
+                ) {
+                    return AppError::Io {
+                        path: path.to_string(),
+                        reason: e.to_string(),
+                    }
+                    .into();
+                }
+                return Response::success("Export recording successfully".to_string());
             }
-        };
 
-        shared_state.observering = true;
-
-        if let Err(e) = state.set_shared_state(shared_state) {
-            return Response::error(format!("Failed to set shared_state: {:?}", e));
+            if let Err(e) = csv_exporter.save_to(&dest_path) {
+                return AppError::Io {
+                    path: path.to_string(),
+                    reason: e.to_string(),
+                }
+                .into();
+            }
+            // 原始数据与CSV同名保存, 供周期分析等离线功能使用
+            if let Err(e) = csv_exporter.save_raw_to(&dest_path.with_extension("raw")) {
+                return AppError::Io {
+                    path: dest_path.with_extension("raw").display().to_string(),
+                    reason: e.to_string(),
+                }
+                .into();
+            }
         }
-
-        if let Err(e) = state.push_shared_state() {
-            return Response::error(format!("Failed to push shared_state: {:?}", e));
+        None => {
+            return AppError::RecorderNotReady.into();
         }
     }
 
-    /*************** 在后台线程中，timeout秒后将 observering = false 并更新状态 ***************/
-    let timeout_millis = params.timeout * 1000;
-    let app_handle = app.app_handle().clone();
-
-    thread::spawn(move || {
-        // 将长时间睡眠改为循环短睡眠并检查退出标志
-        let mut remaining_millis = timeout_millis as i64;
-        while remaining_millis > 0 && observer_running.load(Ordering::Relaxed) {
-            thread::sleep(Duration::from_millis(OBSERVER_CHECK_INTERVAL_MS));
-            remaining_millis -= OBSERVER_CHECK_INTERVAL_MS as i64;
-        }
-
-        // 超时或被取消后都需要更新状态
-        let state = app_handle.state::<AppState>();
-
-        observer_running.store(false, Ordering::Relaxed);
-
-        // 使用分号确保临时值在作用域结束前被清理
-        if let Ok(guard) = state.shared_state.read() {
-            let mut shared_state = guard.clone();
-            drop(guard); // 显式释放读锁
-
-            shared_state.observering = false;
-            let _ = state.set_shared_state(shared_state);
-            let _ = state.push_shared_state();
-        };
-    });
-
-    Response::success("Assistant started successfully".to_string())
+    Response::success("Save csv successfully".to_string())
 }
 
+/// 最近一次上报的设备状态 (状态, 模式, 错误/警告码等)
 #[tauri::command]
-pub fn stop_assistant(state: tauri::State<AppState>) -> Response<String> {
-    // 检查连接状态和运行状态,并停止 observer
-    {
-        let robot_lock = match state.robot_server.read() {
-            Ok(lock) => lock,
-            Err(e) => {
-                return Response::error(format!(
-                    "Failed to acquire robot server read lock: {:?}",
-                    e
-                ))
-            }
-        };
-
-        if !robot_lock.connected {
-            return Response::error("Server is not running");
-        }
-
-        if !robot_lock.observer_running.load(Ordering::Relaxed) {
-            return Response::error("Assistant is not running");
-        }
-
-        robot_lock.observer_running.store(false, Ordering::Relaxed);
-    }
-
-    /*************************** 读取并更新shared_state *************************** */
-    {
-        let mut shared_state = match state.shared_state.read() {
-            Ok(guard) => guard.clone(),
-            Err(e) => {
-                return Response::error(format!(
-                    "Failed to acquire shared_state read lock: {:?}",
-                    e
-                ))
-            }
-        };
-
-        shared_state.observering = false;
-
-        if let Err(e) = state.set_shared_state(shared_state) {
-            return Response::error(format!("Failed to set shared_state: {:?}", e));
-        }
+pub fn get_device_status(
+    state: tauri::State<AppState>,
+    robot_id: Option<String>,
+) -> Response<Option<DeviceStatusReport>> {
+    let robot = match state.robot(robot_id.as_deref()) {
+        Ok(robot) => robot,
+        Err(e) => return e.into(),
+    };
+    let robot_lock = match robot.robot_server.read() {
+        Ok(lock) => lock,
+        Err(_) => return AppError::LockPoisoned("robot_server").into(),
+    };
+    Response::success(
+        robot_lock
+            .status_monitor
+            .as_ref()
+            .and_then(|monitor| monitor.latest()),
+    )
+}
 
-        if let Err(e) = state.push_shared_state() {
-            return Response::error(format!("Failed to push shared_state: {:?}", e));
-        }
+/// 本次连接的错误/警告历史
+#[tauri::command]
+pub fn get_error_history(
+    state: tauri::State<AppState>,
+    robot_id: Option<String>,
+) -> Response<Vec<ErrorRecord>> {
+    let robot = match state.robot(robot_id.as_deref()) {
+        Ok(robot) => robot,
+        Err(e) => return e.into(),
+    };
+    let error_history = match robot.robot_server.read() {
+        Ok(lock) => lock.error_history.clone(),
+        Err(_) => return AppError::LockPoisoned("robot_server").into(),
+    };
+    match error_history.read() {
+        Ok(history) => Response::success(history.records()),
+        Err(_) => AppError::LockPoisoned("error_history").into(),
     }
-
-    Response::success("Assistant stopped successfully".to_string())
 }
 
 #[tauri::command]
-pub fn save_csv(state: tauri::State<AppState>, path: &str) -> Response<String> {
-    // 获取 csv_exporter_arc (避免持有 robot_lock)
-    let csv_exporter_arc = {
-        let robot_lock = match state.robot_server.read() {
-            Ok(lock) => lock,
-            Err(e) => {
-                return Response::error(format!(
-                    "[save_csv]Failed to acquire robot_server lock: {:?}",
-                    e
-                ))
-            }
-        };
-
-        // 检查连接状态
-        if !robot_lock.connected {
-            return Response::error("请先连接机器人服务器");
-        }
-
-        robot_lock.csv_exporter.clone()
+pub fn clear_error_history(
+    state: tauri::State<AppState>,
+    robot_id: Option<String>,
+) -> Response<String> {
+    let robot = match state.robot(robot_id.as_deref()) {
+        Ok(robot) => robot,
+        Err(e) => return e.into(),
     };
-
-    // 保存 CSV 文件
-    let mut csv_exporter_guard = match csv_exporter_arc.write() {
-        Ok(guard) => guard,
-        Err(e) => {
-            return Response::error(format!(
-                "[save_csv]Failed to acquire csv_exporter lock: {:?}",
-                e
-            ))
-        }
+    let error_history = match robot.robot_server.read() {
+        Ok(lock) => lock.error_history.clone(),
+        Err(_) => return AppError::LockPoisoned("robot_server").into(),
     };
-
-    match csv_exporter_guard.as_mut() {
-        Some(csv_exporter) => {
-            if let Err(e) = csv_exporter.save_to(&PathBuf::from(path)) {
-                return Response::error(format!("Failed to save csv: {:?}", e));
-            }
-        }
-        None => {
-            return Response::error("[save_csv]CSV exporter not initialized");
+    match error_history.write() {
+        Ok(mut history) => {
+            history.clear();
+            Response::success("Clear error history successfully".to_string())
         }
+        Err(_) => AppError::LockPoisoned("error_history").into(),
     }
+}
 
-    Response::success("Save csv successfully".to_string())
+/// 控制器错误码与警告码目录
+#[tauri::command]
+pub fn list_error_codes() -> Response<Vec<CodeInfo>> {
+    Response::success(error_codes::catalogue())
 }
 
 #[tauri::command(async)]
 pub async fn get_robot_axis(
     state: tauri::State<'_, AppState>,
+    robot_id: Option<String>,
 ) -> Result<Response<Value>, Response<String>> {
-    let ws_ip = state.ws_ip.read().unwrap().clone();
-    let sdk_data = ws_get_data(ws_ip.as_str()).await;
-    if let Ok(data) = sdk_data {
-        let json = serde_json::to_value(data).unwrap();
-        return Ok(Response::success(json));
-    }
-    Ok(Response::error("Failed to get robot axis"))
+    let result = async || -> Result<Value, AppError> {
+        let ws_ip = state
+            .robot(robot_id.as_deref())?
+            .robot_server
+            .read()
+            .map_err(|_| AppError::LockPoisoned("robot_server"))?
+            .ip
+            .clone();
+        let data = ws_get_data(ws_ip.as_str(), state.language().ws_lang())
+            .await
+            .map_err(|e| AppError::DeviceStatusUnavailable(e.to_string()))?;
+        serde_json::to_value(data).map_err(|e| AppError::Internal(e.to_string()))
+    };
+
+    result_response!(result().await)
 }
@@ -16,26 +16,30 @@ impl Parser {
         F: FnMut(&RobotDataPacket) -> Result<()>,
     {
         let mut processed = 0;
+        let mut offset = 0;
 
-        while let Some(packet_size) = self.get_packet_size(buffer) {
-            if buffer.len() >= packet_size {
-                let packet_data = &buffer[0..packet_size];
+        while let Some(packet_size) = self.get_packet_size(&buffer[offset..]) {
+            if buffer.len() - offset >= packet_size {
+                let packet_data = &buffer[offset..offset + packet_size];
 
                 match self.parse_packet(packet_data) {
                     Ok(packet) => {
-                        handler(&packet)?;
+                        if let Err(e) = handler(&packet) {
+                            buffer.drain(0..offset + packet_size);
+                            return Err(e);
+                        }
                         processed += 1;
                     }
                     Err(e) => eprintln!("解析错误: {}", e),
                 }
-
-                // 移除已处理数据
-                buffer.drain(0..packet_size);
+                offset += packet_size;
             } else {
                 break;
             }
         }
 
+        // 一次性移除已处理数据, 避免每个数据包都移动整个缓冲区
+        buffer.drain(0..offset);
         Ok(processed)
     }
 
@@ -4,12 +4,49 @@ use crate::commands::arm_service::robot_data::RobotDataPacket;
 use crate::commands::arm_service::structs::{
     ChartData, Hertz, Mode, ObserveParams, ObserveType, ResponseChartData, Unit, SHOW_RAD_TYPE,
 };
+use crate::commands::streaming::{BusPacket, PacketBus};
+use crate::utils::i18n;
 use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 use std::io::{self, Result};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// 实时数据连接的超时配置 (毫秒)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+    pub connect_timeout_ms: u64,   // 建立 TCP 连接
+    pub read_idle_timeout_ms: u64, // 没有收到任何数据
+    pub stall_timeout_ms: u64,     // 收到数据但没有完整的数据包 (数据流停滞)
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_ms: 5000,
+            read_idle_timeout_ms: 3000,
+            stall_timeout_ms: 2000,
+        }
+    }
+}
+
+impl ClientConfig {
+    fn connect_timeout(&self) -> Duration {
+        Duration::from_millis(self.connect_timeout_ms.max(1))
+    }
+
+    fn read_idle_timeout(&self) -> Duration {
+        Duration::from_millis(self.read_idle_timeout_ms.max(1))
+    }
+
+    fn stall_timeout(&self) -> Duration {
+        Duration::from_millis(self.stall_timeout_ms.max(1))
+    }
+}
 
 /// 机器人客户端：封装连接管理和数据采集逻辑
 #[derive(Debug)]
@@ -17,121 +54,152 @@ pub struct RobotClient {
     connection: Option<RobotConnection>,
     buffer_size: usize,
     parser: Parser,
-    is_running: Arc<AtomicBool>,
+    config: ClientConfig,
+    // 解码后的每个数据包都发布到总线 (与观测状态无关), 供流式输出使用
+    packet_bus: Option<(PacketBus, String)>,
 }
 
 #[derive(Debug)]
 pub struct ResponseData {
     pub data: ResponseChartData,
     pub csv: bool,
+    // 原始数据包 (用于原始录制与周期分析)
+    pub packet: RobotDataPacket,
 }
 impl RobotClient {
-    /// 初始化机器人客户端
-    pub fn new(ip_addr: String) -> Result<Self> {
-        let read_timeout: u64 = 1000;
+    /// 连接机器人并初始化客户端
+    pub async fn connect(ip_addr: &str, config: ClientConfig) -> Result<Self> {
         let buffer_size: usize = 1024;
 
-        let connection = Some(RobotConnection::connect(ip_addr, read_timeout)?);
+        let connection = Some(RobotConnection::connect(ip_addr, config.connect_timeout()).await?);
         Ok(Self {
             connection,
             buffer_size,
             parser: Parser::new(),
-            is_running: Arc::new(AtomicBool::new(false)),
+            config,
+            packet_bus: None,
         })
     }
 
-    /// 启动数据采集循环
-    pub fn collect_data<F>(
+    /// 设置数据包总线, 发布的数据包标记为 `robot_id`
+    pub fn set_packet_bus(&mut self, packet_bus: PacketBus, robot_id: String) {
+        self.packet_bus = Some((packet_bus, robot_id));
+    }
+
+    /// 启动数据采集循环, `cancel` 取消时正常结束;
+    /// 连接被关闭, 读取超时或数据流停滞时返回错误
+    ///
+    /// `on_packet` 接收每个解码后的数据包 (不抽取, 与观测状态无关), 用于原始录制与周期检测;
+    /// `handler` 只在观测时调用, 5Hz 观测时按 5Hz 抽取
+    pub async fn collect_data<P, F>(
         &mut self,
-        stop_flag: Arc<AtomicBool>,
+        cancel: CancellationToken,
         observer_running: Arc<AtomicBool>,
         observe_params: Arc<RwLock<ObserveParams>>,
+        mut on_packet: P,
         mut handler: F,
     ) -> Result<()>
     where
+        P: FnMut(&RobotDataPacket) -> Result<()>,
         F: FnMut(Result<ResponseData>) -> Result<()>,
     {
         // 检查连接是否存在
-        let connection = self
-            .connection
-            .as_mut()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "未连接到机器人"))?;
+        let connection = self.connection.as_mut().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotConnected,
+                i18n::t("robot.not_connected_client"),
+            )
+        })?;
 
-        self.is_running.store(true, Ordering::Relaxed);
         let mut buffer = vec![0u8; self.buffer_size];
         let mut incomplete_data = Vec::new();
         let mut packet_count = 0;
         let mut last_exec_time = Instant::now();
+        // 看门狗: 最近一次收到完整数据包的时间
+        let mut last_frame_time = Instant::now();
+        let packet_bus = self.packet_bus.clone();
+        let read_idle_timeout = self.config.read_idle_timeout();
+        let stall_timeout = self.config.stall_timeout();
         println!("开始采集机器人数据...");
 
-        while !stop_flag.load(Ordering::Relaxed) {
-            // 读取数据
-            match connection.read(&mut buffer) {
-                Ok(bytes_read) => {
-                    if bytes_read == 0 {
-                        println!("连接已关闭，采集结束");
-                        break;
-                    }
-                    // 添加到缓冲区
-                    incomplete_data.extend_from_slice(&buffer[0..bytes_read]);
-
-                    // 处理完整数据包
-                    let processed =
-                        self.parser
-                            .process_packets(&mut incomplete_data, |packet| {
-                                packet_count += 1;
-                                if observer_running.load(Ordering::Relaxed) {
-                                    let observe_params_clone = observe_params.clone();
-                                    let params = observe_params_clone.read().unwrap();
-
-                                    if params.hz == Hertz::Hz5 {
-                                        // 检查是否需要执行handler
-                                        let exec_interval = Duration::from_secs_f32(1.0 / 5 as f32);
-                                        let elapsed = last_exec_time.elapsed();
-                                        if elapsed >= exec_interval {
-                                            last_exec_time = Instant::now();
-                                            let rp =
-                                                process_chart_data(observe_params.clone(), packet);
-                                            handler(rp)?;
-                                        }
-                                    } else {
-                                        let rp = process_chart_data(observe_params.clone(), packet);
-                                        handler(rp)?;
-                                    }
-                                }
-                                Ok(())
-                            })?;
-
-                    if processed == 0 && incomplete_data.len() > self.buffer_size * 2 {
-                        eprintln!("警告：缓冲区数据过大，可能存在解析错误，清空缓冲区");
-                        incomplete_data.clear();
+        loop {
+            // 读取数据, 取消时立即结束
+            let bytes_read = tokio::select! {
+                _ = cancel.cancelled() => break,
+                read = connection.read(&mut buffer, read_idle_timeout) => read?,
+            };
+            if bytes_read == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    i18n::t("robot.closed_by_peer"),
+                ));
+            }
+            // 添加到缓冲区
+            incomplete_data.extend_from_slice(&buffer[0..bytes_read]);
+
+            // 处理完整数据包
+            let processed = self
+                .parser
+                .process_packets(&mut incomplete_data, |packet| {
+                    packet_count += 1;
+                    // 没有订阅者时不复制数据包
+                    if let Some((bus, robot_id)) =
+                        packet_bus.as_ref().filter(|(b, _)| b.receiver_count() > 0)
+                    {
+                        let _ = bus.send(Arc::new(BusPacket {
+                            robot_id: robot_id.clone(),
+                            packet: packet.clone(),
+                        }));
                     }
-                }
-                Err(e) => {
-                    if self.is_running.load(Ordering::Relaxed) {
-                        return Err(e);
-                    } else {
-                        // 正常关闭时忽略错误
-                        break;
+                    on_packet(packet)?;
+                    if observer_running.load(Ordering::Relaxed) {
+                        let observe_params_clone = observe_params.clone();
+                        let params = observe_params_clone.read().unwrap();
+
+                        if params.hz == Hertz::Hz5 {
+                            // 检查是否需要执行handler
+                            let exec_interval = Duration::from_secs_f32(1.0 / 5 as f32);
+                            let elapsed = last_exec_time.elapsed();
+                            if elapsed >= exec_interval {
+                                last_exec_time = Instant::now();
+                                let rp = process_chart_data(observe_params.clone(), packet);
+                                handler(rp)?;
+                            }
+                        } else {
+                            let rp = process_chart_data(observe_params.clone(), packet);
+                            handler(rp)?;
+                        }
                     }
-                }
+                    Ok(())
+                })?;
+
+            if processed > 0 {
+                last_frame_time = Instant::now();
+            } else if last_frame_time.elapsed() >= stall_timeout {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    i18n::t_args(
+                        "robot.stream_stalled",
+                        &[("ms", stall_timeout.as_millis().to_string())],
+                    ),
+                ));
+            }
+
+            if processed == 0 && incomplete_data.len() > self.buffer_size * 2 {
+                eprintln!("警告：缓冲区数据过大，可能存在解析错误，清空缓冲区");
+                incomplete_data.clear();
             }
         }
 
-        self.is_running.store(false, Ordering::Relaxed);
         Ok(())
     }
 
     /// 断开与机器人的连接
-    pub fn disconnect(&mut self) -> Result<()> {
-        if self.connection.is_some() {
+    pub async fn disconnect(&mut self) -> Result<()> {
+        if let Some(mut conn) = self.connection.take() {
             println!("断开与机器人的连接...");
-            if let Some(conn) = self.connection.as_mut() {
-                let _ = conn.close();
-            }
-            self.connection = None; // 释放连接资源
+            let _ = conn.close().await;
             println!("已成功断开连接");
-            self.is_running.store(false, Ordering::Relaxed);
         }
 
         Ok(())
@@ -143,15 +211,6 @@ impl RobotClient {
     }
 }
 
-// 实现 Drop trait，确保资源被正确释放
-impl Drop for RobotClient {
-    fn drop(&mut self) {
-        // 如果连接还存在，则断开连接
-        if self.is_connected() {
-            let _ = self.disconnect();
-        }
-    }
-}
 /// 处理频率 5hz / 250hz
 // pub fn process_packet_frequency(observe_params: &ObserveParams) -> usize {}
 /// 处理图表数据
@@ -165,7 +224,7 @@ pub fn process_chart_data(
         Err(_) => {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
-                "无法读取观测参数",
+                i18n::t("robot.invalid_observe_params"),
             ));
         }
     };
@@ -213,7 +272,7 @@ pub fn process_chart_data(
                 _ => {
                     return Err(io::Error::new(
                         io::ErrorKind::InvalidData,
-                        "无法读取观测参数",
+                        i18n::t("robot.invalid_observe_params"),
                     ));
                 }
             };
@@ -309,14 +368,14 @@ pub fn process_chart_data(
                 _ => {
                     return Err(io::Error::new(
                         io::ErrorKind::InvalidData,
-                        "无法读取观测参数",
+                        i18n::t("robot.invalid_observe_params"),
                     ));
                 }
             },
             _ => {
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidData,
-                    "无法读取观测参数",
+                    i18n::t("robot.invalid_observe_params"),
                 ));
             }
         };
@@ -347,5 +406,138 @@ pub fn process_chart_data(
     Ok(ResponseData {
         data: s,
         csv: op.csv.clone(),
+        packet: packet.clone(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    const FRAME_SIZE: usize = 784;
+
+    fn config(read_idle_timeout_ms: u64, stall_timeout_ms: u64) -> ClientConfig {
+        ClientConfig {
+            connect_timeout_ms: 1000,
+            read_idle_timeout_ms,
+            stall_timeout_ms,
+        }
+    }
+
+    /// 模拟控制器: 先发送一个完整数据包, 之后每 20ms 发送不足一包的数据 (`trickle`)
+    /// 或不再发送
+    async fn mock_controller(trickle: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut frame = vec![0u8; FRAME_SIZE];
+            frame[0..4].copy_from_slice(&(FRAME_SIZE as u32).to_le_bytes());
+            stream.write_all(&frame).await.unwrap();
+            // 下一包只有包头和少量数据
+            let _ = stream.write_all(&frame[0..8]).await;
+            loop {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                if trickle && stream.write_all(&[0u8; 4]).await.is_err() {
+                    break;
+                }
+            }
+        });
+        addr
+    }
+
+    async fn collect(client: &mut RobotClient, cancel: CancellationToken) -> Result<()> {
+        client
+            .collect_data(
+                cancel,
+                Arc::new(AtomicBool::new(false)),
+                Arc::new(RwLock::new(ObserveParams::default())),
+                |_| Ok(()),
+                |_| Ok(()),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_packets_without_observing() {
+        let addr = mock_controller(false).await;
+        let mut client = RobotClient::connect(&addr, config(10_000, 10_000))
+            .await
+            .unwrap();
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            trigger.cancel();
+        });
+
+        // 未观测时 handler 不会被调用, 每个数据包仍然交给 on_packet
+        let (mut packets, mut observed) = (0, 0);
+        client
+            .collect_data(
+                cancel,
+                Arc::new(AtomicBool::new(false)),
+                Arc::new(RwLock::new(ObserveParams::default())),
+                |_| {
+                    packets += 1;
+                    Ok(())
+                },
+                |_| {
+                    observed += 1;
+                    Ok(())
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!((packets, observed), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_read_timeouts() {
+        // 一直有数据但凑不成完整数据包: 看门狗判定停滞
+        let addr = mock_controller(true).await;
+        let mut client = RobotClient::connect(&addr, config(1000, 200))
+            .await
+            .unwrap();
+        let start = Instant::now();
+        let err = collect(&mut client, CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(err.to_string().contains("停滞"));
+        assert!(start.elapsed() < Duration::from_millis(1000));
+
+        // 完全没有数据: 读取空闲超时
+        let addr = mock_controller(false).await;
+        let mut client = RobotClient::connect(&addr, config(200, 5000))
+            .await
+            .unwrap();
+        let err = collect(&mut client, CancellationToken::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(!err.to_string().contains("停滞"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_promptly() {
+        let addr = mock_controller(false).await;
+        let mut client = RobotClient::connect(&addr, config(10_000, 10_000))
+            .await
+            .unwrap();
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            trigger.cancel();
+        });
+
+        let start = Instant::now();
+        collect(&mut client, cancel).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        client.disconnect().await.unwrap();
+        assert!(!client.is_connected());
+    }
+}
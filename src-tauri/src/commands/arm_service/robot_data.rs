@@ -1,4 +1,4 @@
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{Cursor, Read};
 
 #[derive(Debug, Clone)]
@@ -150,6 +150,62 @@ impl RobotDataPacket {
         })
     }
 
+    /// 序列化为与 `from_bytes` 相同布局的字节 (用于原始数据录制)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf: Vec<u8> = Vec::with_capacity(self.byte_count.max(784) as usize);
+
+        // 写入 Vec 不会失败
+        let _ = buf.write_u32::<LittleEndian>(self.byte_count);
+        let _ = buf.write_i64::<LittleEndian>(self.timestamp);
+        let _ = buf.write_u8(self.motion_state_and_mode);
+        let _ = buf.write_u16::<LittleEndian>(self.instruction_cache_count);
+        buf.extend_from_slice(&self.reserved_system);
+
+        for arr in [
+            &self.target_joint_positions[..],
+            &self.target_joint_velocities[..],
+            &self.target_joint_accelerations[..],
+            &self.actual_joint_positions[..],
+            &self.actual_joint_velocities[..],
+            &self.actual_joint_accelerations[..],
+            &self.actual_joint_currents[..],
+            &self.estimated_joint_torque[..],
+            &self.reserved_joint[..],
+            &self.target_tcp_pose[..],
+            &self.target_tcp_velocity[..],
+            &self.actual_tcp_pose[..],
+            &self.actual_tcp_velocity[..],
+            &self.estimated_tcp_torque[..],
+            &self.target_tcp_accelerations[..],
+            &self.actual_tcp_accelerations[..],
+            &self.reserved_tcp[..],
+            &self.data_torque_sensor[..],
+            &self.filtered_data_torque_sensor[..],
+            &self.reserved_external[..],
+        ] {
+            for v in arr {
+                let _ = buf.write_f32::<LittleEndian>(*v);
+            }
+        }
+
+        // 新固件的包可能更长, 补齐到 byte_count 以保证按包长重新分帧
+        if buf.len() < self.byte_count as usize {
+            buf.resize(self.byte_count as usize, 0);
+        }
+
+        buf
+    }
+
+    /// 运动状态 (低4位): 1 运动中, 2 待机, 3 暂停, 4 停止
+    pub fn motion_state(&self) -> u8 {
+        self.motion_state_and_mode & 0x0F
+    }
+
+    /// 控制模式 (高4位)
+    pub fn control_mode(&self) -> u8 {
+        self.motion_state_and_mode >> 4
+    }
+
     /// 从 Cursor 中读取指定数量的 f32 数组
     fn read_f32_array<const N: usize>(cursor: &mut Cursor<&[u8]>) -> std::io::Result<[f32; N]> {
         let mut arr = [0.0f32; N];
@@ -0,0 +1,297 @@
+// session_backend.rs - 连接状态机的实际实现: TCP 采集线程与录制
+use std::{
+    io,
+    sync::{atomic::Ordering, Arc, RwLock, Weak},
+    thread,
+};
+
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    commands::{
+        arm_service::{
+            csv_exporter::CsvExporter,
+            lifecycle::{LostSignal, RobotBackend},
+            robot_client::RobotClient,
+            robot_data::RobotDataPacket,
+            structs::ObserveParams,
+            ROBOT_PORT,
+        },
+        presets::runtime::PresetRuntime,
+        sessions::archive_current_recording,
+    },
+    state::app_state::{AppState, RobotEvent, RobotServer, RobotSession},
+    utils::i18n,
+};
+
+/// 单台机械臂的采集客户端与录制, 只由连接 actor 线程访问
+#[derive(Debug)]
+pub struct SessionBackend {
+    app: AppHandle,
+    robot_id: String,
+    robot_server: Arc<RwLock<RobotServer>>,
+    // 只用于归档录制, 不持有会话以免循环引用
+    session: Weak<RobotSession>,
+    // 采集线程 (线程内运行客户端), 取消 cancel 时断开并结束
+    handle: Option<thread::JoinHandle<io::Result<()>>>,
+    cancel: CancellationToken,
+}
+
+impl SessionBackend {
+    pub fn new(
+        app: AppHandle,
+        robot_id: &str,
+        robot_server: Arc<RwLock<RobotServer>>,
+        session: Weak<RobotSession>,
+    ) -> Self {
+        Self {
+            app,
+            robot_id: robot_id.to_string(),
+            robot_server,
+            session,
+            handle: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+}
+
+impl RobotBackend for SessionBackend {
+    fn connect(&mut self, ip: &str, lost: LostSignal) -> Result<(), String> {
+        let state = self.app.state::<AppState>();
+
+        /********* socket 读取并推送到前端 *********/
+        // 采集线程使用独立的运行时, 数据回调中的同步操作不阻塞应用的异步任务
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to create runtime: {:?}", e))?;
+        let config = state
+            .settings()
+            .map(|settings| settings.connection)
+            .unwrap_or_default();
+        let robot_ip = format!("{}:{}", ip, ROBOT_PORT);
+        let mut client = runtime
+            .block_on(RobotClient::connect(&robot_ip, config))
+            .map_err(|e| format!("Failed to create RobotClient: {:?}", e))?;
+        client.set_packet_bus(state.packet_bus.clone(), self.robot_id.clone());
+
+        // 获取所有需要的 Arc 克隆
+        let (observer_running, observe_params, csv_exporter, cycle_detector, preset) = {
+            let mut robot_lock = self
+                .robot_server
+                .write()
+                .map_err(|e| format!("Failed to acquire robot server write lock: {:?}", e))?;
+            robot_lock.ip = ip.to_string();
+
+            (
+                robot_lock.observer_running.clone(),
+                robot_lock.observe_params.clone(),
+                robot_lock.csv_exporter.clone(),
+                robot_lock.cycle_detector.clone(),
+                robot_lock.preset.clone(),
+            )
+        };
+
+        /*************************************** 初始化csv导出器 *********************/
+        {
+            let mut csv_exporter = csv_exporter
+                .write()
+                .map_err(|e| format!("Failed to acquire csv_exporter lock: {:?}", e))?;
+
+            // 使用用户数据目录
+            let csv_temp_dir = state.user_data_paths.csv_temp.clone();
+            let exporter = CsvExporter::new(csv_temp_dir, &self.robot_id)
+                .map_err(|e| format!("Failed to create CSV exporter: {:?}", e))?;
+            *csv_exporter = Some(exporter);
+        }
+
+        let cancel = CancellationToken::new();
+        self.cancel = cancel.clone();
+
+        let ah = self.app.clone();
+        let event_robot_id = self.robot_id.clone();
+        let recording = observer_running.clone();
+        let record_params = observe_params.clone();
+        let handler = thread::spawn(move || {
+            let robot_id = event_robot_id.as_str();
+            // 原始录制, 报警与周期检测使用每个数据包, 不受观测频率抽取的影响
+            let on_packet = |packet: &RobotDataPacket| {
+                let observing = recording.load(Ordering::Relaxed);
+                if observing && record_params.read().is_ok_and(|params| params.csv) {
+                    if let Ok(mut csv_exporter_guard) = csv_exporter.write() {
+                        if let Some(csv_exporter) = csv_exporter_guard.as_mut() {
+                            if let Err(e) = csv_exporter.write_raw(packet) {
+                                eprintln!("Failed to write raw packet: {:?}", e);
+                            }
+                        }
+                    }
+                }
+                // 预设的报警按原始数据检查, 短暂越限也不会因抽取而漏报
+                if observing {
+                    if let Ok(mut preset_guard) = preset.write() {
+                        if let Some(preset) = preset_guard.as_mut() {
+                            for alarm in preset.check_alarms(packet) {
+                                let _ = ah.emit(
+                                    "ROBOT_ALARM",
+                                    RobotEvent {
+                                        robot_id,
+                                        payload: &alarm,
+                                    },
+                                );
+                            }
+                        }
+                    }
+                }
+                // 实时周期检测
+                if let Ok(mut detector_guard) = cycle_detector.write() {
+                    if let Some(detector) = detector_guard.as_mut() {
+                        if let Some(cycle) = detector.push(packet) {
+                            let _ = ah.emit(
+                                "ROBOT_CYCLE",
+                                RobotEvent {
+                                    robot_id,
+                                    payload: &cycle,
+                                },
+                            );
+                        }
+                    }
+                }
+                Ok(())
+            };
+            let collect =
+                client.collect_data(cancel, observer_running, observe_params, on_packet, |rp| {
+                    // 发送事件
+                    if let Ok(packet) = rp {
+                        // 预设的显示滤波只作用于推送到前端的数据
+                        let filtered = preset
+                            .write()
+                            .ok()
+                            .and_then(|mut guard| guard.as_mut()?.filter(&packet.data));
+                        let _ = ah.emit(
+                            "ROBOT_TCP_DATA",
+                            RobotEvent {
+                                robot_id,
+                                payload: filtered.as_ref().unwrap_or(&packet.data),
+                            },
+                        );
+                        // 写入csv文件
+                        if packet.csv {
+                            if let Ok(mut csv_exporter_guard) = csv_exporter.write() {
+                                if let Some(csv_exporter) = csv_exporter_guard.as_mut() {
+                                    if let Err(e) = csv_exporter.write_packet(&packet.data) {
+                                        eprintln!("Failed to write packet to CSV: {:?}", e);
+                                    }
+                                }
+                            }
+                        }
+                    } else if let Err(e) = rp {
+                        eprintln!("Failed to collect data: {:?}", e);
+                    }
+                    Ok(())
+                });
+            let result = runtime.block_on(collect);
+            let _ = runtime.block_on(client.disconnect());
+
+            // 如果数据采集因错误退出，发送断开连接事件到前端, 并通知状态机重连
+            if let Err(ref e) = result {
+                eprintln!("数据采集线程异常退出: {}", e);
+                let message = i18n::t_args("robot.connection_lost", &[("reason", e.to_string())]);
+                let _ = ah.emit(
+                    "ROBOT_CONNECTION_LOST",
+                    RobotEvent {
+                        robot_id,
+                        payload: serde_json::json!({ "message": message }),
+                    },
+                );
+                lost.notify(&e.to_string());
+            }
+
+            result
+        });
+
+        self.handle = Some(handler);
+        Ok(())
+    }
+
+    fn disconnect(&mut self) {
+        if let Ok(robot_lock) = self.robot_server.read() {
+            robot_lock.observer_running.store(false, Ordering::Relaxed);
+        }
+
+        // 取消后采集线程立即断开连接并结束
+        self.cancel.cancel();
+        if let Some(handler) = self.handle.take() {
+            if handler.join().is_err() {
+                eprintln!("Failed to join data collection thread");
+            }
+        }
+
+        // 清理临时文件前先归档未保存的录制
+        self.archive();
+
+        let csv_exporter_arc = match self.robot_server.read() {
+            Ok(robot_lock) => robot_lock.csv_exporter.clone(),
+            Err(_) => return,
+        };
+        if let Ok(mut csv_exporter_rw) = csv_exporter_arc.write() {
+            if let Some(csv_exporter) = csv_exporter_rw.as_mut() {
+                if let Err(e) = csv_exporter.delete() {
+                    eprintln!("Failed to clear temp file: {:?}", e);
+                }
+            }
+            *csv_exporter_rw = None;
+        }
+    }
+
+    fn start_observe(
+        &mut self,
+        params: ObserveParams,
+        preset: Option<PresetRuntime>,
+    ) -> Result<(), String> {
+        let robot_lock = self
+            .robot_server
+            .read()
+            .map_err(|e| format!("Failed to acquire robot server read lock: {:?}", e))?;
+
+        *robot_lock
+            .preset
+            .write()
+            .map_err(|e| format!("Failed to acquire preset lock: {:?}", e))? = preset;
+
+        if let Some(csv_exporter) = robot_lock
+            .csv_exporter
+            .write()
+            .map_err(|e| format!("Failed to acquire csv_exporter lock: {:?}", e))?
+            .as_mut()
+        {
+            csv_exporter
+                .create()
+                .map_err(|e| format!("Failed to create temp file: {:?}", e))?;
+        }
+
+        *robot_lock
+            .observe_params
+            .write()
+            .map_err(|e| format!("Failed to acquire observe_params lock: {:?}", e))? = params;
+        robot_lock.observer_running.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn stop_observe(&mut self) {
+        if let Ok(robot_lock) = self.robot_server.read() {
+            robot_lock.observer_running.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// 录制结束, 归档到会话库
+    fn archive(&mut self) {
+        let Some(robot) = self.session.upgrade() else {
+            return;
+        };
+        let state = self.app.state::<AppState>();
+        if let Err(e) = archive_current_recording(&state, &robot) {
+            eprintln!("Failed to archive recording: {}", e);
+        }
+    }
+}
@@ -36,6 +36,21 @@ pub enum ObserveType {
     AnalysisTcpAccelerations,   // 分析TCP加速度
     DifferenceData,             // 差值数据
 }
+impl ObserveType {
+    /// 序列化名称 (snake_case), 用于CSV表头等
+    pub fn name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+            .unwrap_or_default()
+    }
+
+    /// 由序列化名称解析, 如 CSV 表头中的 actual_joint_positions
+    pub fn from_name(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
 // impl PartialEq for ObserveType {
 //     fn eq(&self, other: &Self) -> bool {
 //         self == other
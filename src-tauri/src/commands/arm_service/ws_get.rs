@@ -1,17 +1,147 @@
+// ws_get.rs - 控制器 18333 WebSocket 的设备状态读取
 use futures::SinkExt;
 use futures::StreamExt;
-use serde::Serialize;
-use std::error::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{fmt, time::Duration};
 use tokio::time::timeout;
-use tokio_tungstenite::connect_async;
+use tokio_tungstenite::{connect_async, tungstenite};
 use url::Url;
 
-#[derive(Serialize, Debug)]
+// 控制器 WebSocket 端口
+pub const WS_PORT: u16 = 18333;
+// ws_get_data 的总超时 (连接与等待设备状态)
+pub const WS_TIMEOUT: Duration = Duration::from_secs(5);
+// 等待设备状态时最多读取的消息数
+const MAX_MESSAGES: usize = 10;
+
+#[derive(Serialize, Debug, Default)]
 pub struct WSSdkData {
     pub axis: i32,
     pub ft_sensor: bool,
 }
 
+impl WSSdkData {
+    /// 从设备状态 (键值对) 中解析轴数与力传感器状态, 缺少的键 (旧固件) 使用默认值
+    pub fn from_status(status: &Map<String, Value>) -> Self {
+        let axis = status
+            .get("xarm_axis")
+            .and_then(|axis| axis.as_i64())
+            .unwrap_or(0) as i32;
+        let ft_sensor = status.get("ft_sensor").is_some_and(|ft_sensor| {
+            ft_sensor.get("axis").and_then(|axis| axis.get(1)) == Some(&Value::from(1))
+                && ft_sensor.get("mode") == Some(&Value::from(1))
+        });
+        Self { axis, ft_sensor }
+    }
+}
+
+/// 读取设备状态的错误
+#[derive(Debug)]
+pub enum WsError {
+    InvalidAddress(url::ParseError),
+    Connect(tungstenite::Error),
+    Timeout,
+    NoStatusReport, // 连接关闭或消息数超出限制仍未收到设备状态
+}
+
+impl fmt::Display for WsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WsError::InvalidAddress(e) => write!(f, "Invalid websocket address: {}", e),
+            WsError::Connect(e) => write!(f, "Websocket error: {}", e),
+            WsError::Timeout => write!(f, "Websocket timed out"),
+            WsError::NoStatusReport => write!(f, "No devices_status_report received"),
+        }
+    }
+}
+
+impl std::error::Error for WsError {}
+
+impl From<url::ParseError> for WsError {
+    fn from(e: url::ParseError) -> Self {
+        WsError::InvalidAddress(e)
+    }
+}
+
+impl From<tungstenite::Error> for WsError {
+    fn from(e: tungstenite::Error) -> Self {
+        WsError::Connect(e)
+    }
+}
+
+/// 设备状态 WebSocket 地址, `lang` 为控制器消息语言 (cn/en)
+pub fn status_url(ip: &str, port: u16, lang: &str) -> Result<Url, url::ParseError> {
+    let mut url = Url::parse(&format!("ws://{}:{}/ws", ip, port))?;
+    url.query_pairs_mut()
+        .append_pair("channel", "prod")
+        .append_pair("lang", lang)
+        .append_pair("v", "1");
+    Ok(url)
+}
+
+/// 控制器上报的消息, 其他消息 (或无法解析的消息) 忽略
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", content = "data", rename_all = "snake_case")]
+enum WsMessage {
+    DevicesStatusKeysReport(Vec<Value>),
+    DevicesStatusReport(Vec<Value>),
+}
+
+/// 设备状态消息解析: devices_status_keys_report 中的键与 devices_status_report 中的值一一对应
+#[derive(Debug, Default)]
+pub struct StatusReportParser {
+    keys: Vec<String>,
+}
+
+impl StatusReportParser {
+    /// 解析一条消息, 收到设备状态时返回键值对
+    pub fn parse(&mut self, text: &str) -> Option<Map<String, Value>> {
+        match serde_json::from_str::<WsMessage>(text).ok()? {
+            WsMessage::DevicesStatusKeysReport(keys) => {
+                self.keys = keys
+                    .into_iter()
+                    .filter_map(|key| key.as_str().map(|k| k.to_string()))
+                    .collect();
+                None
+            }
+            // 收到键名之前的设备状态无法解析
+            WsMessage::DevicesStatusReport(_) if self.keys.is_empty() => None,
+            WsMessage::DevicesStatusReport(values) => {
+                Some(self.keys.iter().cloned().zip(values).collect())
+            }
+        }
+    }
+}
+
+/// 获取一次设备状态 (不含超时, 由调用方限制)
+pub async fn ws_get_status(ip: &str, port: u16, lang: &str) -> Result<Map<String, Value>, WsError> {
+    let (ws_stream, _) = connect_async(status_url(ip, port, lang)?).await?;
+    let (mut write, mut read) = ws_stream.split();
+
+    let mut parser = StatusReportParser::default();
+    let mut status = None;
+    let mut response_count = 0;
+    while let Some(msg) = read.next().await {
+        if response_count >= MAX_MESSAGES {
+            break;
+        }
+        response_count += 1;
+
+        let msg = msg?;
+        let Ok(text) = msg.to_text() else {
+            continue;
+        };
+        if let Some(report) = parser.parse(text) {
+            status = Some(report);
+            break;
+        }
+    }
+
+    let _ = write.close().await;
+    status.ok_or(WsError::NoStatusReport)
+}
+
 /// 连接ws状态
 pub async fn ws_connect_state(ip: &str) -> bool {
     let url = Url::parse(format!("ws://{}:18333/ws", ip).as_str());
@@ -48,87 +178,95 @@ pub async fn ws_connect_state(ip: &str) -> bool {
     true
 }
 
-/// 获取ws数据
-pub async fn ws_get_data(ip: &str) -> Result<WSSdkData, Box<dyn Error>> {
-    let url = Url::parse(
-        format!(
-            "ws://{}:18333/ws?channel=prod&lang=cn&v=1&id=1752045900705",
-            ip
-        )
-        .as_str(),
-    )?;
-    println!("连接到 WebSocket 服务器: {}", url);
-
-    let (ws_stream, response) = connect_async(url).await?;
-    println!("服务器响应状态: {}", response.status());
-
-    let (mut write, mut read) = ws_stream.split();
+/// 获取ws数据 (轴数与力传感器状态), 总时长不超过 WS_TIMEOUT
+pub async fn ws_get_data(ip: &str, lang: &str) -> Result<WSSdkData, WsError> {
+    read_sdk_data(ip, WS_PORT, lang, WS_TIMEOUT).await
+}
 
-    // 获取多次的响应结果
-    let mut response_count = 0;
-    // type report cmd devices_status_report
-    println!("等待响应...");
+async fn read_sdk_data(
+    ip: &str,
+    port: u16,
+    lang: &str,
+    limit: Duration,
+) -> Result<WSSdkData, WsError> {
+    let status = timeout(limit, ws_get_status(ip, port, lang))
+        .await
+        .map_err(|_| WsError::Timeout)??;
+    Ok(WSSdkData::from_status(&status))
+}
 
-    let mut sdk_data = WSSdkData {
-        axis: 0,
-        ft_sensor: false,
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
 
-    let mut field_keys: Vec<String> = vec![];
+    // 控制器 WebSocket 录制 (每行一条消息)
+    const TRANSCRIPT_V2: &str = r#"{"cmd":"server_info","data":{"version":"2.5.0","sn":"XI1304C21A9L86"}}
+{"cmd":"devices_status_keys_report","data":["xarm_axis","xarm_type","version","xarm_state","xarm_mode","xarm_error_code","xarm_warn_code","ft_sensor"],"id":1}
+{"cmd":"devices_status_report","data":[6,6,"2.5.0",2,0,0,0,{"axis":[0,1],"mode":1}],"id":2}"#;
 
-    while let Some(msg) = read.next().await {
-        if response_count > 10 {
-            break;
-        }
-        let msg = msg?;
+    // 旧固件: 没有力矩传感器字段, 设备状态先于键名上报一次
+    const TRANSCRIPT_V1: &str = r#"{"cmd":"devices_status_report","data":[7,2]}
+{"cmd":"devices_status_keys_report","data":["xarm_axis","xarm_state"]}
+{"cmd":"devices_status_report","data":[7,2]}"#;
 
-        // 获取响应的 JSON
-        let json: serde_json::Value = serde_json::from_str(&msg.to_string())?;
+    /// 回放录制的消息, 之后保持连接不再发送; 返回端口与收到的请求路径
+    #[allow(clippy::result_large_err)] // 握手回调的错误类型由 tungstenite 决定
+    async fn replay(transcript: &'static str) -> (u16, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (path_tx, path_rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut path_tx = Some(path_tx);
+            let callback = |request: &tungstenite::handshake::server::Request, response| {
+                if let Some(tx) = path_tx.take() {
+                    let _ = tx.send(request.uri().to_string());
+                }
+                Ok(response)
+            };
+            let mut ws = tokio_tungstenite::accept_hdr_async(stream, callback)
+                .await
+                .unwrap();
+            for line in transcript.lines() {
+                let _ = ws.send(Message::Text(line.to_string())).await;
+            }
+            while ws.next().await.is_some() {}
+        });
+        (port, path_rx)
+    }
 
-        // devices_status_report
-        if let Some(cmd) = json.get("cmd") {
-            if cmd == "devices_status_keys_report" {
-                let data = json.get("data").unwrap();
+    #[tokio::test]
+    async fn test_read_transcripts() {
+        let (port, path) = replay(TRANSCRIPT_V2).await;
+        let data = read_sdk_data("127.0.0.1", port, "en", WS_TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(data.axis, 6);
+        assert!(data.ft_sensor);
+        assert!(path.await.unwrap().contains("lang=en"));
 
-                let a = data.as_array().unwrap();
-                a.iter().for_each(|item| {
-                    field_keys.push(item.as_str().unwrap().to_string());
-                });
-            }
-            if cmd == "devices_status_report" {
-                let data = json.get("data").unwrap();
-                let ft_sensor_index = field_keys
-                    .iter()
-                    .position(|item| item == "ft_sensor")
-                    .ok_or_else(|| format!("Missing 'ft_sensor' data"))?;
-                let ft_sensor = data.get(ft_sensor_index).unwrap();
-
-                let xarm_axis_index = field_keys
-                    .iter()
-                    .position(|item| item == "xarm_axis")
-                    .ok_or_else(|| format!("Missing 'xarm_axis' data"))?;
-                let xarm_axis = data.get(xarm_axis_index).unwrap();
-
-                // Replace the problematic line with proper error handling
-                let axis = ft_sensor
-                    .get("axis")
-                    .and_then(|axis_array| axis_array.get(1))
-                    .ok_or_else(|| format!("Missing 'axis' data or insufficient elements"))?;
-                let mode = ft_sensor.get("mode").unwrap();
-
-                let open_ft_sensor = axis == 1 && mode == 1;
-
-                sdk_data.axis = xarm_axis.as_i64().unwrap_or(0) as i32;
-                sdk_data.ft_sensor = open_ft_sensor;
-
-                break;
-            }
-        }
-        response_count += 1;
+        let (port, _) = replay(TRANSCRIPT_V1).await;
+        let status = ws_get_status("127.0.0.1", port, "cn").await.unwrap();
+        assert_eq!(status.len(), 2);
+        let data = WSSdkData::from_status(&status);
+        assert_eq!(data.axis, 7);
+        assert!(!data.ft_sensor);
     }
 
-    write.close().await?;
-    println!("断开连接");
+    #[tokio::test]
+    async fn test_timeout_without_status() {
+        // 只有键名, 没有设备状态
+        let (port, _) =
+            replay(r#"{"cmd":"devices_status_keys_report","data":["xarm_axis"]}"#).await;
+        let result = read_sdk_data("127.0.0.1", port, "cn", Duration::from_millis(200)).await;
+        assert!(matches!(result, Err(WsError::Timeout)));
 
-    Ok(sdk_data)
+        let mut parser = StatusReportParser::default();
+        assert!(parser.parse("not json").is_none());
+        assert!(parser
+            .parse(r#"{"cmd":"devices_status_keys_report","data":"bad"}"#)
+            .is_none());
+    }
 }
@@ -9,6 +9,7 @@ pub struct UserDataInfo {
     pub csv_temp: String,
     pub csv_data: String,
     pub config: String,
+    pub golden: String,
 }
 
 /// 获取用户数据目录信息 (用于调试)
@@ -23,6 +24,7 @@ pub fn get_user_data_paths(state: tauri::State<AppState>) -> Response<UserDataIn
         csv_temp: paths.csv_temp.display().to_string(),
         csv_data: paths.csv_data.display().to_string(),
         config: paths.config.display().to_string(),
+        golden: paths.golden.display().to_string(),
     };
 
     Response::success(info)
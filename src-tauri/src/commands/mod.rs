@@ -1,26 +1,43 @@
 use serde_json::Value;
 
-use crate::{state::app_state::AppState, utils::response::Response};
+use crate::{
+    state::app_state::{AppState, SharedState},
+    utils::response::Response,
+};
 
+pub mod analysis;
 pub mod arm_service;
 pub mod debug;
+pub mod presets;
 pub mod request;
+pub mod rest_api;
+pub mod sessions;
+pub mod settings;
+pub mod streaming;
 pub mod system;
 pub mod tools;
 
+/// 机械臂的共享状态, 未连接任何机械臂时返回默认状态
 #[tauri::command(async)]
 pub async fn get_shared_state(
     state: tauri::State<'_, AppState>,
+    robot_id: Option<String>,
 ) -> Result<Response<Value>, Response<String>> {
-    let r = state
-        .push_shared_state()
-        .map_err(|e| format!("Failed to push shared state: {:?}", e));
+    if robot_id.is_none() && state.robot_sessions().is_empty() {
+        let json = serde_json::to_value(SharedState::default()).unwrap();
+        return Ok(Response::success(json));
+    }
+
+    let robot = match state.robot(robot_id.as_deref()) {
+        Ok(robot) => robot,
+        Err(e) => return Ok(e.into()),
+    };
 
-    if let Err(e) = r {
-        return Ok(Response::error(e));
+    if let Err(e) = robot.push_shared_state() {
+        return Ok(e.into());
     }
 
-    let sd = state.shared_state.read().unwrap().clone();
+    let sd = robot.shared_state.read().unwrap().clone();
     let json = serde_json::to_value(sd).unwrap();
     Ok(Response::success(json))
 }
@@ -0,0 +1,265 @@
+// library.rs - 观测预设库: 每个预设保存为配置目录下 presets/{name}.json
+//
+// 导出文件与库中文件格式相同, 可直接复制到其他电脑导入
+use crate::commands::{
+    arm_service::structs::{ObserveParams, ObserveType},
+    presets::runtime::{AlarmRule, ChannelFilter, PresetRuntime, MAX_FILTER_WINDOW},
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{self},
+    path::{Path, PathBuf},
+};
+
+/// 预设目录名 (位于配置目录)
+pub const PRESETS_DIR: &str = "presets";
+/// 预设文件格式版本
+pub const PRESET_VERSION: u32 = 1;
+
+/// 命名的观测预设
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObservePreset {
+    #[serde(default)]
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub params: ObserveParams,
+    #[serde(default)]
+    pub channels: Vec<ObserveType>, // 导出通道, 为空时导出全部
+    #[serde(default)]
+    pub filters: Vec<ChannelFilter>,
+    #[serde(default)]
+    pub alarms: Vec<AlarmRule>,
+    #[serde(default)]
+    pub updated: String, // 本地时间 %Y-%m-%d %H:%M:%S
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+impl ObservePreset {
+    /// 检查名称 (用作文件名) 与滤波/报警配置
+    pub fn validate(&self) -> io::Result<()> {
+        validate_name(&self.name)?;
+        if self.version > PRESET_VERSION {
+            return Err(invalid_input(format!(
+                "Preset {} was created by a newer version (v{})",
+                self.name, self.version
+            )));
+        }
+
+        for filter in &self.filters {
+            if filter.window == 0 || filter.window > MAX_FILTER_WINDOW {
+                return Err(invalid_input(format!(
+                    "Invalid filter window for {}: {} (1-{})",
+                    filter.channel.name(),
+                    filter.window,
+                    MAX_FILTER_WINDOW
+                )));
+            }
+        }
+        for alarm in &self.alarms {
+            let valid = match (alarm.min, alarm.max) {
+                (None, None) => false,
+                (Some(min), Some(max)) => min <= max,
+                _ => true,
+            };
+            if !valid || alarm.joint == Some(0) {
+                return Err(invalid_input(format!(
+                    "Invalid alarm rule for {}",
+                    alarm.channel.name()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 读取预设文件
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// 写入预设文件
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+    }
+
+    /// 采集过程中使用的滤波与报警状态
+    pub fn runtime(&self) -> PresetRuntime {
+        PresetRuntime::new(
+            &self.name,
+            self.channels.clone(),
+            self.filters.clone(),
+            self.alarms.clone(),
+        )
+    }
+}
+
+/// 预设名称用作文件名, 不能包含路径分隔符等字符
+fn validate_name(name: &str) -> io::Result<()> {
+    let trimmed = name.trim();
+    if trimmed.is_empty()
+        || trimmed != name
+        || name.contains(['/', '\\', '.', ':', '*', '?', '"', '<', '>', '|'])
+    {
+        return Err(invalid_input(format!("Invalid preset name: {}", name)));
+    }
+    Ok(())
+}
+
+fn preset_path(presets_dir: &Path, name: &str) -> io::Result<PathBuf> {
+    validate_name(name)?;
+    Ok(presets_dir.join(format!("{}.json", name)))
+}
+
+/// 列出全部预设, 按名称排序; 无法读取的文件跳过
+pub fn list_presets(presets_dir: &Path) -> io::Result<Vec<ObservePreset>> {
+    let mut presets = vec![];
+    if !presets_dir.exists() {
+        return Ok(presets);
+    }
+
+    for entry in std::fs::read_dir(presets_dir)?.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) == Some("json") {
+            match ObservePreset::load(&path) {
+                Ok(preset) => presets.push(preset),
+                Err(e) => eprintln!("Failed to load preset {}: {:?}", path.display(), e),
+            }
+        }
+    }
+    presets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(presets)
+}
+
+/// 按名称读取预设
+pub fn load_preset(presets_dir: &Path, name: &str) -> io::Result<ObservePreset> {
+    let path = preset_path(presets_dir, name)?;
+    if !path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Preset not found: {}", name),
+        ));
+    }
+    ObservePreset::load(&path)
+}
+
+/// 保存预设, 同名预设被覆盖
+pub fn save_preset(presets_dir: &Path, mut preset: ObservePreset) -> io::Result<ObservePreset> {
+    preset.validate()?;
+    preset.version = PRESET_VERSION;
+    preset.updated = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    std::fs::create_dir_all(presets_dir)?;
+    preset.write(&preset_path(presets_dir, &preset.name)?)?;
+    Ok(preset)
+}
+
+/// 删除预设
+pub fn delete_preset(presets_dir: &Path, name: &str) -> io::Result<()> {
+    std::fs::remove_file(preset_path(presets_dir, name)?)
+}
+
+/// 从文件导入预设; 同名预设已存在且不覆盖时返回 AlreadyExists
+pub fn import_preset(
+    presets_dir: &Path,
+    source: &Path,
+    overwrite: bool,
+) -> io::Result<ObservePreset> {
+    let preset = ObservePreset::load(source)?;
+    preset.validate()?;
+    if !overwrite && preset_path(presets_dir, &preset.name)?.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Preset already exists: {}", preset.name),
+        ));
+    }
+    save_preset(presets_dir, preset)
+}
+
+/// 导出预设到文件
+pub fn export_preset(presets_dir: &Path, name: &str, dest: &Path) -> io::Result<()> {
+    load_preset(presets_dir, name)?.write(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::arm_service::structs::Hertz;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ufactory_presets_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn preset(name: &str) -> ObservePreset {
+        ObservePreset {
+            version: 0,
+            name: name.to_string(),
+            description: String::new(),
+            params: ObserveParams {
+                hz: Hertz::Hz250,
+                ..Default::default()
+            },
+            channels: vec![ObserveType::ActualJointCurrents],
+            filters: vec![],
+            alarms: vec![],
+            updated: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_save_export_import() {
+        let dir = temp_dir("library");
+        let presets_dir = dir.join(PRESETS_DIR);
+
+        save_preset(&presets_dir, preset("welding line")).unwrap();
+        save_preset(&presets_dir, preset("assembly")).unwrap();
+        assert!(save_preset(&presets_dir, preset("../escape")).is_err());
+        let names: Vec<String> = list_presets(&presets_dir)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, ["assembly", "welding line"]);
+
+        // 导出后在另一台电脑 (另一个库) 导入
+        let exported = dir.join("shared.json");
+        export_preset(&presets_dir, "welding line", &exported).unwrap();
+        let other_dir = dir.join("other");
+        let imported = import_preset(&other_dir, &exported, false).unwrap();
+        assert_eq!(imported.version, PRESET_VERSION);
+        assert!(matches!(imported.params.hz, Hertz::Hz250));
+        assert_eq!(imported.channels, vec![ObserveType::ActualJointCurrents]);
+
+        let err = import_preset(&other_dir, &exported, false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(import_preset(&other_dir, &exported, true).is_ok());
+
+        // 读取与删除同样校验名称, 不能访问预设目录以外的文件
+        std::fs::write(dir.join("outside.json"), "{}").unwrap();
+        for name in ["../outside", "..\\outside"] {
+            let err = load_preset(&presets_dir, name).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert!(delete_preset(&presets_dir, name).is_err());
+        }
+        assert!(dir.join("outside.json").exists());
+
+        delete_preset(&presets_dir, "assembly").unwrap();
+        assert_eq!(
+            load_preset(&presets_dir, "assembly").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
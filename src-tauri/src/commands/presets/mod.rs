@@ -0,0 +1,107 @@
+pub mod library;
+pub mod runtime;
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    commands::arm_service::start_observer,
+    result_response,
+    state::app_state::AppState,
+    utils::{error::AppError, response::Response},
+};
+use library::{ObservePreset, PRESETS_DIR};
+
+fn presets_dir(state: &AppState) -> PathBuf {
+    state.user_data_paths.config.join(PRESETS_DIR)
+}
+
+fn preset_load_error(name: &str, e: std::io::Error) -> AppError {
+    AppError::PresetLoad {
+        name: name.to_string(),
+        reason: e.to_string(),
+    }
+}
+
+/// 全部观测预设
+#[tauri::command]
+pub fn list_presets(state: tauri::State<AppState>) -> Response<Vec<ObservePreset>> {
+    library::list_presets(&presets_dir(&state))
+        .map_err(|e| format!("Failed to list presets: {:?}", e))
+        .into()
+}
+
+#[tauri::command]
+pub fn get_preset(state: tauri::State<AppState>, name: &str) -> Response<ObservePreset> {
+    library::load_preset(&presets_dir(&state), name)
+        .map_err(|e| preset_load_error(name, e))
+        .into()
+}
+
+/// 保存预设, 同名预设被覆盖
+#[tauri::command]
+pub fn save_preset(
+    state: tauri::State<AppState>,
+    preset: ObservePreset,
+) -> Response<ObservePreset> {
+    library::save_preset(&presets_dir(&state), preset)
+        .map_err(|e| format!("Failed to save preset: {}", e))
+        .into()
+}
+
+#[tauri::command]
+pub fn delete_preset(state: tauri::State<AppState>, name: &str) -> Response<String> {
+    library::delete_preset(&presets_dir(&state), name)
+        .map(|_| "Delete preset successfully".to_string())
+        .map_err(|e| format!("Failed to delete preset: {:?}", e))
+        .into()
+}
+
+/// 从其他电脑导出的文件导入预设, 默认不覆盖同名预设
+#[tauri::command]
+pub fn import_preset(
+    state: tauri::State<AppState>,
+    path: &str,
+    overwrite: Option<bool>,
+) -> Response<ObservePreset> {
+    library::import_preset(
+        &presets_dir(&state),
+        Path::new(path),
+        overwrite.unwrap_or(false),
+    )
+    .map_err(|e| format!("Failed to import preset: {}", e))
+    .into()
+}
+
+#[tauri::command]
+pub fn export_preset(state: tauri::State<AppState>, name: &str, path: &str) -> Response<String> {
+    library::export_preset(&presets_dir(&state), name, Path::new(path))
+        .map(|_| "Export preset successfully".to_string())
+        .map_err(|e| format!("Failed to export preset: {}", e))
+        .into()
+}
+
+/// 按预设开始观测, 录制期间应用预设的滤波与报警 (ROBOT_ALARM 事件)
+#[tauri::command(async)]
+pub async fn start_assistant_with_preset(
+    state: tauri::State<'_, AppState>,
+    name: &str,
+    robot_id: Option<String>,
+) -> Result<Response<String>, Response<String>> {
+    let preset = match library::load_preset(&presets_dir(&state), name) {
+        Ok(preset) => preset,
+        Err(e) => return Ok(preset_load_error(name, e).into()),
+    };
+    if let Err(e) = preset.validate() {
+        return Ok(AppError::PresetInvalid(e.to_string()).into());
+    }
+
+    result_response!(
+        start_observer(
+            &state,
+            preset.params.clone(),
+            robot_id.as_deref(),
+            Some(preset.runtime()),
+        )
+        .await
+    )
+}
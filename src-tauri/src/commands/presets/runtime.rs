@@ -0,0 +1,234 @@
+// runtime.rs - 观测预设在采集过程中的显示滤波与报警
+use crate::commands::{
+    analysis::recording::packet_values,
+    arm_service::{
+        robot_data::RobotDataPacket,
+        structs::{ChartData, ObserveType, ResponseChartData},
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+// 滑动平均窗口上限 (帧)
+pub const MAX_FILTER_WINDOW: usize = 1000;
+
+/// 显示滤波: 对推送到前端的通道做滑动平均, 不影响 CSV 与原始数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelFilter {
+    pub channel: ObserveType,
+    pub window: usize, // 滑动平均窗口 (帧)
+}
+
+/// 报警规则, 阈值为控制器原始单位 (rad, mm, A, N*m 等)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlarmRule {
+    pub channel: ObserveType,
+    #[serde(default)]
+    pub joint: Option<usize>, // 关节/方向序号, 从 1 开始, 为空时检查全部
+    #[serde(default)]
+    pub min: Option<f32>,
+    #[serde(default)]
+    pub max: Option<f32>,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// 报警状态变化, 超限与恢复各推送一次
+#[derive(Debug, Clone, Serialize)]
+pub struct AlarmEvent {
+    pub rule: usize, // 规则在预设中的序号
+    pub channel: ObserveType,
+    pub joint: usize,
+    pub value: f32,
+    pub active: bool, // true: 超限, false: 恢复
+    pub message: String,
+}
+
+impl AlarmRule {
+    fn exceeded(&self, value: f32) -> bool {
+        self.min.is_some_and(|min| value < min) || self.max.is_some_and(|max| value > max)
+    }
+}
+
+/// 运行中的预设: 记录滤波历史与各规则的报警状态
+#[derive(Debug)]
+pub struct PresetRuntime {
+    pub name: String,
+    pub channels: Vec<ObserveType>,
+    filters: Vec<ChannelFilter>,
+    history: HashMap<usize, Vec<VecDeque<f32>>>, // 滤波序号 -> 各分量的窗口
+    alarms: Vec<AlarmRule>,
+    active: HashMap<(usize, usize), bool>, // (规则序号, 关节序号) -> 是否超限
+}
+
+impl PresetRuntime {
+    pub fn new(
+        name: &str,
+        channels: Vec<ObserveType>,
+        filters: Vec<ChannelFilter>,
+        alarms: Vec<AlarmRule>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            channels,
+            filters,
+            history: HashMap::new(),
+            alarms,
+            active: HashMap::new(),
+        }
+    }
+
+    /// 滤波后的图表数据, 没有匹配的滤波时返回 None (直接使用原数据)
+    pub fn filter(&mut self, data: &ResponseChartData) -> Option<ResponseChartData> {
+        if !data
+            .data
+            .iter()
+            .any(|chart| self.filters.iter().any(|f| f.channel == chart.data_type))
+        {
+            return None;
+        }
+
+        let mut filtered = Vec::with_capacity(data.data.len());
+        for chart in &data.data {
+            let Some(index) = self
+                .filters
+                .iter()
+                .position(|f| f.channel == chart.data_type)
+            else {
+                filtered.push(ChartData {
+                    data_type: chart.data_type,
+                    value: chart.value.clone(),
+                });
+                continue;
+            };
+
+            let window = self.filters[index].window.max(1);
+            let history = self.history.entry(index).or_default();
+            history.resize_with(chart.value.len(), VecDeque::new);
+            let value = chart
+                .value
+                .iter()
+                .zip(history.iter_mut())
+                .map(|(&v, samples)| {
+                    samples.push_back(v);
+                    while samples.len() > window {
+                        samples.pop_front();
+                    }
+                    samples.iter().sum::<f32>() / samples.len() as f32
+                })
+                .collect();
+            filtered.push(ChartData {
+                data_type: chart.data_type,
+                value,
+            });
+        }
+
+        Some(ResponseChartData {
+            data: filtered,
+            date: data.date.clone(),
+        })
+    }
+
+    /// 检查报警规则, 返回状态发生变化的报警
+    pub fn check_alarms(&mut self, packet: &RobotDataPacket) -> Vec<AlarmEvent> {
+        let mut events = vec![];
+        for (rule_index, rule) in self.alarms.iter().enumerate() {
+            let values = packet_values(packet, rule.channel);
+            for (i, &value) in values.iter().enumerate() {
+                let joint = i + 1;
+                if rule.joint.is_some_and(|j| j != joint) {
+                    continue;
+                }
+
+                let active = rule.exceeded(value);
+                let previous = self.active.insert((rule_index, joint), active);
+                if previous.unwrap_or(false) != active {
+                    events.push(AlarmEvent {
+                        rule: rule_index,
+                        channel: rule.channel,
+                        joint,
+                        value,
+                        active,
+                        message: rule.message.clone(),
+                    });
+                }
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moving_average_filter() {
+        let filters = vec![ChannelFilter {
+            channel: ObserveType::ActualJointCurrents,
+            window: 2,
+        }];
+        let mut runtime = PresetRuntime::new("test", vec![], filters, vec![]);
+
+        let frame = |v: f32| ResponseChartData {
+            data: vec![
+                ChartData {
+                    data_type: ObserveType::ActualJointCurrents,
+                    value: vec![v, -v],
+                },
+                ChartData {
+                    data_type: ObserveType::ActualJointPositions,
+                    value: vec![v],
+                },
+            ],
+            date: String::new(),
+        };
+
+        let first = runtime.filter(&frame(1.0)).unwrap();
+        assert_eq!(first.data[0].value, vec![1.0, -1.0]);
+        let second = runtime.filter(&frame(3.0)).unwrap();
+        assert_eq!(second.data[0].value, vec![2.0, -2.0]);
+        let third = runtime.filter(&frame(5.0)).unwrap();
+        assert_eq!(third.data[0].value, vec![4.0, -4.0]);
+        // 未配置滤波的通道保持原值
+        assert_eq!(third.data[1].value, vec![5.0]);
+
+        let unfiltered = ResponseChartData {
+            data: vec![ChartData {
+                data_type: ObserveType::ActualTcpPose,
+                value: vec![1.0],
+            }],
+            date: String::new(),
+        };
+        assert!(runtime.filter(&unfiltered).is_none());
+    }
+
+    #[test]
+    fn test_alarm_transitions() {
+        let alarms = vec![AlarmRule {
+            channel: ObserveType::ActualJointCurrents,
+            joint: Some(2),
+            min: None,
+            max: Some(1.5),
+            message: "J2 overcurrent".to_string(),
+        }];
+        let mut runtime = PresetRuntime::new("test", vec![], vec![], alarms);
+        let mut packet = RobotDataPacket::from_bytes(&[0u8; 784]).unwrap();
+
+        packet.actual_joint_currents[0] = 5.0; // 其他关节不检查
+        assert!(runtime.check_alarms(&packet).is_empty());
+
+        packet.actual_joint_currents[1] = 2.0;
+        let events = runtime.check_alarms(&packet);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].active);
+        assert_eq!(events[0].joint, 2);
+        // 持续超限不重复推送
+        assert!(runtime.check_alarms(&packet).is_empty());
+
+        packet.actual_joint_currents[1] = 1.0;
+        let events = runtime.check_alarms(&packet);
+        assert_eq!(events.len(), 1);
+        assert!(!events[0].active);
+    }
+}
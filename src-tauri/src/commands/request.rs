@@ -1,4 +1,4 @@
-use crate::state::app_state::AppState;
+use crate::{state::app_state::AppState, utils::error::AppError};
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -23,7 +23,7 @@ pub async fn fetch_history_releases<R: tauri::Runtime>(
     _app: tauri::AppHandle<R>,
     state: tauri::State<'_, AppState>,
     version: String,
-) -> Result<Notes, String> {
+) -> Result<Notes, AppError> {
     // 目标 URL
     let url = format!(
         "http://192.168.1.19/releases/xarm/assistant/history/{}.json",
@@ -33,18 +33,26 @@ pub async fn fetch_history_releases<R: tauri::Runtime>(
     // 在锁的作用域内获取客户端
     let client = state.client.lock().unwrap().clone();
     // 发起 GET 请求
-    let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| AppError::HttpRequest(e.to_string()))?;
 
     // 检查响应状态
     if !response.status().is_success() {
-        return Err(format!("HTTP error: {}", response.status()));
+        return Err(AppError::HttpStatus(response.status().as_u16()));
     }
 
     // 解析 JSON 数据到 Release 结构体
-    let release: Release = response.json().await.map_err(|e| e.to_string())?;
+    let release: Release = response
+        .json()
+        .await
+        .map_err(|e| AppError::InvalidResponse(e.to_string()))?;
 
     // 将 notes 字段（JSON 字符串）解析为 Notes 结构体
-    let notes: Notes = serde_json::from_str(&release.notes).map_err(|e| e.to_string())?;
+    let notes: Notes = serde_json::from_str(&release.notes)
+        .map_err(|e| AppError::InvalidResponse(e.to_string()))?;
 
     // 直接返回 Notes 结构体
     Ok(notes)
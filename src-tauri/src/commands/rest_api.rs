@@ -0,0 +1,442 @@
+// rest_api.rs - 本地 REST 控制接口, 供测试脚本等自动化工具调用
+//
+// 只监听 127.0.0.1, 请求需携带 "Authorization: Bearer <token>",
+// 返回与 Tauri 命令相同的 {"code":0,"data":...,"message":"success"}
+//   POST /api/connect     {"ip":"192.168.1.100"}
+//   POST /api/disconnect
+//   POST /api/start       ObserveParams, 与 start_assistant 参数相同
+//   POST /api/stop
+//   POST /api/save        {"path":"D:/run.csv","format":"csv","channels":[...]}
+//   GET  /api/recording?format=parquet   导出当前录制并直接返回文件内容
+//   GET  /api/state       SharedState
+//   GET  /api/axis
+//   GET  /api/robots      已连接的机械臂
+// 同时连接多台机械臂时用查询参数 robot_id 指定机械臂 (默认为 IP), 如 /api/start?robot_id=192.168.1.100
+//
+// 示例: curl -H "Authorization: Bearer $TOKEN" -d '{"ip":"192.168.1.100"}' http://127.0.0.1:18080/api/connect
+use crate::{
+    commands::{
+        arm_service::{
+            connect_robot_server, disconnect_robot_server, get_robot_axis, list_robots, save_csv,
+            start_assistant, stop_assistant,
+            structs::{ObserveParams, ObserveType},
+        },
+        get_shared_state,
+        sessions::export::ExportFormat,
+        streaming::bind_http_server,
+    },
+    state::app_state::AppState,
+    utils::{error::AppError, response::Response},
+};
+use chrono::Local;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fmt,
+    io::{self, Read},
+    net::SocketAddr,
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, Method, Request, Server};
+
+/// 旧版本的配置文件名 (位于配置目录), 现保存在用户设置中
+pub const CONFIG_FILE: &str = "rest_api.json";
+
+// 请求体最大字节数
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+/// REST 接口配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RestApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String, // 为空时自动生成
+}
+
+impl Default for RestApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 18080,
+            token: String::new(),
+        }
+    }
+}
+
+/// 生成 128 位随机令牌 (十六进制)
+fn generate_token() -> io::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// 检查 Authorization 头
+fn authorized(request: &Request, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    request
+        .headers()
+        .iter()
+        .any(|h| h.field.equiv("Authorization") && h.value.as_str() == expected)
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectRequest {
+    ip: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SaveRequest {
+    path: String,
+    format: Option<ExportFormat>,
+    channels: Option<Vec<ObserveType>>,
+}
+
+/// 响应: 状态码 + 内容
+enum Reply {
+    Json(u16, String),
+    File(Vec<u8>, String),
+}
+
+impl Reply {
+    fn json<T: Serialize>(response: Response<T>) -> Self {
+        Reply::Json(200, serde_json::to_string(&response).unwrap_or_default())
+    }
+
+    /// 异步命令的返回值 (成功与失败均为 Response)
+    fn command<T: Serialize>(result: Result<Response<T>, Response<String>>) -> Self {
+        match result {
+            Ok(response) => Self::json(response),
+            Err(response) => Self::json(response),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Reply::Json(
+            status,
+            serde_json::to_string(&Response::<()>::error(message)).unwrap_or_default(),
+        )
+    }
+}
+
+fn parse_body<T: DeserializeOwned>(body: &str) -> Result<T, Reply> {
+    serde_json::from_str(body).map_err(|e| Reply::error(400, format!("Invalid body: {}", e)))
+}
+
+/// 查询参数
+fn query_param(query: &str, name: &str) -> Option<String> {
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.into_owned())
+}
+
+/// 导出当前录制到临时文件并读取内容
+fn download_recording(app: &AppHandle, query: &str, robot_id: Option<String>) -> Reply {
+    let format = match query_param(query, "format") {
+        Some(format) => match serde_json::from_value::<ExportFormat>(Value::String(format)) {
+            Ok(format) => format,
+            Err(e) => return Reply::error(400, format!("Invalid format: {}", e)),
+        },
+        None => ExportFormat::Csv,
+    };
+
+    let file_name = format!(
+        "robot_data_{}.{}",
+        Local::now().format("%Y%m%d_%H%M%S"),
+        format.extension()
+    );
+    let path = std::env::temp_dir().join(format!("ufactory_api_{}", file_name));
+    let response = save_csv(
+        app.state::<AppState>(),
+        &path.to_string_lossy(),
+        Some(format),
+        None,
+        robot_id,
+    );
+    if response.code != 0 {
+        return Reply::json(response);
+    }
+
+    let content = std::fs::read(&path);
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(path.with_extension("raw"));
+    match content {
+        Ok(content) => Reply::File(content, file_name),
+        Err(e) => Reply::error(500, format!("Failed to read exported file: {:?}", e)),
+    }
+}
+
+fn route(app: &AppHandle, method: &Method, url: &str, body: &str) -> Result<Reply, Reply> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let state = || app.state::<AppState>();
+    let robot_id = query_param(query, "robot_id");
+
+    let reply = match (method, path) {
+        (Method::Post, "/api/connect") => {
+            let request: ConnectRequest = parse_body(body)?;
+            Reply::command(tauri::async_runtime::block_on(connect_robot_server(
+                app.clone(),
+                state(),
+                &request.ip,
+                robot_id,
+            )))
+        }
+        (Method::Post, "/api/disconnect") => Reply::command(tauri::async_runtime::block_on(
+            disconnect_robot_server(state(), robot_id),
+        )),
+        (Method::Post, "/api/start") => {
+            let params: ObserveParams = parse_body(body)?;
+            Reply::json(start_assistant(state(), params, robot_id))
+        }
+        (Method::Post, "/api/stop") => Reply::json(stop_assistant(state(), robot_id)),
+        (Method::Post, "/api/save") => {
+            let request: SaveRequest = parse_body(body)?;
+            Reply::json(save_csv(
+                state(),
+                &request.path,
+                request.format,
+                request.channels,
+                robot_id,
+            ))
+        }
+        (Method::Get, "/api/recording") => download_recording(app, query, robot_id),
+        (Method::Get, "/api/state") => Reply::command(tauri::async_runtime::block_on(
+            get_shared_state(state(), robot_id),
+        )),
+        (Method::Get, "/api/axis") => Reply::command(tauri::async_runtime::block_on(
+            get_robot_axis(state(), robot_id),
+        )),
+        (Method::Get, "/api/robots") => Reply::json(list_robots(state())),
+        _ => Reply::error(404, format!("Unknown endpoint: {} {}", method, path)),
+    };
+    Ok(reply)
+}
+
+fn handle_request(app: &AppHandle, token: &str, mut request: Request) -> io::Result<()> {
+    let reply = if !authorized(&request, token) {
+        Reply::error(401, "Unauthorized")
+    } else {
+        let mut body = String::new();
+        match request
+            .as_reader()
+            .take(MAX_BODY_SIZE)
+            .read_to_string(&mut body)
+        {
+            Ok(_) => {
+                let method = request.method().clone();
+                let url = request.url().to_string();
+                route(app, &method, &url, &body).unwrap_or_else(|reply| reply)
+            }
+            Err(e) => Reply::error(400, format!("Failed to read body: {:?}", e)),
+        }
+    };
+
+    let header = |field: &str, value: &str| Header::from_bytes(field, value).expect("valid header");
+    match reply {
+        Reply::Json(status, content) => request.respond(
+            tiny_http::Response::from_string(content)
+                .with_status_code(status)
+                .with_header(header("Content-Type", "application/json")),
+        ),
+        Reply::File(content, file_name) => request.respond(
+            tiny_http::Response::from_data(content)
+                .with_header(header("Content-Type", "application/octet-stream"))
+                .with_header(header(
+                    "Content-Disposition",
+                    &format!("attachment; filename=\"{}\"", file_name),
+                )),
+        ),
+    }
+}
+
+/// 运行中的 REST 接口, 停止或释放时关闭
+pub struct RestApiServer {
+    addr: SocketAddr,
+    token: String,
+    server: Arc<Server>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RestApiServer {
+    /// 绑定 127.0.0.1 并在独立线程中依次处理请求
+    pub fn start(config: &RestApiConfig, app: AppHandle) -> io::Result<Self> {
+        if config.token.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "REST API token is empty",
+            ));
+        }
+
+        let server = Arc::new(bind_http_server("127.0.0.1", config.port)?);
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| io::Error::other("REST API is not bound to an IP address"))?;
+
+        let http_server = server.clone();
+        let token = config.token.clone();
+        let handle = thread::spawn(move || {
+            for request in http_server.incoming_requests() {
+                if let Err(e) = handle_request(&app, &token, request) {
+                    eprintln!("Failed to respond REST API request: {:?}", e);
+                }
+            }
+        });
+        eprintln!("REST API listening on http://{}", addr);
+
+        Ok(Self {
+            addr,
+            token: config.token.clone(),
+            server,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 已按该配置运行, 无需重新绑定
+    fn serves(&self, config: &RestApiConfig) -> bool {
+        self.addr.port() == config.port && self.token == config.token
+    }
+
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    /// 处理完已收到的请求后结束线程; 线程持有的 Server 随之释放, 端口开始关闭
+    fn shutdown(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                eprintln!("Failed to join REST API thread");
+            }
+        }
+    }
+}
+
+impl fmt::Debug for RestApiServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RestApiServer")
+            .field("addr", &self.addr)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for RestApiServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestApiStatus {
+    pub running: bool,
+    pub address: Option<String>,
+}
+
+/// 读取配置, 首次使用时生成并保存令牌
+fn load_rest_api_config(state: &AppState) -> Result<RestApiConfig, AppError> {
+    let mut config = state.settings()?.rest_api;
+    if config.token.is_empty() {
+        config.token = generate_token().map_err(|e| AppError::TokenGenerate(e.to_string()))?;
+        state.update_settings(|s| s.rest_api = config.clone())?;
+    }
+    Ok(config)
+}
+
+/// 按配置启动 REST 接口, 未启用时只停止已有的接口
+fn apply_rest_api(app: &AppHandle, config: &RestApiConfig) -> Result<(), AppError> {
+    let state = app.state::<AppState>();
+    let mut guard = state
+        .rest_api
+        .lock()
+        .map_err(|_| AppError::LockPoisoned("rest_api"))?;
+    if config.enabled && guard.as_ref().is_some_and(|server| server.serves(config)) {
+        return Ok(());
+    }
+    // 等待旧的接口线程结束后再绑定新端口
+    if let Some(server) = guard.take() {
+        server.stop();
+    }
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let server = RestApiServer::start(config, app.clone()).map_err(|e| AppError::ServiceStart {
+        service: "REST API",
+        addr: format!("127.0.0.1:{}", config.port),
+        reason: e.to_string(),
+    })?;
+    *guard = Some(server);
+    Ok(())
+}
+
+/// 启动时按保存的配置恢复 REST 接口
+pub fn restore_rest_api(app: &AppHandle) {
+    let result = load_rest_api_config(&app.state::<AppState>())
+        .and_then(|config| apply_rest_api(app, &config));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+#[tauri::command]
+pub fn get_rest_api_config(state: tauri::State<AppState>) -> Response<RestApiConfig> {
+    load_rest_api_config(&state).into()
+}
+
+/// 保存 REST 接口配置并立即生效, 令牌为空时重新生成
+#[tauri::command]
+pub fn set_rest_api_config(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    mut config: RestApiConfig,
+) -> Response<RestApiConfig> {
+    if config.token.is_empty() {
+        config.token = match generate_token() {
+            Ok(token) => token,
+            Err(e) => return AppError::TokenGenerate(e.to_string()).into(),
+        };
+    }
+    if let Err(e) = state.update_settings(|s| s.rest_api = config.clone()) {
+        return e.into();
+    }
+
+    match apply_rest_api(&app, &config) {
+        Ok(()) => Response::success(config),
+        Err(e) => e.into(),
+    }
+}
+
+#[tauri::command]
+pub fn get_rest_api_status(state: tauri::State<AppState>) -> Response<RestApiStatus> {
+    match state.rest_api.lock() {
+        Ok(server) => Response::success(RestApiStatus {
+            running: server.is_some(),
+            address: server.as_ref().map(|s| format!("http://{}", s.addr())),
+        }),
+        Err(_) => AppError::LockPoisoned("rest_api").into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_and_query() {
+        let token = generate_token().unwrap();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, generate_token().unwrap());
+
+        assert_eq!(
+            query_param("format=parquet&x=1", "format").as_deref(),
+            Some("parquet")
+        );
+        assert_eq!(query_param("", "format"), None);
+    }
+}
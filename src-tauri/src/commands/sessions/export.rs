@@ -0,0 +1,301 @@
+// export.rs - 录制导出 (CSV 以外的格式由原始数据或CSV转换生成)
+use crate::commands::{
+    analysis::recording::{channel_unit, Recording},
+    arm_service::{
+        csv_exporter::{copy_localized, read_raw_packets, CsvExporter},
+        structs::{ObserveParams, ObserveType, Unit},
+    },
+    sessions::{
+        library::{SessionInfo, SessionMeta},
+        mat_exporter::write_mat,
+        mcap_exporter::write_mcap,
+        numpy_exporter::write_npz,
+        parquet_exporter::write_parquet,
+    },
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{self},
+    path::Path,
+};
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Parquet,
+    Mcap,
+    Npz,
+    Mat,
+}
+
+impl ExportFormat {
+    /// 导出文件的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Mcap => "mcap",
+            ExportFormat::Npz => "npz",
+            ExportFormat::Mat => "mat",
+        }
+    }
+}
+
+/// 写入导出文件的会话信息
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportMeta {
+    pub name: String,
+    pub robot_ip: String,
+    pub axis: i32,
+    pub start: String, // 本地时间 %Y-%m-%d %H:%M:%S
+    pub end: String,
+    pub observe_params: ObserveParams,
+}
+
+/// 按观测类型合并的二维数组, 行为采样, 列为关节/坐标分量 (行优先存储)
+#[derive(Debug, Clone)]
+pub struct NamedArray {
+    pub name: String,
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<f64>,
+    pub unit: String, // 各列单位相同时为单个单位, 否则逗号分隔
+}
+
+impl From<&SessionInfo> for ExportMeta {
+    fn from(session: &SessionInfo) -> Self {
+        Self {
+            name: session.name.clone(),
+            robot_ip: session.robot_ip.clone(),
+            axis: session.axis,
+            start: session.start.clone(),
+            end: session.end.clone(),
+            observe_params: session.observe_params.clone(),
+        }
+    }
+}
+
+impl ExportMeta {
+    /// 机器人轴数, 未知时按 7 轴
+    pub fn joint_count(&self) -> usize {
+        match self.axis {
+            1..=7 => self.axis as usize,
+            _ => 7,
+        }
+    }
+
+    /// 导出器当前 (未归档) 的录制
+    pub fn current(exporter: &CsvExporter, meta: SessionMeta) -> Self {
+        Self {
+            name: exporter
+                .temp_path()
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string(),
+            robot_ip: meta.robot_ip,
+            axis: meta.axis,
+            start: exporter
+                .started_at()
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            end: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            observe_params: meta.observe_params,
+        }
+    }
+}
+
+/// 导出录制文件, `channels` 为空时导出全部通道
+///
+/// CSV 格式直接复制, 表头为当前界面语言 (原始数据同名保存); MCAP 需要原始数据 (控制器时间戳);
+/// 其他格式优先由原始数据生成 (弧度), 没有原始数据时由CSV生成
+pub fn export_files(
+    csv_path: &Path,
+    raw_path: Option<&Path>,
+    dest_path: &Path,
+    format: ExportFormat,
+    channels: &[ObserveType],
+    meta: &ExportMeta,
+) -> io::Result<()> {
+    let raw_path = raw_path.filter(|p| p.exists());
+
+    match format {
+        ExportFormat::Csv => {
+            copy_localized(csv_path, dest_path)?;
+            if let Some(raw_path) = raw_path {
+                std::fs::copy(raw_path, dest_path.with_extension("raw"))?;
+            }
+            Ok(())
+        }
+        ExportFormat::Mcap => {
+            let raw_path = raw_path.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "MCAP export requires raw data (.raw)",
+                )
+            })?;
+            write_mcap(&read_raw_packets(raw_path)?, meta, dest_path)
+        }
+        ExportFormat::Parquet => {
+            let (recording, units) =
+                load_recording(csv_path, raw_path, meta.observe_params.unit, channels)?;
+            write_parquet(&recording, &units, meta, dest_path)
+        }
+        ExportFormat::Npz => {
+            let (recording, units) =
+                load_recording(csv_path, raw_path, meta.observe_params.unit, channels)?;
+            let arrays = named_arrays(&recording, &units, meta.joint_count());
+            write_npz(&arrays, meta, dest_path)
+        }
+        ExportFormat::Mat => {
+            let (recording, units) =
+                load_recording(csv_path, raw_path, meta.observe_params.unit, channels)?;
+            let arrays = named_arrays(&recording, &units, meta.joint_count());
+            write_mat(&arrays, meta, dest_path)
+        }
+    }
+}
+
+/// 通道名 {类型}_{序号} 拆分为类型与序号, 其他通道 (如 motion_state) 没有序号
+fn split_channel(name: &str) -> (&str, Option<usize>) {
+    match name.rsplit_once('_') {
+        Some((prefix, index)) => match index.parse() {
+            Ok(index) => (prefix, Some(index)),
+            Err(_) => (name, None),
+        },
+        None => (name, None),
+    }
+}
+
+/// 读取录制, 只保留选中类型的通道, 并确定各通道单位
+fn load_recording(
+    csv_path: &Path,
+    raw_path: Option<&Path>,
+    angle_unit: Unit,
+    channels: &[ObserveType],
+) -> io::Result<(Recording, Vec<&'static str>)> {
+    let raw = raw_path.filter(|p| std::fs::metadata(p).is_ok_and(|m| m.len() > 0));
+    let (mut recording, angle_unit) = match raw {
+        Some(raw_path) => (Recording::load(raw_path)?, Unit::Radian),
+        None => (Recording::from_csv(csv_path)?, angle_unit),
+    };
+
+    if !channels.is_empty() {
+        let names: Vec<String> = channels.iter().map(|ot| ot.name()).collect();
+        recording
+            .channels
+            .retain(|c| names.iter().any(|n| n == split_channel(&c.name).0));
+    }
+
+    let units = recording
+        .channels
+        .iter()
+        .map(|c| channel_unit(&c.name, angle_unit))
+        .collect();
+
+    Ok((recording, units))
+}
+
+/// 将通道按类型合并为 [N, 分量数] 数组, 第一个数组为时间 t (单列);
+/// 关节类型只保留前 `joint_count` 个关节
+pub fn named_arrays(recording: &Recording, units: &[&str], joint_count: usize) -> Vec<NamedArray> {
+    let rows = recording.time.len();
+    let mut arrays = vec![NamedArray {
+        name: "t".to_string(),
+        rows,
+        cols: 1,
+        data: recording.time.clone(),
+        unit: "s".to_string(),
+    }];
+
+    // (名称, 列索引, 列单位)
+    let mut groups: Vec<(String, Vec<usize>, Vec<&str>)> = vec![];
+    for (i, series) in recording.channels.iter().enumerate() {
+        let (name, index) = split_channel(&series.name);
+        if name.contains("joint") && index.is_some_and(|index| index > joint_count) {
+            continue;
+        }
+        let unit = units.get(i).copied().unwrap_or_default();
+        match groups.iter_mut().find(|(n, _, _)| n == name) {
+            Some((_, columns, column_units)) => {
+                columns.push(i);
+                column_units.push(unit);
+            }
+            None => groups.push((name.to_string(), vec![i], vec![unit])),
+        }
+    }
+
+    for (name, columns, column_units) in groups {
+        let mut data = Vec::with_capacity(rows * columns.len());
+        for row in 0..rows {
+            for col in &columns {
+                data.push(recording.channels[*col].values[row]);
+            }
+        }
+
+        let unit = if column_units.iter().all(|u| *u == column_units[0]) {
+            column_units[0].to_string()
+        } else {
+            column_units.join(",")
+        };
+
+        arrays.push(NamedArray {
+            name,
+            rows,
+            cols: columns.len(),
+            data,
+            unit,
+        });
+    }
+
+    arrays
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::analysis::recording::Series;
+
+    #[test]
+    fn test_named_arrays_group_by_type() {
+        let series = |name: &str, v: f64| Series {
+            name: name.to_string(),
+            values: vec![v, v + 10.0],
+        };
+        let recording = Recording {
+            time: vec![0.0, 0.004],
+            channels: vec![
+                series("actual_joint_positions_1", 1.0),
+                series("actual_joint_positions_2", 2.0),
+                series("actual_joint_positions_7", 7.0),
+                series("actual_tcp_pose_3", 3.0),
+                series("actual_tcp_pose_4", 4.0),
+                series("motion_state", 1.0),
+            ],
+        };
+        let units = ["rad", "rad", "rad", "mm", "rad", ""];
+
+        let arrays = named_arrays(&recording, &units, 6);
+        let names: Vec<&str> = arrays.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "t",
+                "actual_joint_positions",
+                "actual_tcp_pose",
+                "motion_state"
+            ]
+        );
+
+        // 6 轴机器人不导出第 7 关节, 数据按行存储
+        let joints = &arrays[1];
+        assert_eq!((joints.rows, joints.cols), (2, 2));
+        assert_eq!(joints.data, vec![1.0, 2.0, 11.0, 12.0]);
+        assert_eq!(joints.unit, "rad");
+        assert_eq!(arrays[2].unit, "mm,rad");
+    }
+}
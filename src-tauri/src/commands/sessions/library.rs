@@ -0,0 +1,511 @@
+// library.rs - 会话库: 自动保存每次录制, 并维护 JSON 索引
+use crate::commands::{
+    arm_service::{
+        csv_exporter::{CsvExporter, RecordingMarker},
+        structs::{ObserveParams, ObserveType},
+    },
+    sessions::export::{export_files, ExportFormat, ExportMeta},
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+// 索引文件名 (位于 UserDataPaths.csv_data)
+const INDEX_FILE: &str = "sessions.json";
+// 索引格式版本
+const INDEX_VERSION: u32 = 1;
+
+/// 会话元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub name: String,
+    pub robot_ip: String,
+    pub axis: i32,
+    pub observe_params: ObserveParams,
+    pub start: String, // 本地时间 %Y-%m-%d %H:%M:%S
+    pub end: String,
+    pub duration: f64, // s
+    pub sample_count: usize,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub csv_file: String, // 相对会话库目录
+    #[serde(default)]
+    pub raw_file: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>, // 来源临时文件名 (不含扩展名), 用于启动时识别已归档的临时文件
+    #[serde(default)]
+    pub markers: Vec<RecordingMarker>, // 录制中的事件标记 (如控制器报错)
+}
+
+/// 归档时由调用方提供的录制信息
+#[derive(Debug, Clone)]
+pub struct SessionMeta {
+    pub robot_ip: String,
+    pub axis: i32,
+    pub observe_params: ObserveParams,
+}
+
+/// 会话查询条件, 未设置的条件不参与过滤
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionQuery {
+    #[serde(default)]
+    pub text: Option<String>, // 匹配名称/备注/IP
+    #[serde(default)]
+    pub tags: Vec<String>, // 需包含全部标签
+    #[serde(default)]
+    pub robot_ip: Option<String>,
+    #[serde(default)]
+    pub from: Option<String>, // 开始时间下限 (同 start 格式, 按字符串比较)
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionIndex {
+    version: u32,
+    sessions: Vec<SessionInfo>,
+}
+
+/// 会话库
+#[derive(Debug)]
+pub struct SessionLibrary {
+    dir: PathBuf,
+    sessions: Vec<SessionInfo>,
+}
+
+impl SessionQuery {
+    fn matches(&self, session: &SessionInfo) -> bool {
+        if let Some(text) = self.text.as_ref().filter(|t| !t.is_empty()) {
+            let text = text.to_lowercase();
+            let hit = [
+                &session.name,
+                &session.notes,
+                &session.robot_ip,
+                &session.id,
+            ]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&text));
+            if !hit {
+                return false;
+            }
+        }
+
+        if !self.tags.iter().all(|tag| session.tags.contains(tag)) {
+            return false;
+        }
+
+        if let Some(ip) = &self.robot_ip {
+            if &session.robot_ip != ip {
+                return false;
+            }
+        }
+
+        if let Some(from) = &self.from {
+            if session.start.as_str() < from.as_str() {
+                return false;
+            }
+        }
+
+        if let Some(to) = &self.to {
+            if session.start.as_str() > to.as_str() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl SessionLibrary {
+    /// 打开会话库, 索引不存在时创建空库;
+    /// 索引无法读取时备份为 sessions.json.<时间>.bak 并从空库开始, 录制文件保留在目录中
+    pub fn open(dir: PathBuf) -> io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+
+        let index_path = dir.join(INDEX_FILE);
+        let sessions = if index_path.exists() {
+            match read_index(&index_path) {
+                Ok(index) => index.sessions,
+                Err(e) => {
+                    let backup = dir.join(format!(
+                        "{}.{}.bak",
+                        INDEX_FILE,
+                        Local::now().format("%Y%m%d_%H%M%S")
+                    ));
+                    // 备份失败时不能继续, 否则下次保存会覆盖原索引
+                    std::fs::rename(&index_path, &backup)?;
+                    eprintln!(
+                        "Failed to read session index {}: {}, backed up to {}",
+                        index_path.display(),
+                        e,
+                        backup.display()
+                    );
+                    vec![]
+                }
+            }
+        } else {
+            vec![]
+        };
+
+        Ok(Self { dir, sessions })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 写入索引 (先写临时文件再替换, 避免写一半时损坏索引)
+    fn save_index(&self) -> io::Result<()> {
+        let index = SessionIndex {
+            version: INDEX_VERSION,
+            sessions: self.sessions.clone(),
+        };
+        let content = serde_json::to_string_pretty(&index)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let tmp_path = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(content.as_bytes())?;
+        // 替换前写入磁盘, 断电后不会留下空的索引
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(tmp_path, self.dir.join(INDEX_FILE))
+    }
+
+    /// 将录制文件复制到会话库并登记; ID 已被占用时追加序号, 不覆盖已有会话的文件
+    pub fn add_files(
+        &mut self,
+        csv_path: &Path,
+        raw_path: Option<&Path>,
+        mut info: SessionInfo,
+    ) -> io::Result<SessionInfo> {
+        info.id = unique_id(&info.id, |id| {
+            self.get(id).is_some()
+                || self.dir.join(format!("{}.csv", id)).exists()
+                || self.dir.join(format!("{}.raw", id)).exists()
+        });
+
+        let csv_file = format!("{}.csv", info.id);
+        std::fs::copy(csv_path, self.dir.join(&csv_file))?;
+        info.csv_file = csv_file;
+
+        info.raw_file = match raw_path.filter(|p| p.exists()) {
+            Some(raw_path) => {
+                let raw_file = format!("{}.raw", info.id);
+                std::fs::copy(raw_path, self.dir.join(&raw_file))?;
+                Some(raw_file)
+            }
+            None => None,
+        };
+
+        self.sessions.push(info.clone());
+        self.save_index()?;
+
+        Ok(info)
+    }
+
+    /// 归档导出器当前的录制, 没有新数据时返回 None
+    pub fn archive(
+        &mut self,
+        exporter: &mut CsvExporter,
+        meta: SessionMeta,
+    ) -> io::Result<Option<SessionInfo>> {
+        if !exporter.needs_archive() {
+            return Ok(None);
+        }
+
+        // 确保缓冲数据已写入临时文件
+        exporter.flush()?;
+
+        let start = exporter.started_at();
+        let end = Local::now();
+        let id = start.format("%Y%m%d_%H%M%S_%3f").to_string();
+
+        let info = SessionInfo {
+            name: format!("robot_data_{}", start.format("%Y%m%d_%H%M%S")),
+            id,
+            robot_ip: meta.robot_ip,
+            axis: meta.axis,
+            observe_params: meta.observe_params,
+            start: start.format("%Y-%m-%d %H:%M:%S").to_string(),
+            end: end.format("%Y-%m-%d %H:%M:%S").to_string(),
+            duration: (end - start).num_milliseconds() as f64 / 1000.0,
+            sample_count: exporter.samples(),
+            notes: String::new(),
+            tags: vec![],
+            csv_file: String::new(),
+            raw_file: None,
+            source: exporter
+                .temp_path()
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_string()),
+            markers: exporter.markers().to_vec(),
+        };
+
+        let info = self.add_files(
+            exporter.temp_path(),
+            Some(exporter.raw_path().as_path()),
+            info,
+        )?;
+        exporter.mark_archived();
+
+        Ok(Some(info))
+    }
+
+    /// 按条件查询, 按开始时间倒序
+    pub fn search(&self, query: &SessionQuery) -> Vec<SessionInfo> {
+        let mut sessions: Vec<SessionInfo> = self
+            .sessions
+            .iter()
+            .filter(|s| query.matches(s))
+            .cloned()
+            .collect();
+        sessions.sort_by(|a, b| b.start.cmp(&a.start));
+        sessions
+    }
+
+    pub fn get(&self, id: &str) -> Option<&SessionInfo> {
+        self.sessions.iter().find(|s| s.id == id)
+    }
+
+    fn get_mut(&mut self, id: &str) -> io::Result<&mut SessionInfo> {
+        self.sessions
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| session_not_found(id))
+    }
+
+    pub fn rename(&mut self, id: &str, name: &str) -> io::Result<SessionInfo> {
+        let session = self.get_mut(id)?;
+        session.name = name.to_string();
+        let session = session.clone();
+        self.save_index()?;
+        Ok(session)
+    }
+
+    /// 更新备注与标签
+    pub fn update(
+        &mut self,
+        id: &str,
+        notes: Option<String>,
+        tags: Option<Vec<String>>,
+    ) -> io::Result<SessionInfo> {
+        let session = self.get_mut(id)?;
+        if let Some(notes) = notes {
+            session.notes = notes;
+        }
+        if let Some(tags) = tags {
+            session.tags = tags;
+        }
+        let session = session.clone();
+        self.save_index()?;
+        Ok(session)
+    }
+
+    /// 删除会话及其文件; 先删除文件, 删除失败时会话仍保留在索引中
+    pub fn delete(&mut self, id: &str) -> io::Result<()> {
+        let pos = self
+            .sessions
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or_else(|| session_not_found(id))?;
+
+        let session = &self.sessions[pos];
+        for file in std::iter::once(&session.csv_file).chain(session.raw_file.iter()) {
+            let path = self.dir.join(file);
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        self.sessions.remove(pos);
+        self.save_index()
+    }
+
+    /// 按指定格式导出会话
+    pub fn export(
+        &self,
+        id: &str,
+        dest_path: &Path,
+        format: ExportFormat,
+        channels: &[ObserveType],
+    ) -> io::Result<()> {
+        let session = self.get(id).ok_or_else(|| session_not_found(id))?;
+        let raw_path = session.raw_file.as_ref().map(|f| self.dir.join(f));
+
+        export_files(
+            &self.dir.join(&session.csv_file),
+            raw_path.as_deref(),
+            dest_path,
+            format,
+            channels,
+            &ExportMeta::from(session),
+        )
+    }
+
+    /// 临时文件是否已归档过
+    pub fn has_source(&self, source: &str) -> bool {
+        self.sessions
+            .iter()
+            .any(|s| s.source.as_deref() == Some(source))
+    }
+
+    /// 会话 CSV 文件的完整路径
+    pub fn csv_path(&self, id: &str) -> Option<PathBuf> {
+        self.get(id).map(|s| self.dir.join(&s.csv_file))
+    }
+}
+
+/// 生成未被占用的 ID, 重复时依次追加 _2, _3, ...
+pub fn unique_id(base: &str, exists: impl Fn(&str) -> bool) -> String {
+    if !exists(base) {
+        return base.to_string();
+    }
+    let mut n = 2;
+    loop {
+        let id = format!("{}_{}", base, n);
+        if !exists(&id) {
+            return id;
+        }
+        n += 1;
+    }
+}
+
+fn read_index(path: &Path) -> io::Result<SessionIndex> {
+    let content = std::fs::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn session_not_found(id: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("Session not found: {}", id),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, ip: &str, tags: &[&str]) -> SessionInfo {
+        SessionInfo {
+            id: id.to_string(),
+            name: format!("robot_data_{}", id),
+            robot_ip: ip.to_string(),
+            axis: 6,
+            observe_params: ObserveParams::default(),
+            start: format!("2025-01-0{} 12:00:00", id),
+            end: format!("2025-01-0{} 12:01:00", id),
+            duration: 60.0,
+            sample_count: 100,
+            notes: String::new(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            csv_file: String::new(),
+            raw_file: None,
+            source: None,
+            markers: vec![],
+        }
+    }
+
+    #[test]
+    fn test_add_search_delete() {
+        let dir = std::env::temp_dir().join(format!(
+            "session_library_test_{}",
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv_path = dir.join("source.csv");
+        std::fs::write(&csv_path, "timestamp,col_1\n0,1\n").unwrap();
+
+        let mut library = SessionLibrary::open(dir.join("library")).unwrap();
+        library
+            .add_files(&csv_path, None, session("1", "192.168.1.10", &["cell-a"]))
+            .unwrap();
+        library
+            .add_files(&csv_path, None, session("2", "192.168.1.11", &["cell-b"]))
+            .unwrap();
+        library
+            .update("2", Some("payload 2kg".to_string()), None)
+            .unwrap();
+
+        // 同一 ID (如两台机械臂同一毫秒开始录制) 追加序号, 不覆盖已有文件
+        let duplicate = library
+            .add_files(&csv_path, None, session("1", "192.168.1.12", &[]))
+            .unwrap();
+        assert_eq!(duplicate.id, "1_2");
+        assert_eq!(duplicate.csv_file, "1_2.csv");
+        assert_eq!(library.get("1").unwrap().robot_ip, "192.168.1.10");
+        library.delete("1_2").unwrap();
+
+        // 重新打开后索引仍然有效
+        let mut library = SessionLibrary::open(dir.join("library")).unwrap();
+        let all = library.search(&SessionQuery::default());
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].id, "2");
+
+        let query = SessionQuery {
+            text: Some("PAYLOAD".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(library.search(&query).len(), 1);
+
+        let query = SessionQuery {
+            tags: vec!["cell-a".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(library.search(&query)[0].robot_ip, "192.168.1.10");
+
+        library.delete("1").unwrap();
+        assert!(!dir.join("library/1.csv").exists());
+        assert_eq!(library.search(&SessionQuery::default()).len(), 1);
+
+        // 文件无法删除时会话保留在索引中
+        std::fs::remove_file(dir.join("library/2.csv")).unwrap();
+        std::fs::create_dir(dir.join("library/2.csv")).unwrap();
+        assert!(library.delete("2").is_err());
+        assert!(library.get("2").is_some());
+        std::fs::remove_dir(dir.join("library/2.csv")).unwrap();
+        library.delete("2").unwrap();
+        assert!(SessionLibrary::open(dir.join("library"))
+            .unwrap()
+            .search(&SessionQuery::default())
+            .is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_open_corrupt_index() {
+        let dir = std::env::temp_dir().join(format!(
+            "session_library_corrupt_{}",
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(INDEX_FILE), "{\"version\":1,\"sessions\":[").unwrap();
+
+        // 损坏的索引被备份, 会话库从空库开始
+        let library = SessionLibrary::open(dir.clone()).unwrap();
+        assert!(library.search(&SessionQuery::default()).is_empty());
+        assert!(!dir.join(INDEX_FILE).exists());
+        let backups: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(backups[0].path()).unwrap(),
+            "{\"version\":1,\"sessions\":["
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
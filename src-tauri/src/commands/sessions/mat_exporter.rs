@@ -0,0 +1,234 @@
+// mat_exporter.rs - 以 MATLAB v5 .mat 格式导出录制 (每个变量 zlib 压缩)
+//
+// 读取: load(path) 后 actual_joint_positions 为 N x axis 矩阵, meta.units 为各变量单位,
+//       meta.labels 为各变量界面语言的名称
+use crate::{
+    commands::sessions::export::{ExportMeta, NamedArray},
+    utils::i18n,
+};
+use chrono::Local;
+use flate2::{write::ZlibEncoder, Compression};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+// 数据类型
+const MI_INT8: u32 = 1;
+const MI_UINT16: u32 = 4;
+const MI_INT32: u32 = 5;
+const MI_UINT32: u32 = 6;
+const MI_DOUBLE: u32 = 9;
+const MI_MATRIX: u32 = 14;
+const MI_COMPRESSED: u32 = 15;
+
+// 数组类型
+const MX_STRUCT_CLASS: u32 = 2;
+const MX_CHAR_CLASS: u32 = 4;
+const MX_DOUBLE_CLASS: u32 = 6;
+
+// 结构体字段名最大长度 (含结尾 0)
+const FIELD_NAME_LENGTH: usize = 32;
+
+/// 写入 .mat 文件
+///
+/// 变量: 各通道矩阵 (t 为 N x 1), axis 轴数, meta 结构体 (会话信息, units 为各变量单位, labels 为名称)
+pub fn write_mat(arrays: &[NamedArray], meta: &ExportMeta, path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(&header())?;
+
+    for array in arrays {
+        write_variable(&mut out, &double_matrix(&array.name, array))?;
+    }
+
+    let axis = NamedArray {
+        name: "axis".to_string(),
+        rows: 1,
+        cols: 1,
+        data: vec![meta.axis as f64],
+        unit: String::new(),
+    };
+    write_variable(&mut out, &double_matrix("axis", &axis))?;
+
+    let units: Vec<(String, Vec<u8>)> = arrays
+        .iter()
+        .map(|a| (a.name.clone(), char_matrix("", &a.unit)))
+        .collect();
+    let language = i18n::language();
+    let labels: Vec<(String, Vec<u8>)> = arrays
+        .iter()
+        .map(|a| {
+            let label = i18n::channel_label(language, &a.name);
+            (a.name.clone(), char_matrix("", &label))
+        })
+        .collect();
+    let hz = NamedArray {
+        name: "hz".to_string(),
+        rows: 1,
+        cols: 1,
+        data: vec![meta.observe_params.hz as u8 as f64],
+        unit: String::new(),
+    };
+    let fields = vec![
+        ("name".to_string(), char_matrix("", &meta.name)),
+        ("robot_ip".to_string(), char_matrix("", &meta.robot_ip)),
+        ("start".to_string(), char_matrix("", &meta.start)),
+        ("end".to_string(), char_matrix("", &meta.end)),
+        ("hz".to_string(), double_matrix("", &hz)),
+        ("units".to_string(), struct_matrix("", &units)),
+        ("labels".to_string(), struct_matrix("", &labels)),
+    ];
+    write_variable(&mut out, &struct_matrix("meta", &fields))?;
+
+    out.flush()
+}
+
+/// 128 字节文件头: 说明文本 (116) + 子系统偏移 (8) + 版本 + 字节序标记
+fn header() -> Vec<u8> {
+    let text = format!(
+        "MATLAB 5.0 MAT-file, Platform: {}, Created on: {}",
+        std::env::consts::OS,
+        Local::now().format("%a %b %e %H:%M:%S %Y")
+    );
+    let mut buf = text.into_bytes();
+    buf.resize(116, b' ');
+    buf.extend_from_slice(&[0; 8]);
+    buf.extend_from_slice(&0x0100u16.to_le_bytes());
+    buf.extend_from_slice(b"IM");
+    buf
+}
+
+/// 压缩后写入一个变量
+fn write_variable<W: Write>(out: &mut W, matrix: &[u8]) -> io::Result<()> {
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    encoder.write_all(matrix)?;
+    let compressed = encoder.finish()?;
+
+    out.write_all(&MI_COMPRESSED.to_le_bytes())?;
+    out.write_all(&(compressed.len() as u32).to_le_bytes())?;
+    out.write_all(&compressed)
+}
+
+/// 数据元素: 类型 (u32) + 字节数 (u32) + 数据, 补齐到 8 字节
+fn element(buf: &mut Vec<u8>, data_type: u32, data: &[u8]) {
+    buf.extend_from_slice(&data_type.to_le_bytes());
+    buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buf.extend_from_slice(data);
+    buf.resize(buf.len().next_multiple_of(8), 0);
+}
+
+/// miMATRIX 元素: 标志, 维度, 名称, 之后为各类型的数据
+fn matrix(name: &str, class: u32, dims: &[i32], body: &[u8]) -> Vec<u8> {
+    let mut content = vec![];
+
+    let mut flags = class.to_le_bytes().to_vec();
+    flags.extend_from_slice(&0u32.to_le_bytes());
+    element(&mut content, MI_UINT32, &flags);
+
+    let dims: Vec<u8> = dims.iter().flat_map(|d| d.to_le_bytes()).collect();
+    element(&mut content, MI_INT32, &dims);
+    element(&mut content, MI_INT8, name.as_bytes());
+    content.extend_from_slice(body);
+
+    let mut buf = vec![];
+    element(&mut buf, MI_MATRIX, &content);
+    buf
+}
+
+/// double 矩阵, 由行优先转为 MATLAB 的列优先
+fn double_matrix(name: &str, array: &NamedArray) -> Vec<u8> {
+    let mut data = Vec::with_capacity(array.data.len() * 8);
+    for col in 0..array.cols {
+        for row in 0..array.rows {
+            data.extend_from_slice(&array.data[row * array.cols + col].to_le_bytes());
+        }
+    }
+
+    let mut body = vec![];
+    element(&mut body, MI_DOUBLE, &data);
+    matrix(
+        name,
+        MX_DOUBLE_CLASS,
+        &[array.rows as i32, array.cols as i32],
+        &body,
+    )
+}
+
+/// 字符数组 (1 x N, UTF-16)
+fn char_matrix(name: &str, value: &str) -> Vec<u8> {
+    let chars: Vec<u16> = value.encode_utf16().collect();
+    let data: Vec<u8> = chars.iter().flat_map(|c| c.to_le_bytes()).collect();
+
+    let mut body = vec![];
+    element(&mut body, MI_UINT16, &data);
+    let dims = if chars.is_empty() {
+        [0, 0]
+    } else {
+        [1, chars.len() as i32]
+    };
+    matrix(name, MX_CHAR_CLASS, &dims, &body)
+}
+
+/// 1 x 1 结构体, 字段值为不带名称的 miMATRIX 元素
+fn struct_matrix(name: &str, fields: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut body = vec![];
+    element(
+        &mut body,
+        MI_INT32,
+        &(FIELD_NAME_LENGTH as i32).to_le_bytes(),
+    );
+
+    let mut names = vec![];
+    for (field, _) in fields {
+        let mut field = field.as_bytes().to_vec();
+        field.truncate(FIELD_NAME_LENGTH - 1);
+        field.resize(FIELD_NAME_LENGTH, 0);
+        names.extend_from_slice(&field);
+    }
+    element(&mut body, MI_INT8, &names);
+
+    for (_, value) in fields {
+        body.extend_from_slice(value);
+    }
+    matrix(name, MX_STRUCT_CLASS, &[1, 1], &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(buf: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_double_matrix_layout() {
+        let array = NamedArray {
+            name: "actual_tcp_pose".to_string(),
+            rows: 2,
+            cols: 3,
+            data: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+            unit: "mm".to_string(),
+        };
+        let buf = double_matrix(&array.name, &array);
+
+        assert_eq!(u32_at(&buf, 0), MI_MATRIX);
+        assert_eq!(u32_at(&buf, 4) as usize, buf.len() - 8);
+        assert_eq!(buf.len() % 8, 0);
+        // 标志中的数组类型
+        assert_eq!(u32_at(&buf, 16), MX_DOUBLE_CLASS);
+        // 维度 2 x 3
+        assert_eq!(u32_at(&buf, 32), 2);
+        assert_eq!(u32_at(&buf, 36), 3);
+        // 名称 (15 字节, 补齐到 16)
+        assert_eq!(u32_at(&buf, 44), 15);
+        assert_eq!(&buf[48..63], b"actual_tcp_pose");
+
+        // 数据列优先: 1, 4, 2, 5, 3, 6
+        assert_eq!(u32_at(&buf, 64), MI_DOUBLE);
+        assert_eq!(u32_at(&buf, 68), 48);
+        let second = f64::from_le_bytes(buf[80..88].try_into().unwrap());
+        assert_eq!(second, 4.0);
+    }
+}
@@ -0,0 +1,724 @@
+// mcap_exporter.rs - 以 MCAP 格式导出原始数据 (JSON 消息 + JSON Schema), 供 Foxglove 等工具查看
+//
+// 格式说明: https://mcap.dev/spec
+// 文件结构: Magic, Header, Schema/Channel/Metadata, Chunk + MessageIndex..., DataEnd,
+//           Summary (Schema/Channel/Statistics/MetadataIndex/ChunkIndex), SummaryOffset, Footer, Magic
+use crate::commands::{arm_service::robot_data::RobotDataPacket, sessions::export::ExportMeta};
+use byteorder::{LittleEndian, WriteBytesExt};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8] = b"\x89MCAP0\r\n";
+// 单个 chunk 的消息数据大小上限
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+// 记录类型
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_CHUNK: u8 = 0x06;
+const OP_MESSAGE_INDEX: u8 = 0x07;
+const OP_CHUNK_INDEX: u8 = 0x08;
+const OP_STATISTICS: u8 = 0x0B;
+const OP_METADATA: u8 = 0x0C;
+const OP_METADATA_INDEX: u8 = 0x0D;
+const OP_SUMMARY_OFFSET: u8 = 0x0E;
+const OP_DATA_END: u8 = 0x0F;
+
+/// 导出的主题
+pub const JOINT_STATES_TOPIC: &str = "/xarm/joint_states";
+pub const TCP_POSE_TOPIC: &str = "/xarm/tcp_pose";
+pub const WRENCH_TOPIC: &str = "/xarm/wrench";
+pub const STATUS_TOPIC: &str = "/xarm/status";
+
+// 通道 id (同时作为 schema id)
+const JOINT_STATES_CHANNEL: u16 = 1;
+const TCP_POSE_CHANNEL: u16 = 2;
+const WRENCH_CHANNEL: u16 = 3;
+const STATUS_CHANNEL: u16 = 4;
+
+// TCP 位姿与力传感器的坐标系名称
+const BASE_FRAME: &str = "base_link";
+const TCP_FRAME: &str = "tcp";
+
+// ---------- 消息 ----------
+
+#[derive(Serialize)]
+struct Time {
+    sec: u64,
+    nsec: u32,
+}
+
+#[derive(Serialize)]
+struct Vector3 {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+#[derive(Serialize)]
+struct Quaternion {
+    x: f64,
+    y: f64,
+    z: f64,
+    w: f64,
+}
+
+#[derive(Serialize)]
+struct Pose {
+    position: Vector3,
+    orientation: Quaternion,
+}
+
+/// foxglove.PoseInFrame
+#[derive(Serialize)]
+struct PoseInFrame<'a> {
+    timestamp: Time,
+    frame_id: &'a str,
+    pose: Pose,
+}
+
+#[derive(Serialize)]
+struct JointState<'a> {
+    timestamp: Time,
+    name: &'a [String],
+    position: &'a [f32],     // rad
+    velocity: &'a [f32],     // rad/s
+    acceleration: &'a [f32], // rad/s^2
+    effort: &'a [f32],       // 估计关节力矩 N*m
+    current: &'a [f32],      // A
+    target_position: &'a [f32],
+}
+
+#[derive(Serialize)]
+struct Wrench<'a> {
+    timestamp: Time,
+    frame_id: &'a str,
+    force: Vector3,      // 滤波后 N
+    torque: Vector3,     // 滤波后 N*m
+    raw_force: Vector3,  // 原始数据 N
+    raw_torque: Vector3, // 原始数据 N*m
+}
+
+#[derive(Serialize)]
+struct Status {
+    timestamp: Time,
+    motion_state: u8,
+    control_mode: u8,
+    instruction_cache_count: u16,
+}
+
+fn time_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "sec": { "type": "integer", "minimum": 0 },
+            "nsec": { "type": "integer", "minimum": 0, "maximum": 999_999_999 }
+        }
+    })
+}
+
+fn vector3_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "x": { "type": "number" },
+            "y": { "type": "number" },
+            "z": { "type": "number" }
+        }
+    })
+}
+
+fn number_array_schema(description: &str) -> serde_json::Value {
+    json!({
+        "type": "array",
+        "items": { "type": "number" },
+        "description": description
+    })
+}
+
+fn joint_state_schema() -> serde_json::Value {
+    json!({
+        "title": "xarm.JointState",
+        "type": "object",
+        "properties": {
+            "timestamp": time_schema(),
+            "name": { "type": "array", "items": { "type": "string" } },
+            "position": number_array_schema("Actual joint positions (rad)"),
+            "velocity": number_array_schema("Actual joint velocities (rad/s)"),
+            "acceleration": number_array_schema("Actual joint accelerations (rad/s^2)"),
+            "effort": number_array_schema("Estimated joint torques (N*m)"),
+            "current": number_array_schema("Actual joint currents (A)"),
+            "target_position": number_array_schema("Target joint positions (rad)")
+        }
+    })
+}
+
+fn pose_in_frame_schema() -> serde_json::Value {
+    json!({
+        "title": "foxglove.PoseInFrame",
+        "type": "object",
+        "properties": {
+            "timestamp": time_schema(),
+            "frame_id": { "type": "string" },
+            "pose": {
+                "type": "object",
+                "properties": {
+                    "position": vector3_schema(),
+                    "orientation": {
+                        "type": "object",
+                        "properties": {
+                            "x": { "type": "number" },
+                            "y": { "type": "number" },
+                            "z": { "type": "number" },
+                            "w": { "type": "number" }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn wrench_schema() -> serde_json::Value {
+    json!({
+        "title": "xarm.Wrench",
+        "type": "object",
+        "properties": {
+            "timestamp": time_schema(),
+            "frame_id": { "type": "string" },
+            "force": vector3_schema(),
+            "torque": vector3_schema(),
+            "raw_force": vector3_schema(),
+            "raw_torque": vector3_schema()
+        }
+    })
+}
+
+fn status_schema() -> serde_json::Value {
+    json!({
+        "title": "xarm.Status",
+        "type": "object",
+        "properties": {
+            "timestamp": time_schema(),
+            "motion_state": { "type": "integer" },
+            "control_mode": { "type": "integer" },
+            "instruction_cache_count": { "type": "integer" }
+        }
+    })
+}
+
+/// 控制器时间戳 (μs) 转换为 MCAP 时间 (ns)
+fn log_time(timestamp: i64) -> u64 {
+    timestamp.max(0) as u64 * 1000
+}
+
+fn time(timestamp: i64) -> Time {
+    let us = timestamp.max(0) as u64;
+    Time {
+        sec: us / 1_000_000,
+        nsec: (us % 1_000_000) as u32 * 1000,
+    }
+}
+
+fn vector3(values: &[f32], scale: f64) -> Vector3 {
+    let v = |i: usize| values.get(i).copied().unwrap_or_default() as f64 * scale;
+    Vector3 {
+        x: v(0),
+        y: v(1),
+        z: v(2),
+    }
+}
+
+/// 由 roll/pitch/yaw (rad, R = Rz(yaw)·Ry(pitch)·Rx(roll)) 计算四元数
+fn quaternion(roll: f64, pitch: f64, yaw: f64) -> Quaternion {
+    let (sr, cr) = (roll / 2.0).sin_cos();
+    let (sp, cp) = (pitch / 2.0).sin_cos();
+    let (sy, cy) = (yaw / 2.0).sin_cos();
+    Quaternion {
+        x: sr * cp * cy - cr * sp * sy,
+        y: cr * sp * cy + sr * cp * sy,
+        z: cr * cp * sy - sr * sp * cy,
+        w: cr * cp * cy + sr * sp * sy,
+    }
+}
+
+/// TCP 位姿 [x, y, z (mm), roll, pitch, yaw (rad)] 转换为 Pose (m + 四元数)
+fn tcp_pose(values: &[f32; 6]) -> Pose {
+    Pose {
+        position: vector3(&values[..3], 0.001),
+        orientation: quaternion(values[3] as f64, values[4] as f64, values[5] as f64),
+    }
+}
+
+/// 将原始数据包写入 MCAP 文件, 以控制器时间戳作为 log time
+pub fn write_mcap(packets: &[RobotDataPacket], meta: &ExportMeta, path: &Path) -> io::Result<()> {
+    let mut writer = McapWriter::new(BufWriter::new(File::create(path)?))?;
+
+    let session: BTreeMap<String, String> = match serde_json::to_value(meta) {
+        Ok(serde_json::Value::Object(map)) => map
+            .into_iter()
+            .map(|(k, v)| match v {
+                serde_json::Value::String(s) => (k, s),
+                v => (k, v.to_string()),
+            })
+            .collect(),
+        _ => BTreeMap::new(),
+    };
+    writer.add_metadata("session", &session)?;
+
+    let topics = [
+        (
+            JOINT_STATES_CHANNEL,
+            JOINT_STATES_TOPIC,
+            joint_state_schema(),
+        ),
+        (TCP_POSE_CHANNEL, TCP_POSE_TOPIC, pose_in_frame_schema()),
+        (WRENCH_CHANNEL, WRENCH_TOPIC, wrench_schema()),
+        (STATUS_CHANNEL, STATUS_TOPIC, status_schema()),
+    ];
+    for (id, topic, schema) in &topics {
+        let name = schema["title"].as_str().unwrap_or_default();
+        writer.add_schema(*id, name, &serde_json::to_vec(schema).map_err(to_io)?)?;
+        writer.add_channel(*id, *id, topic)?;
+    }
+
+    let axis = meta.joint_count();
+    let names: Vec<String> = (1..=axis).map(|i| format!("joint{}", i)).collect();
+
+    for (seq, packet) in packets.iter().enumerate() {
+        let seq = seq as u32;
+        let t = log_time(packet.timestamp);
+
+        let message = JointState {
+            timestamp: time(packet.timestamp),
+            name: &names,
+            position: &packet.actual_joint_positions[..axis],
+            velocity: &packet.actual_joint_velocities[..axis],
+            acceleration: &packet.actual_joint_accelerations[..axis],
+            effort: &packet.estimated_joint_torque[..axis],
+            current: &packet.actual_joint_currents[..axis],
+            target_position: &packet.target_joint_positions[..axis],
+        };
+        writer.write_message(
+            JOINT_STATES_CHANNEL,
+            seq,
+            t,
+            &serde_json::to_vec(&message).map_err(to_io)?,
+        )?;
+
+        let message = PoseInFrame {
+            timestamp: time(packet.timestamp),
+            frame_id: BASE_FRAME,
+            pose: tcp_pose(&packet.actual_tcp_pose),
+        };
+        writer.write_message(
+            TCP_POSE_CHANNEL,
+            seq,
+            t,
+            &serde_json::to_vec(&message).map_err(to_io)?,
+        )?;
+
+        let filtered = &packet.filtered_data_torque_sensor;
+        let raw = &packet.data_torque_sensor;
+        let message = Wrench {
+            timestamp: time(packet.timestamp),
+            frame_id: TCP_FRAME,
+            force: vector3(&filtered[..3], 1.0),
+            torque: vector3(&filtered[3..], 1.0),
+            raw_force: vector3(&raw[..3], 1.0),
+            raw_torque: vector3(&raw[3..], 1.0),
+        };
+        writer.write_message(
+            WRENCH_CHANNEL,
+            seq,
+            t,
+            &serde_json::to_vec(&message).map_err(to_io)?,
+        )?;
+
+        let message = Status {
+            timestamp: time(packet.timestamp),
+            motion_state: packet.motion_state(),
+            control_mode: packet.control_mode(),
+            instruction_cache_count: packet.instruction_cache_count,
+        };
+        writer.write_message(
+            STATUS_CHANNEL,
+            seq,
+            t,
+            &serde_json::to_vec(&message).map_err(to_io)?,
+        )?;
+    }
+
+    writer.finish()?.flush()
+}
+
+fn to_io(e: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+// ---------- 编码 ----------
+
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn put_bytes(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buf.extend_from_slice(data);
+}
+
+fn put_string_map(buf: &mut Vec<u8>, map: &BTreeMap<String, String>) {
+    let mut content = vec![];
+    for (k, v) in map {
+        put_str(&mut content, k);
+        put_str(&mut content, v);
+    }
+    put_bytes(buf, &content);
+}
+
+fn put_u16_u64_map(buf: &mut Vec<u8>, map: &BTreeMap<u16, u64>) {
+    let mut content = vec![];
+    for (k, v) in map {
+        content.extend_from_slice(&k.to_le_bytes());
+        content.extend_from_slice(&v.to_le_bytes());
+    }
+    put_bytes(buf, &content);
+}
+
+/// 记录: opcode (u8) + 长度 (u64) + 内容
+fn record(opcode: u8, content: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(content.len() + 9);
+    buf.push(opcode);
+    buf.extend_from_slice(&(content.len() as u64).to_le_bytes());
+    buf.extend_from_slice(content);
+    buf
+}
+
+/// 正在写入的 chunk
+#[derive(Default)]
+struct Chunk {
+    records: Vec<u8>,
+    start_time: u64,
+    end_time: u64,
+    // channel id -> [(log time, chunk 内偏移)]
+    message_offsets: BTreeMap<u16, Vec<(u64, u64)>>,
+}
+
+/// 只写 MCAP 写入器 (chunk 不压缩, CRC 为 0 表示不校验)
+struct McapWriter<W: Write> {
+    out: W,
+    offset: u64,
+    chunk: Chunk,
+    // Summary 中重复的记录
+    schemas: Vec<u8>,
+    channels: Vec<u8>,
+    metadata_indexes: Vec<u8>,
+    chunk_indexes: Vec<u8>,
+    // 统计
+    schema_count: u16,
+    channel_count: u32,
+    metadata_count: u32,
+    chunk_count: u32,
+    message_count: u64,
+    message_start_time: Option<u64>,
+    message_end_time: u64,
+    channel_message_counts: BTreeMap<u16, u64>,
+}
+
+impl<W: Write> McapWriter<W> {
+    fn new(out: W) -> io::Result<Self> {
+        let mut writer = Self {
+            out,
+            offset: 0,
+            chunk: Chunk::default(),
+            schemas: vec![],
+            channels: vec![],
+            metadata_indexes: vec![],
+            chunk_indexes: vec![],
+            schema_count: 0,
+            channel_count: 0,
+            metadata_count: 0,
+            chunk_count: 0,
+            message_count: 0,
+            message_start_time: None,
+            message_end_time: 0,
+            channel_message_counts: BTreeMap::new(),
+        };
+
+        writer.write_raw(MAGIC)?;
+        let mut content = vec![];
+        put_str(&mut content, ""); // profile
+        put_str(&mut content, "UFACTORY_Assistant"); // library
+        writer.write_raw(&record(OP_HEADER, &content))?;
+
+        Ok(writer)
+    }
+
+    fn write_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        self.out.write_all(data)?;
+        self.offset += data.len() as u64;
+        Ok(())
+    }
+
+    fn add_schema(&mut self, id: u16, name: &str, data: &[u8]) -> io::Result<()> {
+        let mut content = vec![];
+        content.extend_from_slice(&id.to_le_bytes());
+        put_str(&mut content, name);
+        put_str(&mut content, "jsonschema");
+        put_bytes(&mut content, data);
+
+        let record = record(OP_SCHEMA, &content);
+        self.schemas.extend_from_slice(&record);
+        self.schema_count += 1;
+        self.write_raw(&record)
+    }
+
+    fn add_channel(&mut self, id: u16, schema_id: u16, topic: &str) -> io::Result<()> {
+        let mut content = vec![];
+        content.extend_from_slice(&id.to_le_bytes());
+        content.extend_from_slice(&schema_id.to_le_bytes());
+        put_str(&mut content, topic);
+        put_str(&mut content, "json");
+        put_string_map(&mut content, &BTreeMap::new());
+
+        let record = record(OP_CHANNEL, &content);
+        self.channels.extend_from_slice(&record);
+        self.channel_count += 1;
+        self.write_raw(&record)
+    }
+
+    fn add_metadata(&mut self, name: &str, metadata: &BTreeMap<String, String>) -> io::Result<()> {
+        let mut content = vec![];
+        put_str(&mut content, name);
+        put_string_map(&mut content, metadata);
+        let metadata_record = record(OP_METADATA, &content);
+
+        let mut index = vec![];
+        index.write_u64::<LittleEndian>(self.offset)?;
+        index.write_u64::<LittleEndian>(metadata_record.len() as u64)?;
+        put_str(&mut index, name);
+        self.metadata_indexes
+            .extend_from_slice(&record(OP_METADATA_INDEX, &index));
+        self.metadata_count += 1;
+
+        self.write_raw(&metadata_record)
+    }
+
+    fn write_message(
+        &mut self,
+        channel_id: u16,
+        sequence: u32,
+        log_time: u64,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let mut content = Vec::with_capacity(data.len() + 22);
+        content.extend_from_slice(&channel_id.to_le_bytes());
+        content.extend_from_slice(&sequence.to_le_bytes());
+        content.extend_from_slice(&log_time.to_le_bytes()); // log time
+        content.extend_from_slice(&log_time.to_le_bytes()); // publish time
+        content.extend_from_slice(data);
+
+        let chunk = &mut self.chunk;
+        if chunk.records.is_empty() {
+            chunk.start_time = log_time;
+            chunk.end_time = log_time;
+        }
+        chunk.start_time = chunk.start_time.min(log_time);
+        chunk.end_time = chunk.end_time.max(log_time);
+        chunk
+            .message_offsets
+            .entry(channel_id)
+            .or_default()
+            .push((log_time, chunk.records.len() as u64));
+        chunk
+            .records
+            .extend_from_slice(&record(OP_MESSAGE, &content));
+
+        self.message_count += 1;
+        *self.channel_message_counts.entry(channel_id).or_default() += 1;
+        let start = self.message_start_time.get_or_insert(log_time);
+        *start = (*start).min(log_time);
+        self.message_end_time = self.message_end_time.max(log_time);
+
+        if self.chunk.records.len() >= CHUNK_SIZE {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// 写入当前 chunk 及其 MessageIndex, 并记录 ChunkIndex
+    fn flush_chunk(&mut self) -> io::Result<()> {
+        if self.chunk.records.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::take(&mut self.chunk);
+        let records_len = chunk.records.len() as u64;
+
+        let mut content = Vec::with_capacity(chunk.records.len() + 40);
+        content.write_u64::<LittleEndian>(chunk.start_time)?;
+        content.write_u64::<LittleEndian>(chunk.end_time)?;
+        content.write_u64::<LittleEndian>(records_len)?; // uncompressed size
+        content.write_u32::<LittleEndian>(0)?; // uncompressed crc
+        put_str(&mut content, ""); // compression
+        content.write_u64::<LittleEndian>(records_len)?;
+        content.extend_from_slice(&chunk.records);
+
+        let chunk_start = self.offset;
+        let chunk_record = record(OP_CHUNK, &content);
+        self.write_raw(&chunk_record)?;
+
+        let message_index_start = self.offset;
+        let mut message_index_offsets = BTreeMap::new();
+        for (channel_id, entries) in &chunk.message_offsets {
+            let mut content = vec![];
+            content.write_u16::<LittleEndian>(*channel_id)?;
+            content.write_u32::<LittleEndian>((entries.len() * 16) as u32)?;
+            for (time, offset) in entries {
+                content.write_u64::<LittleEndian>(*time)?;
+                content.write_u64::<LittleEndian>(*offset)?;
+            }
+            message_index_offsets.insert(*channel_id, self.offset);
+            self.write_raw(&record(OP_MESSAGE_INDEX, &content))?;
+        }
+
+        let mut index = vec![];
+        index.write_u64::<LittleEndian>(chunk.start_time)?;
+        index.write_u64::<LittleEndian>(chunk.end_time)?;
+        index.write_u64::<LittleEndian>(chunk_start)?;
+        index.write_u64::<LittleEndian>(chunk_record.len() as u64)?;
+        put_u16_u64_map(&mut index, &message_index_offsets);
+        index.write_u64::<LittleEndian>(self.offset - message_index_start)?;
+        put_str(&mut index, ""); // compression
+        index.write_u64::<LittleEndian>(records_len)?; // compressed size
+        index.write_u64::<LittleEndian>(records_len)?; // uncompressed size
+        self.chunk_indexes
+            .extend_from_slice(&record(OP_CHUNK_INDEX, &index));
+        self.chunk_count += 1;
+
+        Ok(())
+    }
+
+    /// 写入 DataEnd, Summary 与 Footer
+    fn finish(mut self) -> io::Result<W> {
+        self.flush_chunk()?;
+        self.write_raw(&record(OP_DATA_END, &0u32.to_le_bytes()))?;
+
+        let mut statistics = vec![];
+        statistics.write_u64::<LittleEndian>(self.message_count)?;
+        statistics.write_u16::<LittleEndian>(self.schema_count)?;
+        statistics.write_u32::<LittleEndian>(self.channel_count)?;
+        statistics.write_u32::<LittleEndian>(0)?; // attachment count
+        statistics.write_u32::<LittleEndian>(self.metadata_count)?;
+        statistics.write_u32::<LittleEndian>(self.chunk_count)?;
+        statistics.write_u64::<LittleEndian>(self.message_start_time.unwrap_or_default())?;
+        statistics.write_u64::<LittleEndian>(self.message_end_time)?;
+        put_u16_u64_map(&mut statistics, &self.channel_message_counts);
+        let statistics = record(OP_STATISTICS, &statistics);
+
+        let summary_start = self.offset;
+        let groups = [
+            (OP_SCHEMA, std::mem::take(&mut self.schemas)),
+            (OP_CHANNEL, std::mem::take(&mut self.channels)),
+            (OP_STATISTICS, statistics),
+            (
+                OP_METADATA_INDEX,
+                std::mem::take(&mut self.metadata_indexes),
+            ),
+            (OP_CHUNK_INDEX, std::mem::take(&mut self.chunk_indexes)),
+        ];
+        let mut summary_offsets = vec![];
+        for (opcode, data) in groups {
+            if data.is_empty() {
+                continue;
+            }
+            let mut content = vec![opcode];
+            content.write_u64::<LittleEndian>(self.offset)?;
+            content.write_u64::<LittleEndian>(data.len() as u64)?;
+            summary_offsets.extend_from_slice(&record(OP_SUMMARY_OFFSET, &content));
+            self.write_raw(&data)?;
+        }
+
+        let summary_offset_start = self.offset;
+        self.write_raw(&summary_offsets)?;
+
+        let mut footer = vec![];
+        footer.write_u64::<LittleEndian>(summary_start)?;
+        footer.write_u64::<LittleEndian>(summary_offset_start)?;
+        footer.write_u32::<LittleEndian>(0)?; // summary crc
+        self.write_raw(&record(OP_FOOTER, &footer))?;
+        self.write_raw(MAGIC)?;
+
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::ReadBytesExt;
+    use std::io::{Cursor, Read};
+
+    fn read_record(cursor: &mut Cursor<&[u8]>) -> (u8, Vec<u8>) {
+        let opcode = cursor.read_u8().unwrap();
+        let len = cursor.read_u64::<LittleEndian>().unwrap() as usize;
+        let mut content = vec![0; len];
+        cursor.read_exact(&mut content).unwrap();
+        (opcode, content)
+    }
+
+    #[test]
+    fn test_write_indexed_mcap() {
+        let mut writer = McapWriter::new(vec![]).unwrap();
+        writer.add_schema(1, "xarm.Status", b"{}").unwrap();
+        writer.add_channel(1, 1, STATUS_TOPIC).unwrap();
+        for i in 0..3u64 {
+            writer
+                .write_message(1, i as u32, 1_000 + i * 4_000_000, br#"{"motion_state":1}"#)
+                .unwrap();
+        }
+        let data = writer.finish().unwrap();
+
+        assert!(data.starts_with(MAGIC));
+        assert!(data.ends_with(MAGIC));
+
+        // Footer 位于结尾 Magic 之前, 指向 Summary
+        let footer_start = data.len() - MAGIC.len() - (1 + 8 + 20);
+        let mut cursor = Cursor::new(&data[footer_start..]);
+        let (opcode, footer) = read_record(&mut cursor);
+        assert_eq!(opcode, OP_FOOTER);
+        let summary_start = u64::from_le_bytes(footer[..8].try_into().unwrap()) as usize;
+
+        // Summary: Schema, Channel, Statistics, ChunkIndex
+        let mut cursor = Cursor::new(&data[summary_start..]);
+        let opcodes: Vec<u8> = (0..4).map(|_| read_record(&mut cursor).0).collect();
+        assert_eq!(
+            opcodes,
+            vec![OP_SCHEMA, OP_CHANNEL, OP_STATISTICS, OP_CHUNK_INDEX]
+        );
+
+        // 数据区: Header, Schema, Channel, Chunk, MessageIndex, DataEnd
+        let mut cursor = Cursor::new(&data[MAGIC.len()..]);
+        let opcodes: Vec<u8> = (0..6).map(|_| read_record(&mut cursor).0).collect();
+        assert_eq!(
+            opcodes,
+            vec![
+                OP_HEADER,
+                OP_SCHEMA,
+                OP_CHANNEL,
+                OP_CHUNK,
+                OP_MESSAGE_INDEX,
+                OP_DATA_END
+            ]
+        );
+    }
+}
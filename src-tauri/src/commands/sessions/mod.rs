@@ -0,0 +1,248 @@
+pub mod export;
+pub mod library;
+pub mod mat_exporter;
+pub mod mcap_exporter;
+pub mod numpy_exporter;
+pub mod parquet_exporter;
+pub mod recovery;
+
+use std::{io, path::PathBuf};
+
+use tauri::Emitter;
+
+use crate::{
+    commands::arm_service::structs::ObserveType,
+    commands::sessions::{
+        export::ExportFormat,
+        library::{SessionInfo, SessionLibrary, SessionMeta, SessionQuery},
+        recovery::{self, OrphanedRecording},
+    },
+    state::app_state::{AppState, RobotEvent, RobotSession},
+    utils::{error::AppError, response::Response},
+};
+
+/// 机械臂当前的录制信息 (机器人 IP, 轴数, 观测参数)
+pub fn current_session_meta(robot: &RobotSession) -> Result<SessionMeta, AppError> {
+    let (robot_ip, observe_params) = {
+        let robot_lock = robot
+            .robot_server
+            .read()
+            .map_err(|_| AppError::LockPoisoned("robot_server"))?;
+
+        let observe_params = robot_lock
+            .observe_params
+            .read()
+            .map_err(|_| AppError::LockPoisoned("observe_params"))?
+            .clone();
+
+        (robot_lock.ip.clone(), observe_params)
+    };
+
+    let axis = robot
+        .shared_state
+        .read()
+        .map_err(|_| AppError::LockPoisoned("shared_state"))?
+        .axis;
+
+    Ok(SessionMeta {
+        robot_ip,
+        axis,
+        observe_params,
+    })
+}
+
+/// 将机械臂当前的录制归档到会话库, 成功时推送 SESSION_SAVED 事件
+///
+/// 录制结束 (停止/超时/断开) 时调用, 同一次录制只会归档一次
+pub fn archive_current_recording(
+    state: &AppState,
+    robot: &RobotSession,
+) -> Result<Option<SessionInfo>, AppError> {
+    let meta = current_session_meta(robot)?;
+    let csv_exporter_arc = robot
+        .robot_server
+        .read()
+        .map_err(|_| AppError::LockPoisoned("robot_server"))?
+        .csv_exporter
+        .clone();
+
+    let mut csv_exporter_guard = csv_exporter_arc
+        .write()
+        .map_err(|_| AppError::LockPoisoned("csv_exporter"))?;
+
+    let Some(csv_exporter) = csv_exporter_guard.as_mut() else {
+        return Ok(None);
+    };
+
+    let info = state
+        .session_library
+        .lock()
+        .map_err(|_| AppError::LockPoisoned("session_library"))?
+        .archive(csv_exporter, meta)
+        .map_err(|e| AppError::SessionLibrary(e.to_string()))?;
+
+    if let Some(info) = &info {
+        let event = RobotEvent {
+            robot_id: &robot.id,
+            payload: info,
+        };
+        let _ = state.app.emit("SESSION_SAVED", &event);
+    }
+
+    Ok(info)
+}
+
+/// 会话库操作的错误; 会话不在索引中时为 SessionNotFound, 其余 (如文件读写失败) 为 SessionLibrary
+fn library_error(library: &SessionLibrary, id: &str, e: io::Error) -> AppError {
+    match library.get(id) {
+        None => AppError::SessionNotFound(id.to_string()),
+        Some(_) => AppError::SessionLibrary(e.to_string()),
+    }
+}
+
+/// 查询会话 (不传条件时返回全部)
+#[tauri::command]
+pub fn list_sessions(
+    state: tauri::State<AppState>,
+    query: Option<SessionQuery>,
+) -> Response<Vec<SessionInfo>> {
+    match state.session_library.lock() {
+        Ok(library) => Response::success(library.search(&query.unwrap_or_default())),
+        Err(_) => AppError::LockPoisoned("session_library").into(),
+    }
+}
+
+#[tauri::command]
+pub fn get_session(state: tauri::State<AppState>, id: &str) -> Response<SessionInfo> {
+    match state.session_library.lock() {
+        Ok(library) => match library.get(id) {
+            Some(session) => Response::success(session.clone()),
+            None => AppError::SessionNotFound(id.to_string()).into(),
+        },
+        Err(_) => AppError::LockPoisoned("session_library").into(),
+    }
+}
+
+#[tauri::command]
+pub fn rename_session(
+    state: tauri::State<AppState>,
+    id: &str,
+    name: &str,
+) -> Response<SessionInfo> {
+    match state.session_library.lock() {
+        Ok(mut library) => library
+            .rename(id, name)
+            .map_err(|e| library_error(&library, id, e))
+            .into(),
+        Err(_) => AppError::LockPoisoned("session_library").into(),
+    }
+}
+
+/// 更新会话备注与标签
+#[tauri::command]
+pub fn update_session(
+    state: tauri::State<AppState>,
+    id: &str,
+    notes: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Response<SessionInfo> {
+    match state.session_library.lock() {
+        Ok(mut library) => library
+            .update(id, notes, tags)
+            .map_err(|e| library_error(&library, id, e))
+            .into(),
+        Err(_) => AppError::LockPoisoned("session_library").into(),
+    }
+}
+
+#[tauri::command]
+pub fn delete_session(state: tauri::State<AppState>, id: &str) -> Response<String> {
+    match state.session_library.lock() {
+        Ok(mut library) => library
+            .delete(id)
+            .map(|_| "Session deleted".to_string())
+            .map_err(|e| library_error(&library, id, e))
+            .into(),
+        Err(_) => AppError::LockPoisoned("session_library").into(),
+    }
+}
+
+/// 导出会话, 默认CSV (原始数据同名保存); `channels` 为空时导出全部通道
+#[tauri::command]
+pub fn export_session(
+    state: tauri::State<AppState>,
+    id: &str,
+    path: &str,
+    format: Option<ExportFormat>,
+    channels: Option<Vec<ObserveType>>,
+) -> Response<String> {
+    match state.session_library.lock() {
+        Ok(library) => library
+            .export(
+                id,
+                &PathBuf::from(path),
+                format.unwrap_or_default(),
+                &channels.unwrap_or_default(),
+            )
+            .map(|_| "Export session successfully".to_string())
+            .map_err(|e| library_error(&library, id, e))
+            .into(),
+        Err(_) => AppError::LockPoisoned("session_library").into(),
+    }
+}
+
+/// 启动时发现的遗留临时录制 (异常退出未归档)
+#[tauri::command]
+pub fn list_orphaned_recordings(state: tauri::State<AppState>) -> Response<Vec<OrphanedRecording>> {
+    match state.orphaned_recordings.lock() {
+        Ok(orphans) => Response::success(orphans.clone()),
+        Err(_) => AppError::LockPoisoned("orphaned_recordings").into(),
+    }
+}
+
+/// 将遗留录制恢复到会话库
+#[tauri::command]
+pub fn recover_orphaned_recording(
+    state: tauri::State<AppState>,
+    name: &str,
+) -> Response<SessionInfo> {
+    let mut orphans = match state.orphaned_recordings.lock() {
+        Ok(orphans) => orphans,
+        Err(_) => return AppError::LockPoisoned("orphaned_recordings").into(),
+    };
+
+    let Some(pos) = orphans.iter().position(|o| o.name == name) else {
+        return AppError::OrphanNotFound(name.to_string()).into();
+    };
+
+    let mut library = match state.session_library.lock() {
+        Ok(library) => library,
+        Err(_) => return AppError::LockPoisoned("session_library").into(),
+    };
+
+    match recovery::recover(&mut library, &orphans[pos]) {
+        Ok(info) => {
+            orphans.remove(pos);
+            Response::success(info)
+        }
+        Err(e) => AppError::RecoverFailed(e.to_string()).into(),
+    }
+}
+
+/// 丢弃遗留录制
+#[tauri::command]
+pub fn discard_orphaned_recording(state: tauri::State<AppState>, name: &str) -> Response<String> {
+    let mut orphans = match state.orphaned_recordings.lock() {
+        Ok(orphans) => orphans,
+        Err(_) => return AppError::LockPoisoned("orphaned_recordings").into(),
+    };
+
+    let Some(pos) = orphans.iter().position(|o| o.name == name) else {
+        return AppError::OrphanNotFound(name.to_string()).into();
+    };
+
+    let orphan = orphans.remove(pos);
+    recovery::discard_files(&orphan.csv_path);
+
+    Response::success("Orphaned recording discarded".to_string())
+}
@@ -0,0 +1,154 @@
+// numpy_exporter.rs - 以 NumPy .npz 格式导出录制 (每个数组一个 .npy, zip 压缩)
+//
+// 读取: data = np.load(path); data["actual_joint_positions"].shape == (N, axis)
+//       json.loads(data["metadata"].item()) 为会话信息, 各数组单位与界面语言的名称
+use crate::{
+    commands::sessions::export::{ExportMeta, NamedArray},
+    utils::i18n,
+};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Write},
+    path::Path,
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+// 头部 (magic + 版本 + 长度 + 头字典) 按 64 字节对齐
+const NPY_ALIGN: usize = 64;
+
+/// 写入 .npz 文件
+///
+/// 除各通道数组外, 还包含 axis (int32 标量) 和 metadata (JSON 字符串标量)
+pub fn write_npz(arrays: &[NamedArray], meta: &ExportMeta, path: &Path) -> io::Result<()> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+
+    for array in arrays {
+        zip.start_file(format!("{}.npy", array.name), options)?;
+        zip.write_all(&npy_f64(array))?;
+    }
+
+    zip.start_file("axis.npy", options)?;
+    zip.write_all(&npy_i32_scalar(meta.axis))?;
+
+    let units: BTreeMap<&str, &str> = arrays
+        .iter()
+        .map(|a| (a.name.as_str(), a.unit.as_str()))
+        .collect();
+    let language = i18n::language();
+    let labels: BTreeMap<&str, String> = arrays
+        .iter()
+        .map(|a| (a.name.as_str(), i18n::channel_label(language, &a.name)))
+        .collect();
+    let metadata = json!({
+        "session": meta,
+        "units": units,
+        "labels": labels,
+    });
+    zip.start_file("metadata.npy", options)?;
+    zip.write_all(&npy_str_scalar(&metadata.to_string()))?;
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// .npy 头部 (格式版本 1.0)
+fn npy_header(descr: &str, shape: &[usize]) -> Vec<u8> {
+    let shape = match shape {
+        [] => "()".to_string(),
+        [n] => format!("({},)", n),
+        dims => format!(
+            "({})",
+            dims.iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut dict = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+
+    // magic (6) + 版本 (2) + 头长度 (2) + 头字典 + '\n'
+    let unpadded = NPY_MAGIC.len() + 4 + dict.len() + 1;
+    let padding = (NPY_ALIGN - unpadded % NPY_ALIGN) % NPY_ALIGN;
+    dict.push_str(&" ".repeat(padding));
+    dict.push('\n');
+
+    let mut buf = Vec::with_capacity(unpadded + padding);
+    buf.extend_from_slice(NPY_MAGIC);
+    buf.extend_from_slice(&[1, 0]);
+    buf.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+    buf.extend_from_slice(dict.as_bytes());
+    buf
+}
+
+/// float64 数组, 单列数组保存为一维 [N]
+fn npy_f64(array: &NamedArray) -> Vec<u8> {
+    let shape = if array.cols == 1 {
+        vec![array.rows]
+    } else {
+        vec![array.rows, array.cols]
+    };
+
+    let mut buf = npy_header("<f8", &shape);
+    buf.reserve(array.data.len() * 8);
+    for v in &array.data {
+        buf.extend_from_slice(&v.to_le_bytes());
+    }
+    buf
+}
+
+fn npy_i32_scalar(value: i32) -> Vec<u8> {
+    let mut buf = npy_header("<i4", &[]);
+    buf.extend_from_slice(&value.to_le_bytes());
+    buf
+}
+
+/// Unicode 字符串标量 (UTF-32)
+fn npy_str_scalar(value: &str) -> Vec<u8> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut buf = npy_header(&format!("<U{}", chars.len().max(1)), &[]);
+    for c in &chars {
+        buf.extend_from_slice(&(*c as u32).to_le_bytes());
+    }
+    if chars.is_empty() {
+        buf.extend_from_slice(&0u32.to_le_bytes());
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npy_layout() {
+        let array = NamedArray {
+            name: "actual_joint_positions".to_string(),
+            rows: 2,
+            cols: 3,
+            data: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+            unit: "rad".to_string(),
+        };
+        let buf = npy_f64(&array);
+
+        let header_len = u16::from_le_bytes([buf[8], buf[9]]) as usize;
+        assert_eq!((10 + header_len) % NPY_ALIGN, 0);
+        let header = std::str::from_utf8(&buf[10..10 + header_len]).unwrap();
+        assert!(header.contains("'descr': '<f8'"));
+        assert!(header.contains("'shape': (2, 3)"));
+        assert!(header.ends_with('\n'));
+
+        // 行优先: 第二行第一个元素为 4.0
+        let data = &buf[10 + header_len..];
+        assert_eq!(data.len(), 6 * 8);
+        assert_eq!(f64::from_le_bytes(data[24..32].try_into().unwrap()), 4.0);
+    }
+}
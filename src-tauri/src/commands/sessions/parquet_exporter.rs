@@ -0,0 +1,178 @@
+// parquet_exporter.rs - 以 Apache Parquet 格式导出录制 (列式存储, Snappy 压缩)
+use crate::{
+    commands::{
+        analysis::recording::{Recording, MOTION_STATE_CHANNEL},
+        sessions::export::ExportMeta,
+    },
+    utils::i18n,
+};
+use arrow_array::{ArrayRef, Float32Array, Float64Array, Int32Array, RecordBatch};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self},
+    path::Path,
+    sync::Arc,
+};
+
+// 每个 row group 的行数 (250Hz 约 4 分钟)
+const ROW_GROUP_SIZE: usize = 64 * 1024;
+
+/// 文件级元数据: 会话信息 (JSON)
+pub const SESSION_METADATA_KEY: &str = "ufactory.session";
+/// 文件级元数据: 通道名, 单位与界面语言的通道名称 (JSON)
+pub const CHANNELS_METADATA_KEY: &str = "ufactory.channels";
+/// 字段级元数据: 单位
+pub const UNIT_METADATA_KEY: &str = "unit";
+
+#[derive(Debug, Serialize)]
+struct ChannelInfo<'a> {
+    name: &'a str,
+    unit: &'a str,
+    label: String,
+}
+
+/// 写入 Parquet 文件
+///
+/// 第一列 time 为相对第一帧的时间 (s, f64), 之后每个通道一列 (f32, 运动状态为 i32)
+pub fn write_parquet(
+    recording: &Recording,
+    units: &[&str],
+    meta: &ExportMeta,
+    path: &Path,
+) -> io::Result<()> {
+    let language = i18n::language();
+    let mut fields = vec![field("time", DataType::Float64, "s")];
+    let mut channels = vec![ChannelInfo {
+        name: "time",
+        unit: "s",
+        label: i18n::channel_label(language, "time"),
+    }];
+    for (i, series) in recording.channels.iter().enumerate() {
+        let unit = units.get(i).copied().unwrap_or_default();
+        let data_type = if series.name == MOTION_STATE_CHANNEL {
+            DataType::Int32
+        } else {
+            DataType::Float32
+        };
+        fields.push(field(&series.name, data_type, unit));
+        channels.push(ChannelInfo {
+            name: &series.name,
+            unit,
+            label: i18n::channel_label(language, &series.name),
+        });
+    }
+
+    let metadata = HashMap::from([
+        (SESSION_METADATA_KEY.to_string(), to_json(meta)?),
+        (CHANNELS_METADATA_KEY.to_string(), to_json(&channels)?),
+    ]);
+    let schema = Arc::new(Schema::new_with_metadata(fields, metadata));
+
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_max_row_group_size(ROW_GROUP_SIZE)
+        .build();
+    let mut writer =
+        ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(props)).map_err(to_io)?;
+
+    let rows = recording.time.len();
+    for start in (0..rows).step_by(ROW_GROUP_SIZE) {
+        let end = (start + ROW_GROUP_SIZE).min(rows);
+
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(Float64Array::from(
+            recording.time[start..end].to_vec(),
+        ))];
+        for series in &recording.channels {
+            let values = series.values[start..end].iter();
+            let column: ArrayRef = if series.name == MOTION_STATE_CHANNEL {
+                Arc::new(Int32Array::from_iter_values(values.map(|v| *v as i32)))
+            } else {
+                Arc::new(Float32Array::from_iter_values(values.map(|v| *v as f32)))
+            };
+            columns.push(column);
+        }
+
+        let batch = RecordBatch::try_new(schema.clone(), columns).map_err(to_io)?;
+        writer.write(&batch).map_err(to_io)?;
+    }
+
+    writer.close().map_err(to_io)?;
+    Ok(())
+}
+
+fn field(name: &str, data_type: DataType, unit: &str) -> Field {
+    Field::new(name, data_type, false).with_metadata(HashMap::from([(
+        UNIT_METADATA_KEY.to_string(),
+        unit.to_string(),
+    )]))
+}
+
+fn to_json<T: Serialize>(value: &T) -> io::Result<String> {
+    serde_json::to_string(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn to_io<E: std::error::Error + Send + Sync + 'static>(e: E) -> io::Error {
+    io::Error::other(e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{analysis::recording::Series, arm_service::structs::ObserveParams};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    #[test]
+    fn test_write_parquet_schema() {
+        let path = std::env::temp_dir().join(format!(
+            "parquet_export_test_{}.parquet",
+            chrono::Local::now()
+                .timestamp_nanos_opt()
+                .unwrap_or_default()
+        ));
+
+        let rows = ROW_GROUP_SIZE + 10;
+        let recording = Recording {
+            time: (0..rows).map(|i| i as f64 * 0.004).collect(),
+            channels: vec![
+                Series {
+                    name: "actual_joint_positions_1".to_string(),
+                    values: (0..rows).map(|i| i as f64 * 0.001).collect(),
+                },
+                Series {
+                    name: MOTION_STATE_CHANNEL.to_string(),
+                    values: vec![1.0; rows],
+                },
+            ],
+        };
+        let meta = ExportMeta {
+            name: "robot_data_20250107_120000".to_string(),
+            robot_ip: "192.168.1.10".to_string(),
+            axis: 6,
+            start: "2025-01-07 12:00:00".to_string(),
+            end: "2025-01-07 12:04:22".to_string(),
+            observe_params: ObserveParams::default(),
+        };
+        write_parquet(&recording, &["rad", ""], &meta, &path).unwrap();
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let schema = builder.schema().clone();
+        assert_eq!(schema.fields().len(), 3);
+        assert_eq!(schema.field(1).name(), "actual_joint_positions_1");
+        assert_eq!(schema.field(1).metadata()[UNIT_METADATA_KEY], "rad");
+        assert_eq!(schema.field(2).data_type(), &DataType::Int32);
+        assert!(schema.metadata()[SESSION_METADATA_KEY].contains("192.168.1.10"));
+
+        let read_rows: usize = builder
+            .build()
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .sum();
+        assert_eq!(read_rows, rows);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
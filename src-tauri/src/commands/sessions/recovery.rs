@@ -0,0 +1,318 @@
+// recovery.rs - 启动时发现并修复异常退出遗留的临时录制
+use crate::commands::{
+    arm_service::structs::ObserveParams,
+    sessions::library::{SessionInfo, SessionLibrary},
+};
+use chrono::{Local, TimeZone};
+use serde::Serialize;
+use std::{
+    fs::OpenOptions,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+// 从 CSV 末尾向前读取的块大小
+const TAIL_CHUNK: u64 = 64 * 1024;
+
+/// 临时文件保留天数, 超期的遗留录制不再提供恢复
+pub const TEMP_RETENTION_DAYS: u32 = 7;
+
+/// 遗留的临时录制 (CSV 与同名 .raw)
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanedRecording {
+    pub name: String, // 文件名 (不含扩展名)
+    pub csv_path: PathBuf,
+    pub raw_path: Option<PathBuf>,
+    pub rows: usize,
+    pub start: Option<String>, // 第一行时间 (本地时间)
+    pub end: Option<String>,
+    pub duration: f64, // s
+    pub size: u64,     // CSV + raw 字节数
+}
+
+/// 扫描临时目录中的遗留录制, 截断不完整的最后一行/最后一帧;
+/// 空录制和已归档到会话库的临时文件直接删除
+///
+/// 应用为单实例, 启动时临时目录中的文件都属于上次运行
+pub fn scan_orphans(
+    csv_temp_dir: &Path,
+    library: &SessionLibrary,
+) -> io::Result<Vec<OrphanedRecording>> {
+    let mut orphans = vec![];
+    if !csv_temp_dir.exists() {
+        return Ok(orphans);
+    }
+
+    for entry in std::fs::read_dir(csv_temp_dir)?.flatten() {
+        let csv_path = entry.path();
+        if csv_path.extension().and_then(|e| e.to_str()) != Some("csv") {
+            continue;
+        }
+
+        let archived = csv_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .is_some_and(|name| library.has_source(name));
+        if archived {
+            discard_files(&csv_path);
+            continue;
+        }
+
+        let raw_path = Some(csv_path.with_extension("raw")).filter(|p| p.exists());
+        match inspect(&csv_path, raw_path) {
+            Ok(Some(orphan)) => orphans.push(orphan),
+            Ok(None) => discard_files(&csv_path),
+            Err(e) => eprintln!("Failed to inspect {}: {:?}", csv_path.display(), e),
+        }
+    }
+    orphans.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(orphans)
+}
+
+/// 修复并统计单个遗留录制, 没有数据行时返回 None
+fn inspect(csv_path: &Path, raw_path: Option<PathBuf>) -> io::Result<Option<OrphanedRecording>> {
+    truncate_partial_csv(csv_path)?;
+    if let Some(raw_path) = &raw_path {
+        truncate_partial_raw(raw_path)?;
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(csv_path)?;
+
+    let mut rows = 0;
+    let mut first_ts: Option<i64> = None;
+    let mut last_ts: Option<i64> = None;
+    for record in reader.records() {
+        let record = record?;
+        // 表头行没有时间戳
+        let Some(ts) = record.get(0).and_then(|f| f.trim().parse::<i64>().ok()) else {
+            continue;
+        };
+        rows += 1;
+        first_ts.get_or_insert(ts);
+        last_ts = Some(ts);
+    }
+
+    if rows == 0 {
+        return Ok(None);
+    }
+
+    let size = std::fs::metadata(csv_path)?.len()
+        + raw_path
+            .as_ref()
+            .and_then(|p| std::fs::metadata(p).ok())
+            .map_or(0, |m| m.len());
+
+    Ok(Some(OrphanedRecording {
+        name: csv_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        csv_path: csv_path.to_path_buf(),
+        raw_path,
+        rows,
+        start: first_ts.map(format_millis),
+        end: last_ts.map(format_millis),
+        duration: match (first_ts, last_ts) {
+            (Some(first), Some(last)) => (last - first) as f64 / 1000.0,
+            _ => 0.0,
+        },
+        size,
+    }))
+}
+
+fn format_millis(ts: i64) -> String {
+    Local
+        .timestamp_millis_opt(ts)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// CSV 截断到最后一个完整行, 并去掉列数与上一行不一致的最后一行
+pub fn truncate_partial_csv(path: &Path) -> io::Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let file_len = file.metadata()?.len();
+
+    // 从末尾读取, 直到包含最后两行完整的数据或读到文件开头
+    let mut tail_len = TAIL_CHUNK.min(file_len);
+    loop {
+        let start = file_len - tail_len;
+        let mut tail = vec![0u8; tail_len as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut tail)?;
+
+        let mut len = match tail.iter().rposition(|b| *b == b'\n') {
+            Some(pos) => pos + 1,
+            None => 0,
+        };
+        // 未读到文件开头时第一行可能不完整, 不参与比较
+        let lines: Vec<&[u8]> = tail[..len]
+            .split(|b| *b == b'\n')
+            .filter(|l| !l.is_empty())
+            .collect();
+        if start > 0 && lines.len() < 3 {
+            tail_len = (tail_len * 2).min(file_len);
+            continue;
+        }
+
+        if let [.., prev, last] = lines.as_slice() {
+            let fields = |line: &[u8]| line.iter().filter(|b| **b == b',').count();
+            if fields(prev) != fields(last) {
+                len -= last.len() + 1;
+            }
+        }
+
+        let len = start + len as u64;
+        if len < file_len {
+            file.set_len(len)?;
+        }
+        return Ok(());
+    }
+}
+
+/// 原始数据截断到最后一个完整帧 (帧头 4 字节为包长)
+pub fn truncate_partial_raw(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    let file_len = file.metadata()?.len();
+
+    // 只读取帧头, 跳过帧内容
+    let mut reader = BufReader::new(&file);
+    let mut header = [0u8; 4];
+    let mut offset = 0u64;
+    while offset + 4 <= file_len {
+        reader.read_exact(&mut header)?;
+        let size = u32::from_le_bytes(header) as u64;
+        if size < 4 || offset + size > file_len {
+            break;
+        }
+        reader.seek_relative(size as i64 - 4)?;
+        offset += size;
+    }
+    drop(reader);
+
+    if offset < file_len {
+        file.set_len(offset)?;
+    }
+    Ok(())
+}
+
+/// 将遗留录制恢复到会话库, 并删除临时文件
+pub fn recover(
+    library: &mut SessionLibrary,
+    orphan: &OrphanedRecording,
+) -> io::Result<SessionInfo> {
+    let id = orphan
+        .name
+        .strip_prefix("robot_data_")
+        .unwrap_or(&orphan.name)
+        .to_string();
+
+    let info = SessionInfo {
+        id: format!("{}_recovered", id),
+        name: orphan.name.clone(),
+        robot_ip: String::new(),
+        axis: 0,
+        observe_params: ObserveParams::default(),
+        start: orphan.start.clone().unwrap_or_default(),
+        end: orphan.end.clone().unwrap_or_default(),
+        duration: orphan.duration,
+        sample_count: orphan.rows,
+        notes: "Recovered after unexpected exit".to_string(),
+        tags: vec!["recovered".to_string()],
+        csv_file: String::new(),
+        raw_file: None,
+        source: Some(orphan.name.clone()),
+        markers: vec![],
+    };
+
+    let info = library.add_files(&orphan.csv_path, orphan.raw_path.as_deref(), info)?;
+    discard_files(&orphan.csv_path);
+
+    Ok(info)
+}
+
+/// 删除遗留录制 (CSV 与同名 .raw)
+pub fn discard_files(csv_path: &Path) {
+    for path in [csv_path.to_path_buf(), csv_path.with_extension("raw")] {
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                eprintln!("Failed to remove {}: {:?}", path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_partial_rows() {
+        let dir = std::env::temp_dir().join(format!(
+            "recovery_test_{}",
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // 最后一行只写了一半
+        let csv_path = dir.join("robot_data_20250107_120000.csv");
+        std::fs::write(
+            &csv_path,
+            "timestamp,col_1,col_2\n1736222400000,1,2\n1736222401000,3,4\n1736222402000,5",
+        )
+        .unwrap();
+
+        // 两个完整帧 + 半帧
+        let raw_path = csv_path.with_extension("raw");
+        let mut raw = vec![];
+        for _ in 0..2 {
+            raw.extend_from_slice(&8u32.to_le_bytes());
+            raw.extend_from_slice(&[0u8; 4]);
+        }
+        raw.extend_from_slice(&8u32.to_le_bytes());
+        std::fs::write(&raw_path, raw).unwrap();
+
+        let library = SessionLibrary::open(dir.join("library")).unwrap();
+        let orphans = scan_orphans(&dir, &library).unwrap();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].rows, 2);
+        assert_eq!(orphans[0].duration, 1.0);
+        assert!(std::fs::read_to_string(&csv_path)
+            .unwrap()
+            .ends_with("1736222401000,3,4\n"));
+        assert_eq!(std::fs::metadata(&raw_path).unwrap().len(), 16);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_truncate_large_csv_from_tail() {
+        let path = std::env::temp_dir().join(format!(
+            "recovery_tail_{}.csv",
+            Local::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+
+        // 超过一个读取块; 最后一行有换行但字段不全
+        let mut content = String::from("timestamp,col_1,col_2\n");
+        for i in 0..20_000 {
+            content.push_str(&format!("{},{},{}\n", 1736222400000i64 + i, i, i));
+        }
+        let complete = content.len() as u64;
+        content.push_str("1736222420000,5\n");
+        std::fs::write(&path, &content).unwrap();
+
+        truncate_partial_csv(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), complete);
+
+        // 已完整的文件保持不变
+        truncate_partial_csv(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), complete);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,78 @@
+// settings.rs - 用户设置命令, 修改后推送 USER_SETTINGS_CHANGED 事件
+use crate::{
+    commands::streaming::apply_streaming_settings,
+    state::{
+        app_state::AppState,
+        user_settings::{UserSettings, SETTINGS_VERSION},
+    },
+    utils::{error::AppError, response::Response},
+};
+
+#[tauri::command]
+pub fn get_user_settings(state: tauri::State<AppState>) -> Response<UserSettings> {
+    state.settings().into()
+}
+
+/// 保存全部设置, 流式输出配置变化时立即生效
+#[tauri::command(async)]
+pub async fn set_user_settings(
+    state: tauri::State<'_, AppState>,
+    settings: UserSettings,
+) -> Result<Response<UserSettings>, Response<String>> {
+    let previous = match state.settings() {
+        Ok(previous) => previous,
+        Err(e) => return Ok(e.into()),
+    };
+
+    let saved = match state.update_settings(|s| {
+        *s = settings;
+        s.version = SETTINGS_VERSION;
+    }) {
+        Ok(saved) => saved,
+        Err(e) => return Ok(e.into()),
+    };
+
+    let streaming_changed = serde_json::to_value(&previous.streaming).ok()
+        != serde_json::to_value(&saved.streaming).ok();
+    if streaming_changed {
+        // 设置已保存; 多个服务启动失败时记录全部原因, 返回第一个
+        let mut errors = apply_streaming_settings(&state, &saved.streaming).await;
+        for e in &errors {
+            eprintln!("{}", e);
+        }
+        if !errors.is_empty() {
+            return Ok(errors.swap_remove(0).into());
+        }
+    }
+
+    Ok(Response::success(saved))
+}
+
+/// 设置最近连接机械臂的昵称
+#[tauri::command]
+pub fn set_robot_nickname(
+    state: tauri::State<AppState>,
+    ip: &str,
+    nickname: &str,
+) -> Response<UserSettings> {
+    let mut found = false;
+    let result = state.update_settings(|s| {
+        if let Some(robot) = s.recent_robots.iter_mut().find(|r| r.ip == ip) {
+            robot.nickname = nickname.trim().to_string();
+            found = true;
+        }
+    });
+
+    match result {
+        Ok(_) if !found => AppError::RecentRobotNotFound(ip.to_string()).into(),
+        result => result.into(),
+    }
+}
+
+/// 从连接历史中移除
+#[tauri::command]
+pub fn remove_recent_robot(state: tauri::State<AppState>, ip: &str) -> Response<UserSettings> {
+    state
+        .update_settings(|s| s.recent_robots.retain(|r| r.ip != ip))
+        .into()
+}
@@ -0,0 +1,491 @@
+// metrics.rs - Prometheus 指标 HTTP 端点 (GET /metrics)
+//
+// 指标由数据包总线更新, 与抓取间隔无关: gauge 保存最新值, counter 持续累加.
+// 除 xarm_lagged_packets_total 外均带 robot_id 标签, 各机械臂分别统计
+//   xarm_joint_current_amperes{joint}             实际关节电流
+//   xarm_joint_torque_newton_meters{joint}        估算关节扭矩
+//   xarm_joint_tracking_error_rms_radians{joint}  最近 1s 目标与实际关节位置之差的 RMS
+//   xarm_packet_rate_hertz                        最近 1s 的数据包频率 (控制器时间)
+//   xarm_packets_total / xarm_dropped_packets_total  接收/丢失的数据包 (按时间戳间隔推算)
+//   xarm_connected / xarm_motion_state            连接状态 (0/1), 运动状态
+//   xarm_alarms_total{alarm}                      报警次数
+//   xarm_lagged_packets_total                     指标更新不及时而跳过的数据包
+// 可通过 robot_id 只统计其中一台
+use crate::commands::{
+    arm_service::robot_data::RobotDataPacket,
+    streaming::{accepts_robot, bind_http_server, BusPacket, PacketBus, MOTION_STATE_STOPPED},
+};
+use prometheus::{
+    Encoder, GaugeVec, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self},
+    net::SocketAddr,
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+use tiny_http::{Header, Response, Server};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    watch,
+};
+
+/// 旧版本的配置文件名 (位于配置目录), 已迁移到用户设置 streaming.metrics
+pub const CONFIG_FILE: &str = "metrics.json";
+
+// 频率/RMS 统计窗口 (控制器时间)
+const WINDOW_US: i64 = 1_000_000;
+// 间隔超过标称间隔的倍数时视为丢包
+const GAP_FACTOR: f64 = 1.5;
+const JOINTS: usize = 7;
+
+/// Prometheus 端点配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub host: String, // 默认只监听本机
+    pub port: u16,
+    pub robot_id: Option<String>, // 只统计指定机械臂, 省略时分别统计全部机械臂
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: 9464,
+            robot_id: None,
+        }
+    }
+}
+
+fn to_io(e: prometheus::Error) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// 机器人指标
+#[derive(Debug)]
+pub struct RobotMetrics {
+    registry: Registry,
+    joint_current: GaugeVec,
+    joint_torque: GaugeVec,
+    tracking_error_rms: GaugeVec,
+    packet_rate: GaugeVec,
+    packets: IntCounterVec,
+    dropped_packets: IntCounterVec,
+    lagged_packets: IntCounter,
+    connected: IntGaugeVec,
+    motion_state: IntGaugeVec,
+    alarms: IntCounterVec,
+}
+
+impl RobotMetrics {
+    pub fn new() -> io::Result<Self> {
+        let joint_gauge = |name: &str, help: &str| {
+            GaugeVec::new(Opts::new(name, help), &["robot_id", "joint"]).map_err(to_io)
+        };
+        let robot_counter = |name: &str, help: &str| {
+            IntCounterVec::new(Opts::new(name, help), &["robot_id"]).map_err(to_io)
+        };
+        let robot_gauge = |name: &str, help: &str| {
+            IntGaugeVec::new(Opts::new(name, help), &["robot_id"]).map_err(to_io)
+        };
+        let metrics = Self {
+            registry: Registry::new(),
+            joint_current: joint_gauge("xarm_joint_current_amperes", "Actual joint current")?,
+            joint_torque: joint_gauge("xarm_joint_torque_newton_meters", "Estimated joint torque")?,
+            tracking_error_rms: joint_gauge(
+                "xarm_joint_tracking_error_rms_radians",
+                "RMS of target minus actual joint position over the last second",
+            )?,
+            packet_rate: GaugeVec::new(
+                Opts::new("xarm_packet_rate_hertz", "Received packets per second"),
+                &["robot_id"],
+            )
+            .map_err(to_io)?,
+            packets: robot_counter("xarm_packets_total", "Received packets")?,
+            dropped_packets: robot_counter(
+                "xarm_dropped_packets_total",
+                "Packets missing according to controller timestamp gaps",
+            )?,
+            lagged_packets: IntCounter::new(
+                "xarm_lagged_packets_total",
+                "Packets skipped because the metrics updater fell behind",
+            )
+            .map_err(to_io)?,
+            connected: robot_gauge("xarm_connected", "Robot connection state (0/1)")?,
+            motion_state: robot_gauge("xarm_motion_state", "Robot motion state")?,
+            alarms: IntCounterVec::new(
+                Opts::new("xarm_alarms_total", "Robot alarms"),
+                &["robot_id", "alarm"],
+            )
+            .map_err(to_io)?,
+        };
+
+        let collectors: [Box<dyn prometheus::core::Collector>; 10] = [
+            Box::new(metrics.joint_current.clone()),
+            Box::new(metrics.joint_torque.clone()),
+            Box::new(metrics.tracking_error_rms.clone()),
+            Box::new(metrics.packet_rate.clone()),
+            Box::new(metrics.packets.clone()),
+            Box::new(metrics.dropped_packets.clone()),
+            Box::new(metrics.lagged_packets.clone()),
+            Box::new(metrics.connected.clone()),
+            Box::new(metrics.motion_state.clone()),
+            Box::new(metrics.alarms.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).map_err(to_io)?;
+        }
+        Ok(metrics)
+    }
+
+    /// 更新各机械臂的连接状态, 已移除的机械臂不再输出
+    pub fn set_connected(&self, robots: &[(String, bool)]) {
+        self.connected.reset();
+        for (robot_id, connected) in robots {
+            self.connected
+                .with_label_values(&[robot_id])
+                .set(*connected as i64);
+        }
+    }
+
+    /// Prometheus 文本格式
+    pub fn encode(&self) -> String {
+        let mut buf = vec![];
+        let _ = TextEncoder::new().encode(&self.registry.gather(), &mut buf);
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
+
+/// 按数据包更新指标, 每台机械臂分别保存窗口统计
+#[derive(Debug)]
+struct Collector {
+    metrics: Arc<RobotMetrics>,
+    windows: HashMap<String, Window>,
+}
+
+impl Collector {
+    fn new(metrics: Arc<RobotMetrics>) -> Self {
+        Self {
+            metrics,
+            windows: HashMap::new(),
+        }
+    }
+
+    fn observe(&mut self, packet: &BusPacket) {
+        self.windows
+            .entry(packet.robot_id.clone())
+            .or_insert_with(Window::new)
+            .observe(&self.metrics, &packet.robot_id, packet);
+    }
+}
+
+/// 单台机械臂的窗口统计
+#[derive(Debug)]
+struct Window {
+    window_start: Option<i64>,
+    last_timestamp: Option<i64>,
+    count: u32,
+    intervals: Vec<i64>,
+    squared_error: [f64; JOINTS],
+    nominal_interval: Option<i64>, // 上一窗口的间隔中位数
+    motion_state: Option<u8>,
+}
+
+impl Window {
+    fn new() -> Self {
+        Self {
+            window_start: None,
+            last_timestamp: None,
+            count: 0,
+            intervals: vec![],
+            squared_error: [0.0; JOINTS],
+            nominal_interval: None,
+            motion_state: None,
+        }
+    }
+
+    fn reset_window(&mut self, start: i64) {
+        self.window_start = Some(start);
+        self.count = 0;
+        self.intervals.clear();
+        self.squared_error = [0.0; JOINTS];
+    }
+
+    fn observe(&mut self, metrics: &RobotMetrics, robot_id: &str, packet: &RobotDataPacket) {
+        metrics.packets.with_label_values(&[robot_id]).inc();
+        for joint in 0..JOINTS {
+            let label = (joint + 1).to_string();
+            metrics
+                .joint_current
+                .with_label_values(&[robot_id, &label])
+                .set(packet.actual_joint_currents[joint] as f64);
+            metrics
+                .joint_torque
+                .with_label_values(&[robot_id, &label])
+                .set(packet.estimated_joint_torque[joint] as f64);
+        }
+
+        let motion_state = packet.motion_state();
+        metrics
+            .motion_state
+            .with_label_values(&[robot_id])
+            .set(motion_state as i64);
+        let previous = self.motion_state.replace(motion_state);
+        if motion_state == MOTION_STATE_STOPPED && previous.is_some_and(|s| s != motion_state) {
+            metrics
+                .alarms
+                .with_label_values(&[robot_id, "motion_stopped"])
+                .inc();
+        }
+
+        let timestamp = packet.timestamp;
+        match self.last_timestamp {
+            // 时间戳回退 (控制器重启) 时重新统计
+            Some(last) if timestamp <= last => {
+                self.nominal_interval = None;
+                self.reset_window(timestamp);
+            }
+            Some(last) => {
+                let interval = timestamp - last;
+                if let Some(nominal) = self.nominal_interval.filter(|n| *n > 0) {
+                    if interval as f64 > nominal as f64 * GAP_FACTOR {
+                        let missing = (interval as f64 / nominal as f64).round() as u64 - 1;
+                        metrics
+                            .dropped_packets
+                            .with_label_values(&[robot_id])
+                            .inc_by(missing);
+                    }
+                }
+                self.intervals.push(interval);
+            }
+            None => self.reset_window(timestamp),
+        }
+        self.last_timestamp = Some(timestamp);
+
+        self.count += 1;
+        for joint in 0..JOINTS {
+            let error = (packet.target_joint_positions[joint]
+                - packet.actual_joint_positions[joint]) as f64;
+            self.squared_error[joint] += error * error;
+        }
+
+        let start = self.window_start.unwrap_or(timestamp);
+        let elapsed = timestamp - start;
+        if elapsed >= WINDOW_US {
+            self.close_window(metrics, robot_id, elapsed);
+            self.reset_window(timestamp);
+        }
+    }
+
+    /// 窗口结束: 更新频率, RMS 与标称间隔
+    fn close_window(&mut self, metrics: &RobotMetrics, robot_id: &str, elapsed: i64) {
+        metrics
+            .packet_rate
+            .with_label_values(&[robot_id])
+            .set(self.intervals.len() as f64 * 1_000_000.0 / elapsed as f64);
+        for joint in 0..JOINTS {
+            metrics
+                .tracking_error_rms
+                .with_label_values(&[robot_id, &(joint + 1).to_string()])
+                .set((self.squared_error[joint] / self.count as f64).sqrt());
+        }
+
+        self.intervals.sort_unstable();
+        if let Some(median) = self.intervals.get(self.intervals.len() / 2) {
+            self.nominal_interval = Some(*median);
+        }
+    }
+}
+
+/// 运行中的指标端点
+pub struct MetricsServer {
+    addr: SocketAddr,
+    robot_id: Option<String>,
+    metrics: Arc<RobotMetrics>,
+    server: Arc<Server>,
+    handle: Option<JoinHandle<()>>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl MetricsServer {
+    /// 绑定地址, 在当前 tokio 运行时中更新指标, HTTP 请求由独立线程处理
+    pub fn start(
+        config: &MetricsConfig,
+        bus: PacketBus,
+        connected: &[(String, bool)],
+    ) -> io::Result<Self> {
+        let metrics = Arc::new(RobotMetrics::new()?);
+        metrics.set_connected(connected);
+
+        let server = Arc::new(bind_http_server(&config.host, config.port)?);
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| io::Error::other("Metrics server is not bound to an IP address"))?;
+
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        tokio::spawn(update_loop(
+            Collector::new(metrics.clone()),
+            bus.subscribe(),
+            config.robot_id.clone(),
+            shutdown_rx,
+        ));
+
+        let http_server = server.clone();
+        let http_metrics = metrics.clone();
+        let handle = thread::spawn(move || {
+            let content_type = Header::from_bytes("Content-Type", TextEncoder::new().format_type())
+                .expect("valid header");
+            for request in http_server.incoming_requests() {
+                let response = if request.url() == "/metrics" {
+                    Response::from_string(http_metrics.encode()).with_header(content_type.clone())
+                } else {
+                    Response::from_string("Not Found").with_status_code(404)
+                };
+                let _ = request.respond(response);
+            }
+        });
+        eprintln!("Metrics endpoint listening on http://{}/metrics", addr);
+
+        Ok(Self {
+            addr,
+            robot_id: config.robot_id.clone(),
+            metrics,
+            server,
+            handle: Some(handle),
+            shutdown,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 统计的机械臂, None 为全部
+    pub fn robot_id(&self) -> Option<&str> {
+        self.robot_id.as_deref()
+    }
+
+    pub fn metrics(&self) -> &RobotMetrics {
+        &self.metrics
+    }
+
+    pub fn stop(mut self) {
+        self.close();
+    }
+
+    /// 停止更新并等待 HTTP 线程结束, 线程持有的 Server 随之释放
+    fn close(&mut self) {
+        let _ = self.shutdown.send(true);
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                eprintln!("Failed to join metrics server thread");
+            }
+        }
+    }
+}
+
+impl fmt::Debug for MetricsServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetricsServer")
+            .field("addr", &self.addr)
+            .field("robot_id", &self.robot_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+async fn update_loop(
+    mut collector: Collector,
+    mut packets: broadcast::Receiver<Arc<BusPacket>>,
+    robot_id: Option<String>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            packet = packets.recv() => match packet {
+                Ok(packet) if accepts_robot(&robot_id, &packet) => collector.observe(&packet),
+                Ok(_) => continue,
+                // 积压的数据包已被丢弃, 无法区分所属机械臂
+                Err(RecvError::Lagged(skipped)) => collector.metrics.lagged_packets.inc_by(skipped),
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(robot_id: &str, timestamp: i64, motion_state: u8) -> BusPacket {
+        let mut data = vec![0u8; 784];
+        data[..4].copy_from_slice(&784u32.to_le_bytes());
+        data[4..12].copy_from_slice(&timestamp.to_le_bytes());
+        data[12] = motion_state;
+        let mut packet = RobotDataPacket::from_bytes(&data).unwrap();
+        packet.target_joint_positions[0] = 0.01;
+        packet.actual_joint_currents[1] = 1.5;
+        BusPacket {
+            robot_id: robot_id.to_string(),
+            packet,
+        }
+    }
+
+    #[test]
+    fn test_collect_rate_drops_and_alarms() {
+        let metrics = Arc::new(RobotMetrics::new().unwrap());
+        let mut collector = Collector::new(metrics.clone());
+
+        // 250Hz 共 3s, 第 2s 中丢失 2 个包; 另一台机械臂的时间戳错开且不丢包
+        let mut timestamp = 0;
+        for i in 0..750 {
+            if i == 400 {
+                timestamp += 8_000;
+            }
+            let motion_state = if i >= 700 { MOTION_STATE_STOPPED } else { 1 };
+            collector.observe(&packet("a", timestamp, motion_state));
+            collector.observe(&packet("b", i * 4_000 + 500_000, 1));
+            timestamp += 4_000;
+        }
+
+        let a = |metric: &IntCounterVec| metric.with_label_values(&["a"]).get();
+        assert_eq!(a(&metrics.packets), 750);
+        assert_eq!(a(&metrics.dropped_packets), 2);
+        assert_eq!(metrics.dropped_packets.with_label_values(&["b"]).get(), 0);
+        let rate = metrics.packet_rate.with_label_values(&["b"]).get();
+        assert!((rate - 250.0).abs() < 1.0);
+        let rms = metrics
+            .tracking_error_rms
+            .with_label_values(&["a", "1"])
+            .get();
+        assert!((rms - 0.01).abs() < 1e-6);
+        assert_eq!(
+            metrics
+                .alarms
+                .with_label_values(&["a", "motion_stopped"])
+                .get(),
+            1
+        );
+
+        metrics.set_connected(&[("a".to_string(), true)]);
+        let text = metrics.encode();
+        assert!(text.contains("xarm_joint_current_amperes{joint=\"2\",robot_id=\"a\"} 1.5"));
+        assert!(text.contains("xarm_motion_state{robot_id=\"a\"} 4"));
+        assert!(text.contains("xarm_motion_state{robot_id=\"b\"} 1"));
+        assert!(text.contains("xarm_connected{robot_id=\"a\"} 1"));
+    }
+}
@@ -0,0 +1,519 @@
+pub mod metrics;
+pub mod mqtt_publisher;
+pub mod udp_publisher;
+pub mod ws_server;
+
+use std::{
+    io::{self},
+    ops::Deref,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tiny_http::Server;
+use tokio::sync::broadcast;
+
+use crate::{
+    commands::{
+        analysis::recording::{packet_values, MOTION_STATE_CHANNEL, RAW_CHANNELS},
+        arm_service::{robot_data::RobotDataPacket, structs::ObserveType},
+        streaming::{
+            metrics::{MetricsConfig, MetricsServer},
+            mqtt_publisher::{MqttConfig, MqttPublisher},
+            udp_publisher::{UdpPublisher, UdpPublisherConfig},
+            ws_server::WsServer,
+        },
+    },
+    state::{app_state::AppState, user_settings::StreamingSettings},
+    utils::{error::AppError, response::Response},
+};
+
+/// 数据包总线: 各机械臂采集线程解码后的每个数据包都会发布到总线
+pub type PacketBus = broadcast::Sender<Arc<BusPacket>>;
+
+/// 总线上的数据包, 标记来源机械臂
+#[derive(Debug, Clone)]
+pub struct BusPacket {
+    pub robot_id: String,
+    pub packet: RobotDataPacket,
+}
+
+impl Deref for BusPacket {
+    type Target = RobotDataPacket;
+
+    fn deref(&self) -> &RobotDataPacket {
+        &self.packet
+    }
+}
+
+/// 是否接收该机械臂的数据包, `robot_id` 为空时接收全部机械臂
+pub fn accepts_robot(robot_id: &Option<String>, packet: &BusPacket) -> bool {
+    robot_id.is_none() || robot_id.as_deref() == Some(packet.robot_id.as_str())
+}
+
+// 总线缓冲的数据包数 (250Hz 约 4s), 订阅者处理不过来时丢弃最旧的数据包
+const PACKET_BUS_CAPACITY: usize = 1024;
+
+pub fn packet_bus() -> PacketBus {
+    broadcast::channel(PACKET_BUS_CAPACITY).0
+}
+
+/// 运动状态: 停止 (错误或急停后进入), 进入时视为报警
+pub const MOTION_STATE_STOPPED: u8 = 4;
+
+/// 可流式输出的通道: 原始数据中的观测类型与运动状态
+pub fn channel_names() -> Vec<String> {
+    RAW_CHANNELS
+        .iter()
+        .map(|ot| ot.name())
+        .chain(std::iter::once(MOTION_STATE_CHANNEL.to_string()))
+        .collect()
+}
+
+/// 数据包中通道的数值, 未知通道返回 None
+pub fn channel_values(packet: &RobotDataPacket, name: &str) -> Option<Vec<f32>> {
+    if name == MOTION_STATE_CHANNEL {
+        return Some(vec![packet.motion_state() as f32]);
+    }
+    ObserveType::from_name(name)
+        .filter(|ot| RAW_CHANNELS.contains(ot))
+        .map(|ot| packet_values(packet, ot).to_vec())
+}
+
+/// 检查通道名是否均可流式输出
+pub fn validate_channels(channels: &[String]) -> Result<(), AppError> {
+    let known = channel_names();
+    match channels.iter().find(|c| !known.contains(c)) {
+        Some(unknown) => Err(AppError::UnknownChannel(unknown.clone())),
+        None => Ok(()),
+    }
+}
+
+/// 按控制器时间戳抽取数据包, 限制最大发送频率
+#[derive(Debug)]
+pub struct Decimator {
+    interval_us: i64,
+    last_sent: Option<i64>,
+}
+
+impl Decimator {
+    /// `rate` 为最大频率 Hz, 省略或不大于 0 时不抽取
+    pub fn new(rate: Option<f64>) -> Self {
+        let interval_us = match rate {
+            Some(rate) if rate > 0.0 => (1_000_000.0 / rate) as i64,
+            _ => 0,
+        };
+        Self {
+            interval_us,
+            last_sent: None,
+        }
+    }
+
+    /// 是否发送该时间戳的数据包
+    pub fn accept(&mut self, timestamp: i64) -> bool {
+        if let Some(last) = self.last_sent {
+            // 时间戳回退 (控制器重启) 时重新开始计时
+            if timestamp >= last && timestamp - last < self.interval_us {
+                return false;
+            }
+        }
+        self.last_sent = Some(timestamp);
+        true
+    }
+}
+
+/// 本地 WebSocket 服务配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamingServerConfig {
+    #[serde(default = "default_host")]
+    pub host: String, // 默认只监听本机
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_port() -> u16 {
+    8765
+}
+
+impl Default for StreamingServerConfig {
+    fn default() -> Self {
+        Self {
+            host: default_host(),
+            port: default_port(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamingServerStatus {
+    pub running: bool,
+    pub address: Option<String>,
+    pub clients: usize,
+}
+
+/// 启动本地 WebSocket 流式服务, 已运行时先停止
+#[tauri::command(async)]
+pub async fn start_streaming_server(
+    state: tauri::State<'_, AppState>,
+    config: Option<StreamingServerConfig>,
+) -> Result<Response<StreamingServerStatus>, Response<String>> {
+    let config = config.unwrap_or_default();
+
+    // 等待旧的监听端口释放后再绑定
+    let running = state
+        .streaming_server
+        .lock()
+        .ok()
+        .and_then(|mut server| server.take());
+    if let Some(server) = running {
+        server.close().await;
+    }
+
+    let server = match WsServer::start(&config, state.packet_bus.clone()).await {
+        Ok(server) => server,
+        Err(e) => {
+            return Ok(AppError::ServiceStart {
+                service: "WebSocket",
+                addr: format!("{}:{}", config.host, config.port),
+                reason: e.to_string(),
+            }
+            .into())
+        }
+    };
+
+    let status = StreamingServerStatus {
+        running: true,
+        address: Some(server.addr().to_string()),
+        clients: 0,
+    };
+    match state.streaming_server.lock() {
+        Ok(mut guard) => {
+            *guard = Some(server);
+            Ok(Response::success(status))
+        }
+        Err(_) => Ok(AppError::LockPoisoned("streaming_server").into()),
+    }
+}
+
+#[tauri::command]
+pub fn stop_streaming_server(state: tauri::State<AppState>) -> Response<String> {
+    match state.streaming_server.lock() {
+        Ok(mut server) => match server.take() {
+            Some(server) => {
+                server.stop();
+                Response::success("Streaming server stopped".to_string())
+            }
+            None => AppError::ServiceNotRunning("WebSocket").into(),
+        },
+        Err(_) => AppError::LockPoisoned("streaming_server").into(),
+    }
+}
+
+#[tauri::command]
+pub fn get_streaming_server_status(
+    state: tauri::State<AppState>,
+) -> Response<StreamingServerStatus> {
+    match state.streaming_server.lock() {
+        Ok(server) => Response::success(match server.as_ref() {
+            Some(server) => StreamingServerStatus {
+                running: true,
+                address: Some(server.addr().to_string()),
+                clients: server.clients(),
+            },
+            None => StreamingServerStatus {
+                running: false,
+                address: None,
+                clients: 0,
+            },
+        }),
+        Err(_) => AppError::LockPoisoned("streaming_server").into(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UdpPublisherStatus {
+    pub running: bool,
+    pub target: Option<String>,
+    pub sent: u64,
+}
+
+/// 绑定 HTTP 端点 (指标端点与 REST 接口)
+///
+/// tiny_http 在释放 Server 后由其监听线程异步关闭端口, 重启同一端口时短暂重试
+pub(crate) fn bind_http_server(host: &str, port: u16) -> io::Result<Server> {
+    let mut attempts = 0;
+    loop {
+        match Server::http((host, port)) {
+            Ok(server) => return Ok(server),
+            Err(e) => {
+                let in_use = e
+                    .downcast_ref::<io::Error>()
+                    .is_some_and(|e| e.kind() == io::ErrorKind::AddrInUse);
+                if !in_use || attempts >= 20 {
+                    return Err(io::Error::new(io::ErrorKind::AddrInUse, e.to_string()));
+                }
+                attempts += 1;
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+    }
+}
+
+/// 按配置启动 UDP 发布, 未启用时只停止已有的发布
+async fn apply_udp_publisher(
+    state: &AppState,
+    config: &UdpPublisherConfig,
+) -> Result<(), AppError> {
+    if let Ok(mut publisher) = state.udp_publisher.lock() {
+        publisher.take();
+    }
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let publisher = UdpPublisher::start(config, state.packet_bus.clone())
+        .await
+        .map_err(|e| AppError::ServiceStart {
+            service: "UDP",
+            addr: format!("{}:{}", config.host, config.port),
+            reason: e.to_string(),
+        })?;
+    let mut guard = state
+        .udp_publisher
+        .lock()
+        .map_err(|_| AppError::LockPoisoned("udp_publisher"))?;
+    *guard = Some(publisher);
+    Ok(())
+}
+
+/// 按设置重新启动 UDP, MQTT 发布与指标端点, 返回启动失败的原因
+pub async fn apply_streaming_settings(
+    state: &AppState,
+    settings: &StreamingSettings,
+) -> Vec<AppError> {
+    let mut errors = vec![];
+    if let Err(e) = apply_udp_publisher(state, &settings.udp).await {
+        errors.push(e);
+    }
+    if let Err(e) = apply_mqtt_publisher(state, &settings.mqtt) {
+        errors.push(e);
+    }
+    if let Err(e) = apply_metrics_server(state, &settings.metrics) {
+        errors.push(e);
+    }
+    errors
+}
+
+/// 启动时按用户设置恢复 UDP, MQTT 发布与指标端点
+pub fn restore_publishers(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let settings = match state.settings() {
+            Ok(settings) => settings,
+            Err(e) => return eprintln!("{}", e),
+        };
+
+        for e in apply_streaming_settings(&state, &settings.streaming).await {
+            eprintln!("{}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_udp_publisher_config(state: tauri::State<AppState>) -> Response<UdpPublisherConfig> {
+    state.settings().map(|s| s.streaming.udp).into()
+}
+
+/// 保存 UDP 发布配置并立即生效
+#[tauri::command(async)]
+pub async fn set_udp_publisher_config(
+    state: tauri::State<'_, AppState>,
+    config: UdpPublisherConfig,
+) -> Result<Response<String>, Response<String>> {
+    if let Err(e) = validate_channels(&config.channels) {
+        return Ok(e.into());
+    }
+    if let Err(e) = state.update_settings(|s| s.streaming.udp = config.clone()) {
+        return Ok(e.into());
+    }
+
+    Ok(match apply_udp_publisher(&state, &config).await {
+        Ok(()) => Response::success("UDP publisher config saved".to_string()),
+        Err(e) => e.into(),
+    })
+}
+
+#[tauri::command]
+pub fn get_udp_publisher_status(state: tauri::State<AppState>) -> Response<UdpPublisherStatus> {
+    match state.udp_publisher.lock() {
+        Ok(publisher) => Response::success(match publisher.as_ref() {
+            Some(publisher) => UdpPublisherStatus {
+                running: true,
+                target: Some(publisher.target().to_string()),
+                sent: publisher.sent(),
+            },
+            None => UdpPublisherStatus {
+                running: false,
+                target: None,
+                sent: 0,
+            },
+        }),
+        Err(_) => AppError::LockPoisoned("udp_publisher").into(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MqttPublisherStatus {
+    pub running: bool,
+    pub connected: bool,
+    pub broker: Option<String>,
+    pub published: u64,
+}
+
+/// 按配置启动 MQTT 发布, 未启用时只停止已有的发布
+fn apply_mqtt_publisher(state: &AppState, config: &MqttConfig) -> Result<(), AppError> {
+    let mut guard = state
+        .mqtt_publisher
+        .lock()
+        .map_err(|_| AppError::LockPoisoned("mqtt_publisher"))?;
+    guard.take();
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let publisher = MqttPublisher::start(config, state.packet_bus.clone()).map_err(|e| {
+        AppError::ServiceStart {
+            service: "MQTT",
+            addr: format!("{}:{}", config.host, config.port),
+            reason: e.to_string(),
+        }
+    })?;
+    *guard = Some(publisher);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_mqtt_config(state: tauri::State<AppState>) -> Response<MqttConfig> {
+    state.settings().map(|s| s.streaming.mqtt).into()
+}
+
+/// 保存 MQTT 配置并立即生效 (重新连接 Broker)
+#[tauri::command(async)]
+pub async fn set_mqtt_config(
+    state: tauri::State<'_, AppState>,
+    config: MqttConfig,
+) -> Result<Response<String>, Response<String>> {
+    if let Err(e) = state.update_settings(|s| s.streaming.mqtt = config.clone()) {
+        return Ok(e.into());
+    }
+
+    Ok(match apply_mqtt_publisher(&state, &config) {
+        Ok(()) => Response::success("MQTT config saved".to_string()),
+        Err(e) => e.into(),
+    })
+}
+
+#[tauri::command]
+pub fn get_mqtt_status(state: tauri::State<AppState>) -> Response<MqttPublisherStatus> {
+    match state.mqtt_publisher.lock() {
+        Ok(publisher) => Response::success(match publisher.as_ref() {
+            Some(publisher) => MqttPublisherStatus {
+                running: true,
+                connected: publisher.connected(),
+                broker: Some(publisher.broker().to_string()),
+                published: publisher.published(),
+            },
+            None => MqttPublisherStatus {
+                running: false,
+                connected: false,
+                broker: None,
+                published: 0,
+            },
+        }),
+        Err(_) => AppError::LockPoisoned("mqtt_publisher").into(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsServerStatus {
+    pub running: bool,
+    pub address: Option<String>,
+}
+
+/// 机械臂连接或断开后更新指标端点中的连接状态
+pub fn update_connection_metric(state: &AppState) {
+    if let Ok(server) = state.metrics_server.lock() {
+        if let Some(server) = server.as_ref() {
+            let connected = state.robot_connections(server.robot_id());
+            server.metrics().set_connected(&connected);
+        }
+    }
+}
+
+/// 按配置启动指标端点, 未启用时只停止已有的端点
+fn apply_metrics_server(state: &AppState, config: &MetricsConfig) -> Result<(), AppError> {
+    let mut guard = state
+        .metrics_server
+        .lock()
+        .map_err(|_| AppError::LockPoisoned("metrics_server"))?;
+    // 等待旧的端点线程结束后再绑定
+    if let Some(server) = guard.take() {
+        server.stop();
+    }
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let connected = state.robot_connections(config.robot_id.as_deref());
+    let server =
+        MetricsServer::start(config, state.packet_bus.clone(), &connected).map_err(|e| {
+            AppError::ServiceStart {
+                service: "Prometheus",
+                addr: format!("{}:{}", config.host, config.port),
+                reason: e.to_string(),
+            }
+        })?;
+    *guard = Some(server);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_metrics_config(state: tauri::State<AppState>) -> Response<MetricsConfig> {
+    state.settings().map(|s| s.streaming.metrics).into()
+}
+
+/// 保存指标端点配置并立即生效
+#[tauri::command(async)]
+pub async fn set_metrics_config(
+    state: tauri::State<'_, AppState>,
+    config: MetricsConfig,
+) -> Result<Response<String>, Response<String>> {
+    if let Err(e) = state.update_settings(|s| s.streaming.metrics = config.clone()) {
+        return Ok(e.into());
+    }
+
+    Ok(match apply_metrics_server(&state, &config) {
+        Ok(()) => Response::success("Metrics config saved".to_string()),
+        Err(e) => e.into(),
+    })
+}
+
+#[tauri::command]
+pub fn get_metrics_status(state: tauri::State<AppState>) -> Response<MetricsServerStatus> {
+    match state.metrics_server.lock() {
+        Ok(server) => Response::success(MetricsServerStatus {
+            running: server.is_some(),
+            address: server
+                .as_ref()
+                .map(|s| format!("http://{}/metrics", s.addr())),
+        }),
+        Err(_) => AppError::LockPoisoned("metrics_server").into(),
+    }
+}
@@ -0,0 +1,501 @@
+// mqtt_publisher.rs - 将抽取后的遥测数据发布到 MQTT Broker
+//
+// 主题 (可配置, 为空时不发布):
+//   xarm/joint_positions  {"timestamp":12.3,"robot_id":"...","values":[...]}  rad
+//   xarm/joint_currents   {"timestamp":12.3,"robot_id":"...","values":[...]}  A
+//   xarm/tcp_pose         {"timestamp":12.3,"robot_id":"...","values":[...]}  mm & rad
+//   xarm/motion_state     {"timestamp":12.3,"robot_id":"...","motion_state":1,"control_mode":0}
+//   xarm/alarm            {"timestamp":12.3,"robot_id":"...","alarm":"motion_stopped","motion_state":4}
+//   xarm/status (retained) {"online":true,"robot_id":"...","motion_state":2,"timestamp":12.3},
+//                          断开时遗嘱为 {"online":false}
+// timestamp 为控制器时间 (s). 各机械臂分别抽取与跟踪运动状态, 由 robot_id 区分;
+// 可通过 robot_id 只发布其中一台. 本地测试: mosquitto -v, mosquitto_sub -t 'xarm/#' -v
+use crate::commands::{
+    arm_service::robot_data::RobotDataPacket,
+    streaming::{accepts_robot, BusPacket, Decimator, PacketBus, MOTION_STATE_STOPPED},
+};
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Outgoing, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    io::{self},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    watch,
+};
+
+/// 旧版本的配置文件名 (位于配置目录), 已迁移到用户设置 streaming.mqtt
+pub const CONFIG_FILE: &str = "mqtt.json";
+
+// 发送队列长度, 断线时超出的遥测直接丢弃
+const REQUEST_CAPACITY: usize = 64;
+// 重连间隔 (指数退避)
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+// 停止时等待离线状态与断开报文发出的时间
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 各数据的发布主题, 为空时不发布
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttTopics {
+    pub joint_positions: String,
+    pub joint_currents: String,
+    pub tcp_pose: String,
+    pub motion_state: String,
+    pub alarm: String,
+    pub status: String,
+}
+
+impl Default for MqttTopics {
+    fn default() -> Self {
+        Self {
+            joint_positions: "xarm/joint_positions".to_string(),
+            joint_currents: "xarm/joint_currents".to_string(),
+            tcp_pose: "xarm/tcp_pose".to_string(),
+            motion_state: "xarm/motion_state".to_string(),
+            alarm: "xarm/alarm".to_string(),
+            status: "xarm/status".to_string(),
+        }
+    }
+}
+
+/// MQTT 发布配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub qos: u8,         // 0, 1, 2
+    pub rate: f64,       // 遥测最大发布频率 Hz
+    pub keep_alive: u64, // s
+    pub topics: MqttTopics,
+    pub robot_id: Option<String>, // 只发布指定机械臂, 省略时发布全部机械臂
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: 1883,
+            client_id: "ufactory-assistant".to_string(),
+            username: None,
+            password: None,
+            qos: 0,
+            rate: 10.0,
+            keep_alive: 30,
+            topics: MqttTopics::default(),
+            robot_id: None,
+        }
+    }
+}
+
+impl MqttConfig {
+    fn options(&self) -> MqttOptions {
+        let mut options = MqttOptions::new(&self.client_id, &self.host, self.port);
+        options.set_keep_alive(Duration::from_secs(self.keep_alive.max(5)));
+        if let Some(username) = self.username.as_ref().filter(|u| !u.is_empty()) {
+            options.set_credentials(username, self.password.clone().unwrap_or_default());
+        }
+        if !self.topics.status.is_empty() {
+            options.set_last_will(LastWill::new(
+                &self.topics.status,
+                json!({ "online": false }).to_string(),
+                QoS::AtLeastOnce,
+                true,
+            ));
+        }
+        options
+    }
+}
+
+/// 待发布的消息
+#[derive(Debug, PartialEq)]
+struct Message {
+    topic: String,
+    payload: String,
+    retain: bool,
+}
+
+/// 单台机械臂的抽取与运动状态
+#[derive(Debug)]
+struct RobotTelemetry {
+    decimator: Decimator,
+    motion_state: Option<u8>,
+}
+
+/// 由数据包生成遥测消息: 数值按频率抽取, 运动状态变化时立即发布状态与报警
+#[derive(Debug)]
+struct Telemetry {
+    topics: MqttTopics,
+    rate: f64,
+    robots: HashMap<String, RobotTelemetry>,
+}
+
+impl Telemetry {
+    fn new(topics: MqttTopics, rate: f64) -> Self {
+        Self {
+            topics,
+            rate,
+            robots: HashMap::new(),
+        }
+    }
+
+    fn messages(&mut self, robot_id: &str, packet: &RobotDataPacket) -> Vec<Message> {
+        let rate = self.rate;
+        let robot = self
+            .robots
+            .entry(robot_id.to_string())
+            .or_insert_with(|| RobotTelemetry {
+                decimator: Decimator::new(Some(rate)),
+                motion_state: None,
+            });
+        let timestamp = packet.timestamp as f64 / 1_000_000.0;
+        let motion_state = packet.motion_state();
+        let mut messages = vec![];
+        let mut push = |topic: &str, payload: Value, retain: bool| {
+            if !topic.is_empty() {
+                messages.push(Message {
+                    topic: topic.to_string(),
+                    payload: payload.to_string(),
+                    retain,
+                });
+            }
+        };
+
+        if robot.motion_state != Some(motion_state) {
+            let previous = robot.motion_state.replace(motion_state);
+            push(
+                &self.topics.status,
+                json!({
+                    "online": true,
+                    "robot_id": robot_id,
+                    "motion_state": motion_state,
+                    "timestamp": timestamp,
+                }),
+                true,
+            );
+            if motion_state == MOTION_STATE_STOPPED && previous.is_some() {
+                push(
+                    &self.topics.alarm,
+                    json!({
+                        "timestamp": timestamp,
+                        "robot_id": robot_id,
+                        "alarm": "motion_stopped",
+                        "motion_state": motion_state,
+                    }),
+                    false,
+                );
+            }
+        }
+
+        if robot.decimator.accept(packet.timestamp) {
+            let values = |values: &[f32]| json!({ "timestamp": timestamp, "robot_id": robot_id, "values": values });
+            push(
+                &self.topics.joint_positions,
+                values(&packet.actual_joint_positions),
+                false,
+            );
+            push(
+                &self.topics.joint_currents,
+                values(&packet.actual_joint_currents),
+                false,
+            );
+            push(
+                &self.topics.tcp_pose,
+                values(&packet.actual_tcp_pose),
+                false,
+            );
+            push(
+                &self.topics.motion_state,
+                json!({
+                    "timestamp": timestamp,
+                    "robot_id": robot_id,
+                    "motion_state": motion_state,
+                    "control_mode": packet.control_mode(),
+                }),
+                false,
+            );
+        }
+
+        messages
+    }
+}
+
+/// 运行中的 MQTT 发布, 断线后自动重连, 停止或释放时发布离线状态并断开
+#[derive(Debug)]
+pub struct MqttPublisher {
+    broker: String,
+    shutdown: watch::Sender<bool>,
+    connected: Arc<AtomicBool>,
+    published: Arc<AtomicU64>,
+}
+
+impl MqttPublisher {
+    /// 在当前 tokio 运行时中开始连接与发布
+    pub fn start(config: &MqttConfig, bus: PacketBus) -> io::Result<Self> {
+        let qos = rumqttc::qos(config.qos).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid QoS: {}", config.qos),
+            )
+        })?;
+        if config.rate <= 0.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid rate: {}", config.rate),
+            ));
+        }
+
+        let (client, eventloop) = AsyncClient::new(config.options(), REQUEST_CAPACITY);
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let connected = Arc::new(AtomicBool::new(false));
+        let published = Arc::new(AtomicU64::new(0));
+
+        tokio::spawn(connection_loop(
+            client.clone(),
+            eventloop,
+            config.topics.status.clone(),
+            shutdown_rx.clone(),
+            connected.clone(),
+        ));
+        tokio::spawn(publish_loop(
+            client,
+            bus.subscribe(),
+            config.robot_id.clone(),
+            Telemetry::new(config.topics.clone(), config.rate),
+            qos,
+            shutdown_rx,
+            published.clone(),
+        ));
+
+        let broker = format!("{}:{}", config.host, config.port);
+        eprintln!("MQTT publisher connecting to {}", broker);
+        Ok(Self {
+            broker,
+            shutdown,
+            connected,
+            published,
+        })
+    }
+
+    pub fn broker(&self) -> &str {
+        &self.broker
+    }
+
+    pub fn connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// 已加入发送队列的消息数
+    pub fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+
+    pub fn stop(self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+impl Drop for MqttPublisher {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+/// 驱动 MQTT 连接: 连接成功后发布在线状态, 出错时按退避间隔重连
+async fn connection_loop(
+    client: AsyncClient,
+    mut eventloop: rumqttc::EventLoop,
+    status_topic: String,
+    mut shutdown: watch::Receiver<bool>,
+    connected: Arc<AtomicBool>,
+) {
+    let mut backoff = RECONNECT_MIN;
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            event = eventloop.poll() => match event {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    connected.store(true, Ordering::Relaxed);
+                    backoff = RECONNECT_MIN;
+                    if !status_topic.is_empty() {
+                        let online = json!({ "online": true }).to_string();
+                        let _ = client.try_publish(&status_topic, QoS::AtLeastOnce, true, online);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    if connected.swap(false, Ordering::Relaxed) {
+                        eprintln!("MQTT connection lost: {:?}", e);
+                    }
+                    tokio::select! {
+                        _ = shutdown.changed() => break,
+                        _ = tokio::time::sleep(backoff) => {}
+                    }
+                    backoff = (backoff * 2).min(RECONNECT_MAX);
+                }
+            },
+        }
+    }
+
+    if connected.swap(false, Ordering::Relaxed) {
+        if !status_topic.is_empty() {
+            let offline = json!({ "online": false }).to_string();
+            let _ = client.try_publish(&status_topic, QoS::AtLeastOnce, true, offline);
+        }
+        let _ = client.try_disconnect();
+        let _ = tokio::time::timeout(FLUSH_TIMEOUT, async {
+            while let Ok(event) = eventloop.poll().await {
+                if let Event::Outgoing(Outgoing::Disconnect) = event {
+                    break;
+                }
+            }
+        })
+        .await;
+    }
+}
+
+async fn publish_loop(
+    client: AsyncClient,
+    mut packets: broadcast::Receiver<Arc<BusPacket>>,
+    robot_id: Option<String>,
+    mut telemetry: Telemetry,
+    qos: QoS,
+    mut shutdown: watch::Receiver<bool>,
+    published: Arc<AtomicU64>,
+) {
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            packet = packets.recv() => match packet {
+                Ok(packet) if accepts_robot(&robot_id, &packet) => {
+                    for message in telemetry.messages(&packet.robot_id, &packet) {
+                        // 队列已满 (断线中) 时丢弃, 不阻塞数据包处理
+                        if client
+                            .try_publish(message.topic, qos, message.retain, message.payload)
+                            .is_ok()
+                        {
+                            published.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::streaming::packet_bus;
+
+    fn packet(timestamp: i64, motion_state: u8) -> RobotDataPacket {
+        let mut data = vec![0u8; 784];
+        data[..4].copy_from_slice(&784u32.to_le_bytes());
+        data[4..12].copy_from_slice(&timestamp.to_le_bytes());
+        data[12] = motion_state;
+        let mut packet = RobotDataPacket::from_bytes(&data).unwrap();
+        packet.actual_joint_positions[0] = 0.5;
+        packet
+    }
+
+    #[test]
+    fn test_telemetry_messages() {
+        let topics = MqttTopics {
+            joint_currents: String::new(),
+            ..Default::default()
+        };
+        let mut telemetry = Telemetry::new(topics, 10.0);
+
+        // 首个数据包: 状态 + 遥测 (电流主题已禁用)
+        let messages = telemetry.messages("a", &packet(1_000_000, 1));
+        let topics: Vec<&str> = messages.iter().map(|m| m.topic.as_str()).collect();
+        assert_eq!(
+            topics,
+            [
+                "xarm/status",
+                "xarm/joint_positions",
+                "xarm/tcp_pose",
+                "xarm/motion_state"
+            ]
+        );
+        assert!(messages[0].retain);
+        let positions: Value = serde_json::from_str(&messages[1].payload).unwrap();
+        assert_eq!(positions["timestamp"], 1.0);
+        assert_eq!(positions["values"][0], 0.5);
+        assert_eq!(positions["robot_id"], "a");
+
+        // 10Hz 抽取, 运动状态未变化
+        assert!(telemetry.messages("a", &packet(1_004_000, 1)).is_empty());
+
+        // 另一台机械臂单独抽取, 首个数据包同样发布状态与遥测
+        let messages = telemetry.messages("b", &packet(1_005_000, 1));
+        assert_eq!(messages.len(), 4);
+        assert!(messages[0].payload.contains("\"robot_id\":\"b\""));
+
+        // 进入停止状态: 立即发布状态与报警
+        let messages = telemetry.messages("a", &packet(1_008_000, MOTION_STATE_STOPPED));
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].topic, "xarm/alarm");
+        assert!(messages[1].payload.contains("motion_stopped"));
+    }
+
+    /// 需要本地 Broker: mosquitto -p 1883, 运行 cargo test -- --ignored
+    #[tokio::test]
+    #[ignore]
+    async fn test_publish_to_local_broker() {
+        let (subscriber, mut eventloop) =
+            AsyncClient::new(MqttOptions::new("ufactory-test-sub", "127.0.0.1", 1883), 10);
+        subscriber
+            .subscribe("xarm/joint_positions", QoS::AtLeastOnce)
+            .await
+            .unwrap();
+
+        let bus = packet_bus();
+        let config = MqttConfig {
+            enabled: true,
+            client_id: "ufactory-test-pub".to_string(),
+            qos: 1,
+            ..Default::default()
+        };
+        let publisher = MqttPublisher::start(&config, bus.clone()).unwrap();
+
+        let received = tokio::time::timeout(Duration::from_secs(10), async {
+            let mut timestamp = 0;
+            loop {
+                if publisher.connected() {
+                    timestamp += 200_000;
+                    let _ = bus.send(Arc::new(BusPacket {
+                        robot_id: "test".to_string(),
+                        packet: packet(timestamp, 1),
+                    }));
+                }
+                let poll = tokio::time::timeout(Duration::from_millis(100), eventloop.poll());
+                if let Ok(Ok(Event::Incoming(Packet::Publish(publish)))) = poll.await {
+                    return publish;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        let payload: Value = serde_json::from_slice(&received.payload).unwrap();
+        assert_eq!(payload["values"][0], 0.5);
+        publisher.stop();
+    }
+}
@@ -0,0 +1,298 @@
+// udp_publisher.rs - 以 PlotJuggler "UDP Server" (JSON) 插件的格式发布数据包
+//
+// 每个数据包发送一个扁平 JSON 对象, 多分量通道按 CSV 表头展开为 {通道}_{序号}:
+//   {"timestamp":12.345,"robot_id":"192.168.1.100","actual_joint_positions_1":0.1,...}
+// timestamp 为控制器时间 (s). PlotJuggler 中 Message Protocol 选择 JSON,
+// 勾选 "use field [timestamp] if available" 即按控制器时间绘图.
+// 各机械臂分别按频率抽取; PlotJuggler 会把各机械臂的数据画在同一组曲线中,
+// 同时连接多台时可通过 robot_id 只发布其中一台
+use crate::commands::{
+    arm_service::robot_data::RobotDataPacket,
+    streaming::{
+        accepts_robot, channel_names, channel_values, validate_channels, BusPacket, Decimator,
+        PacketBus,
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    io::{self},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    net::UdpSocket,
+    sync::{
+        broadcast::{self, error::RecvError},
+        watch,
+    },
+};
+
+/// 旧版本的配置文件名 (位于配置目录), 已迁移到用户设置 streaming.udp
+pub const CONFIG_FILE: &str = "plotjuggler.json";
+
+/// UDP 发布配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UdpPublisherConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16, // PlotJuggler 默认端口 9870
+    #[serde(default)]
+    pub channels: Vec<String>, // 为空时发布全部通道
+    #[serde(default)]
+    pub rate: Option<f64>, // 最大发送频率 Hz, 省略时发布每个数据包
+    #[serde(default)]
+    pub robot_id: Option<String>, // 只发布指定机械臂, 省略时发布全部机械臂
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_port() -> u16 {
+    9870
+}
+
+impl Default for UdpPublisherConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: default_host(),
+            port: default_port(),
+            channels: vec![],
+            rate: None,
+            robot_id: None,
+        }
+    }
+}
+
+impl UdpPublisherConfig {
+    /// 发布的通道, 未选择时为全部通道
+    fn selected_channels(&self) -> Vec<String> {
+        if self.channels.is_empty() {
+            channel_names()
+        } else {
+            self.channels.clone()
+        }
+    }
+}
+
+/// 数据包转为扁平 JSON
+pub fn flat_json(robot_id: &str, packet: &RobotDataPacket, channels: &[String]) -> String {
+    let mut object = Map::new();
+    object.insert(
+        "timestamp".to_string(),
+        Value::from(packet.timestamp as f64 / 1_000_000.0),
+    );
+    object.insert("robot_id".to_string(), Value::from(robot_id));
+
+    for channel in channels {
+        let Some(values) = channel_values(packet, channel) else {
+            continue;
+        };
+        if values.len() == 1 {
+            object.insert(channel.clone(), Value::from(values[0]));
+        } else {
+            for (i, v) in values.iter().enumerate() {
+                object.insert(format!("{}_{}", channel, i + 1), Value::from(*v));
+            }
+        }
+    }
+
+    Value::Object(object).to_string()
+}
+
+/// 运行中的 UDP 发布, 停止或释放时结束发送
+#[derive(Debug)]
+pub struct UdpPublisher {
+    target: String,
+    shutdown: watch::Sender<bool>,
+    sent: Arc<AtomicU64>,
+}
+
+impl UdpPublisher {
+    /// 解析目标地址并在当前 tokio 运行时中开始发布
+    pub async fn start(config: &UdpPublisherConfig, bus: PacketBus) -> io::Result<Self> {
+        validate_channels(&config.channels)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let target = tokio::net::lookup_host((config.host.as_str(), config.port))
+            .await?
+            .next()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Failed to resolve {}", config.host),
+                )
+            })?;
+        let local = if target.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(target).await?;
+
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let sent = Arc::new(AtomicU64::new(0));
+        tokio::spawn(publish_loop(
+            socket,
+            bus.subscribe(),
+            config.robot_id.clone(),
+            config.selected_channels(),
+            config.rate,
+            shutdown_rx,
+            sent.clone(),
+        ));
+        eprintln!("UDP publisher sending to {}", target);
+
+        Ok(Self {
+            target: target.to_string(),
+            shutdown,
+            sent,
+        })
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// 已发送的数据包数
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    pub fn stop(self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+impl Drop for UdpPublisher {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+async fn publish_loop(
+    socket: UdpSocket,
+    mut packets: broadcast::Receiver<Arc<BusPacket>>,
+    robot_id: Option<String>,
+    channels: Vec<String>,
+    rate: Option<f64>,
+    mut shutdown: watch::Receiver<bool>,
+    sent: Arc<AtomicU64>,
+) {
+    let mut decimators: HashMap<String, Decimator> = HashMap::new();
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            packet = packets.recv() => match packet {
+                Ok(packet) => {
+                    if !accepts_robot(&robot_id, &packet) {
+                        continue;
+                    }
+                    let decimator = decimators
+                        .entry(packet.robot_id.clone())
+                        .or_insert_with(|| Decimator::new(rate));
+                    if !decimator.accept(packet.timestamp) {
+                        continue;
+                    }
+                    let json = flat_json(&packet.robot_id, &packet, &channels);
+                    // 目标端口未监听时 (PlotJuggler 未启动) 发送会失败, 忽略即可
+                    if socket.send(json.as_bytes()).await.is_ok() {
+                        sent.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::streaming::packet_bus;
+
+    fn packet(robot_id: &str, timestamp: i64) -> BusPacket {
+        let mut data = vec![0u8; 784];
+        data[..4].copy_from_slice(&784u32.to_le_bytes());
+        data[4..12].copy_from_slice(&timestamp.to_le_bytes());
+        let mut packet = RobotDataPacket::from_bytes(&data).unwrap();
+        packet.actual_joint_positions[1] = 0.25;
+        BusPacket {
+            robot_id: robot_id.to_string(),
+            packet,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_flat_json() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = UdpPublisherConfig {
+            enabled: true,
+            port: receiver.local_addr().unwrap().port(),
+            channels: vec![
+                "actual_joint_positions".to_string(),
+                "motion_state".to_string(),
+            ],
+            rate: Some(50.0),
+            robot_id: Some("a".to_string()),
+            ..Default::default()
+        };
+        let bus = packet_bus();
+        let publisher = UdpPublisher::start(&config, bus.clone()).await.unwrap();
+
+        // 50Hz 抽取: 第二个包 (4ms 后) 被丢弃, 其他机械臂的数据包不发布
+        for timestamp in [2_000_000, 2_004_000, 2_020_000] {
+            bus.send(Arc::new(packet("b", timestamp + 1000))).unwrap();
+            bus.send(Arc::new(packet("a", timestamp))).unwrap();
+        }
+
+        let mut buf = [0u8; 4096];
+        let mut received = vec![];
+        for _ in 0..2 {
+            let len = receiver.recv(&mut buf).await.unwrap();
+            received.push(serde_json::from_slice::<Value>(&buf[..len]).unwrap());
+        }
+        assert_eq!(received[0]["timestamp"], 2.0);
+        assert_eq!(received[0]["robot_id"], "a");
+        assert_eq!(received[0]["actual_joint_positions_2"], 0.25);
+        assert_eq!(received[0]["motion_state"], 0.0);
+        assert!(received[0].get("actual_joint_positions").is_none());
+        assert_eq!(received[1]["timestamp"], 2.02);
+
+        publisher.stop();
+
+        // 未指定机械臂时各机械臂分别抽取, 不会因另一台的数据包而被丢弃
+        let config = UdpPublisherConfig {
+            robot_id: None,
+            ..config
+        };
+        let bus = packet_bus();
+        let publisher = UdpPublisher::start(&config, bus.clone()).await.unwrap();
+        bus.send(Arc::new(packet("a", 3_000_000))).unwrap();
+        bus.send(Arc::new(packet("b", 3_001_000))).unwrap();
+        let mut robots = vec![];
+        for _ in 0..2 {
+            let len = receiver.recv(&mut buf).await.unwrap();
+            let json = serde_json::from_slice::<Value>(&buf[..len]).unwrap();
+            robots.push(json["robot_id"].as_str().unwrap().to_string());
+        }
+        assert_eq!(robots, ["a", "b"]);
+        publisher.stop();
+
+        let bad = UdpPublisherConfig {
+            channels: vec!["unknown".to_string()],
+            ..Default::default()
+        };
+        assert!(UdpPublisher::start(&bad, bus).await.is_err());
+    }
+}
@@ -0,0 +1,324 @@
+// ws_server.rs - 本地 WebSocket 服务, 将解码后的数据包以 JSON 转发给第三方工具
+//
+// 协议 (文本消息, JSON):
+//   连接后服务端发送  {"type":"hello","channels":[...]}
+//   订阅             {"op":"subscribe","channels":["actual_joint_positions"],"rate":50,"robot_id":"..."}
+//                    rate 为最大发送频率 Hz (按控制器时间戳抽取, 每台机械臂单独计算), 省略时转发每个数据包
+//                    robot_id 只订阅指定机械臂, 省略时订阅全部机械臂
+//   取消订阅         {"op":"unsubscribe"}
+//   数据             {"type":"data","robot_id":"...","timestamp":<控制器时间戳 μs>,"channels":{"actual_joint_positions":[...]}}
+//   应答/错误        {"type":"ack","op":"subscribe"} / {"type":"error","message":"..."}
+use crate::commands::streaming::{
+    accepts_robot, channel_names, channel_values, validate_channels, BusPacket, Decimator,
+    PacketBus, StreamingServerConfig,
+};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self},
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{broadcast::error::RecvError, watch},
+    task::JoinHandle,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum ClientRequest {
+    Subscribe {
+        channels: Vec<String>,
+        #[serde(default)]
+        rate: Option<f64>,
+        #[serde(default)]
+        robot_id: Option<String>,
+    },
+    Unsubscribe,
+}
+
+#[derive(Serialize)]
+struct DataMessage<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    robot_id: &'a str,
+    timestamp: i64,
+    channels: BTreeMap<&'a str, Vec<f32>>,
+}
+
+/// 客户端订阅: 通道, 发送频率与机械臂
+#[derive(Debug)]
+struct Subscription {
+    channels: Vec<String>,
+    rate: Option<f64>,
+    robot_id: Option<String>,
+    decimators: HashMap<String, Decimator>,
+}
+
+impl Subscription {
+    fn new(
+        channels: Vec<String>,
+        rate: Option<f64>,
+        robot_id: Option<String>,
+    ) -> Result<Self, String> {
+        validate_channels(&channels)?;
+        if channels.is_empty() {
+            return Err("No channels to subscribe".to_string());
+        }
+
+        Ok(Self {
+            channels,
+            rate,
+            robot_id,
+            decimators: HashMap::new(),
+        })
+    }
+
+    /// 按发送频率抽取, 需要发送时返回数据消息
+    fn message(&mut self, packet: &BusPacket) -> Option<String> {
+        if !accepts_robot(&self.robot_id, packet) {
+            return None;
+        }
+        let rate = self.rate;
+        let decimator = self
+            .decimators
+            .entry(packet.robot_id.clone())
+            .or_insert_with(|| Decimator::new(rate));
+        if !decimator.accept(packet.timestamp) {
+            return None;
+        }
+
+        let message = DataMessage {
+            kind: "data",
+            robot_id: &packet.robot_id,
+            timestamp: packet.timestamp,
+            channels: self
+                .channels
+                .iter()
+                .filter_map(|c| Some((c.as_str(), channel_values(packet, c)?)))
+                .collect(),
+        };
+        serde_json::to_string(&message).ok()
+    }
+}
+
+/// 运行中的 WebSocket 服务, 停止或释放时关闭所有连接
+#[derive(Debug)]
+pub struct WsServer {
+    addr: SocketAddr,
+    shutdown: watch::Sender<bool>,
+    clients: Arc<AtomicUsize>,
+    accept: Option<JoinHandle<()>>,
+}
+
+impl WsServer {
+    /// 绑定地址并在当前 tokio 运行时中开始接受连接
+    pub async fn start(config: &StreamingServerConfig, bus: PacketBus) -> io::Result<Self> {
+        let listener = TcpListener::bind((config.host.as_str(), config.port)).await?;
+        let addr = listener.local_addr()?;
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let clients = Arc::new(AtomicUsize::new(0));
+
+        let accept = tokio::spawn(accept_loop(listener, bus, shutdown_rx, clients.clone()));
+        eprintln!("Streaming server listening on ws://{}", addr);
+
+        Ok(Self {
+            addr,
+            shutdown,
+            clients,
+            accept: Some(accept),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 当前连接数
+    pub fn clients(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+
+    pub fn stop(self) {
+        let _ = self.shutdown.send(true);
+    }
+
+    /// 停止并等待接受连接的任务结束, 返回后监听端口已释放
+    pub async fn close(mut self) {
+        let _ = self.shutdown.send(true);
+        if let Some(accept) = self.accept.take() {
+            let _ = accept.await;
+        }
+    }
+}
+
+impl Drop for WsServer {
+    fn drop(&mut self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    bus: PacketBus,
+    mut shutdown: watch::Receiver<bool>,
+    clients: Arc<AtomicUsize>,
+) {
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, peer)) => {
+                    let bus = bus.clone();
+                    let shutdown = shutdown.clone();
+                    let clients = clients.clone();
+                    tokio::spawn(async move {
+                        clients.fetch_add(1, Ordering::Relaxed);
+                        if let Err(e) = handle_client(stream, bus, shutdown).await {
+                            eprintln!("Streaming client {} error: {:?}", peer, e);
+                        }
+                        clients.fetch_sub(1, Ordering::Relaxed);
+                    });
+                }
+                Err(e) => eprintln!("Failed to accept streaming client: {:?}", e),
+            },
+        }
+    }
+}
+
+async fn handle_client(
+    stream: TcpStream,
+    bus: PacketBus,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    let ws = tokio_tungstenite::accept_async(stream).await?;
+    let (mut sink, mut source) = ws.split();
+    let mut packets = bus.subscribe();
+    let mut subscription: Option<Subscription> = None;
+
+    let hello = json!({ "type": "hello", "channels": channel_names() });
+    sink.send(Message::Text(hello.to_string())).await?;
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            message = source.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e),
+                };
+
+                let reply = match serde_json::from_str::<ClientRequest>(&text) {
+                    Ok(ClientRequest::Subscribe { channels, rate, robot_id }) => {
+                        match Subscription::new(channels, rate, robot_id) {
+                            Ok(s) => {
+                                subscription = Some(s);
+                                json!({ "type": "ack", "op": "subscribe" })
+                            }
+                            Err(e) => json!({ "type": "error", "message": e }),
+                        }
+                    }
+                    Ok(ClientRequest::Unsubscribe) => {
+                        subscription = None;
+                        json!({ "type": "ack", "op": "unsubscribe" })
+                    }
+                    Err(e) => json!({ "type": "error", "message": format!("Invalid request: {}", e) }),
+                };
+                sink.send(Message::Text(reply.to_string())).await?;
+            }
+            packet = packets.recv() => match packet {
+                Ok(packet) => {
+                    if let Some(message) = subscription.as_mut().and_then(|s| s.message(&packet)) {
+                        sink.send(Message::Text(message)).await?;
+                    }
+                }
+                // 客户端处理过慢, 跳过积压的数据包
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+
+    let _ = sink.send(Message::Close(None)).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{arm_service::robot_data::RobotDataPacket, streaming::packet_bus};
+
+    fn packet(robot_id: &str, timestamp: i64) -> BusPacket {
+        let mut data = vec![0u8; 784];
+        data[..4].copy_from_slice(&784u32.to_le_bytes());
+        data[4..12].copy_from_slice(&timestamp.to_le_bytes());
+        let mut packet = RobotDataPacket::from_bytes(&data).unwrap();
+        packet.actual_joint_positions[0] = 0.5;
+        BusPacket {
+            robot_id: robot_id.to_string(),
+            packet,
+        }
+    }
+
+    #[test]
+    fn test_subscription_rate() {
+        let channels = vec!["actual_joint_positions".to_string()];
+        let mut subscription = Subscription::new(channels.clone(), Some(50.0), None).unwrap();
+
+        // 250Hz 数据按 50Hz 发送: 每台机械臂每 5 个包发送 1 个
+        let sent = (0..25)
+            .flat_map(|i| [packet("a", i * 4000), packet("b", i * 4000)])
+            .filter(|p| subscription.message(p).is_some())
+            .count();
+        assert_eq!(sent, 10);
+
+        // 只订阅指定机械臂
+        let mut subscription = Subscription::new(channels, None, Some("b".to_string())).unwrap();
+        assert!(subscription.message(&packet("a", 0)).is_none());
+        assert!(subscription
+            .message(&packet("b", 0))
+            .unwrap()
+            .contains(r#""robot_id":"b""#));
+
+        assert!(Subscription::new(vec!["unknown".to_string()], None, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stream_to_client() {
+        let bus = packet_bus();
+        let config = StreamingServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+        };
+        let server = WsServer::start(&config, bus.clone()).await.unwrap();
+
+        let url = format!("ws://{}", server.addr());
+        let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let hello = ws.next().await.unwrap().unwrap().into_text().unwrap();
+        assert!(hello.contains("actual_joint_positions"));
+
+        let request = r#"{"op":"subscribe","channels":["actual_joint_positions","motion_state"]}"#;
+        ws.send(Message::Text(request.to_string())).await.unwrap();
+        let ack = ws.next().await.unwrap().unwrap().into_text().unwrap();
+        assert!(ack.contains("ack"));
+
+        bus.send(Arc::new(packet("a", 1_000_000))).unwrap();
+        let data = ws.next().await.unwrap().unwrap().into_text().unwrap();
+        let data: serde_json::Value = serde_json::from_str(&data).unwrap();
+        assert_eq!(data["robot_id"], "a");
+        assert_eq!(data["timestamp"], 1_000_000);
+        assert_eq!(data["channels"]["actual_joint_positions"][0], 0.5);
+        assert_eq!(data["channels"]["motion_state"][0], 0.0);
+
+        server.stop();
+    }
+}
@@ -1,4 +1,7 @@
-use crate::utils::response::Response;
+use crate::{
+    state::{app_state::AppState, user_settings::UpdateChannel},
+    utils::{error::AppError, response::Response},
+};
 use log::{error, info};
 use serde::Serialize;
 use serde_json::json;
@@ -27,6 +30,7 @@ pub async fn set_beta_updater<R: tauri::Runtime>(
     webview: Webview<R>,
 ) -> Response<serde_json::Value> {
     info!("开始检查测试版更新");
+    save_update_channel(&app, UpdateChannel::Beta);
 
     let update_urls = vec!["http://192.168.1.19/releases/xarm/assistant/releases_beta.json"];
 
@@ -39,6 +43,7 @@ pub async fn set_stable_updater<R: tauri::Runtime>(
     webview: Webview<R>,
 ) -> Response<serde_json::Value> {
     info!("开始检查生产版更新");
+    save_update_channel(&app, UpdateChannel::Stable);
 
     let update_urls = vec![
         "http://192.168.1.19/releases/xarm/assistant/latest.json",
@@ -59,6 +64,15 @@ pub async fn is_appimage() -> bool {
     true
 }
 
+/// 记录用户选择的更新通道
+fn save_update_channel<R: tauri::Runtime>(app: &tauri::AppHandle<R>, channel: UpdateChannel) {
+    if let Some(state) = app.try_state::<AppState>() {
+        if let Err(e) = state.update_settings(|s| s.update_channel = channel) {
+            error!("保存更新通道失败: {}", e);
+        }
+    }
+}
+
 async fn set_updater_urls<R: tauri::Runtime>(
     app: tauri::AppHandle<R>,
     webview: Webview<R>,
@@ -68,58 +82,58 @@ async fn set_updater_urls<R: tauri::Runtime>(
     for url in update_urls {
         match tauri::Url::parse(url) {
             Ok(parsed) => endpoints.push(parsed),
-            Err(e) => return Response::error(format!("解析更新URL失败: {}", e)),
+            Err(e) => return AppError::UpdaterUrl(e.to_string()).into(),
         }
     }
 
-    if let Ok(ub) = app.updater_builder().endpoints(endpoints) {
-        info!("更新器初始化成功");
-        // 构建更新检查器
-        let updater = match ub.build() {
-            Ok(u) => u,
-            Err(e) => return Response::error(format!("无法初始化更新器: {}", e)),
-        };
-
-        // 检查更新
-        match updater.check().await {
-            Ok(update) => {
-                let mut metadata = Metadata::default();
-                if let Some(update_data) = update {
-                    info!("发现新版本: {}", update_data.version);
-                    metadata.available = true;
-                    metadata
-                        .current_version
-                        .clone_from(&update_data.current_version);
-                    metadata.version.clone_from(&update_data.version);
-                    metadata.date = update_data.date.map(|d| d.to_string());
-                    metadata.body.clone_from(&update_data.body);
-                    metadata.rid = Some(webview.resources_table().add(update_data));
-
-                    // let update_info = serde_json::json!({
-                    //     "version": update_data.version,
-                    //     "current_version": update_data.current_version,
-                    //     "body": update_data.body,
-                    //     "date": update_data.date,
-                    //     "rid": rid,
-                    // });
-                    Response::success(serde_json::to_value(metadata).unwrap_or_else(|e| {
-                        error!("序列化 Metadata 失败: {}", e);
-                        json!({})
-                    }))
-                    // Response::success(metadata)
-                } else {
-                    info!("当前已是最新版本");
-                    Response::success(serde_json::json!({
-                        "is_latest": true
-                    }))
-                }
-            }
-            Err(e) => {
-                error!("检查更新失败: {}", e);
-                Response::error(format!("检查更新失败: {}", e))
+    let ub = match app.updater_builder().endpoints(endpoints) {
+        Ok(ub) => ub,
+        Err(e) => return AppError::UpdaterInit(e.to_string()).into(),
+    };
+    info!("更新器初始化成功");
+    // 构建更新检查器
+    let updater = match ub.build() {
+        Ok(u) => u,
+        Err(e) => return AppError::UpdaterInit(e.to_string()).into(),
+    };
+
+    // 检查更新
+    match updater.check().await {
+        Ok(update) => {
+            let mut metadata = Metadata::default();
+            if let Some(update_data) = update {
+                info!("发现新版本: {}", update_data.version);
+                metadata.available = true;
+                metadata
+                    .current_version
+                    .clone_from(&update_data.current_version);
+                metadata.version.clone_from(&update_data.version);
+                metadata.date = update_data.date.map(|d| d.to_string());
+                metadata.body.clone_from(&update_data.body);
+                metadata.rid = Some(webview.resources_table().add(update_data));
+
+                // let update_info = serde_json::json!({
+                //     "version": update_data.version,
+                //     "current_version": update_data.current_version,
+                //     "body": update_data.body,
+                //     "date": update_data.date,
+                //     "rid": rid,
+                // });
+                Response::success(serde_json::to_value(metadata).unwrap_or_else(|e| {
+                    error!("序列化 Metadata 失败: {}", e);
+                    json!({})
+                }))
+                // Response::success(metadata)
+            } else {
+                info!("当前已是最新版本");
+                Response::success(serde_json::json!({
+                    "is_latest": true
+                }))
             }
         }
-    } else {
-        Response::error("无法初始化更新器".to_string())
+        Err(e) => {
+            error!("检查更新失败: {}", e);
+            AppError::UpdateCheck(e.to_string()).into()
+        }
     }
 }
@@ -0,0 +1,178 @@
+// headless.rs - 无界面录制, 用于产线验收等自动化场景
+//
+// 用法:
+//   UFACTORY_Assistant --headless --ip 192.168.1.100 --duration 30 --out run.raw [--golden pick_place.json] [--lang en]
+//
+// 退出码: 0 通过 (或未指定标准录制) / 1 未通过 / 2 出错
+use crate::{
+    commands::{
+        analysis::golden::{GoldenRun, EXIT_ERROR, EXIT_PASS},
+        arm_service::{
+            robot_client::{ClientConfig, RobotClient},
+            structs::ObserveParams,
+            ROBOT_PORT,
+        },
+    },
+    utils::i18n::{self, Language},
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc, RwLock},
+    time::Duration,
+};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug)]
+struct HeadlessArgs {
+    ip: String,
+    duration: f64,           // 录制时长 s
+    out: PathBuf,            // 原始数据输出路径
+    golden: Option<PathBuf>, // 标准录制配置 (.json)
+    language: Language,      // 检查报告的语言 (zh-CN / en)
+}
+
+impl HeadlessArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let value = |name: &str| {
+            args.iter()
+                .position(|a| a == name)
+                .and_then(|i| args.get(i + 1))
+                .cloned()
+        };
+
+        let ip = value("--ip").ok_or_else(|| "Missing --ip".to_string())?;
+        let duration = value("--duration")
+            .ok_or_else(|| "Missing --duration".to_string())?
+            .parse::<f64>()
+            .map_err(|e| format!("Invalid --duration: {}", e))?;
+        if !duration.is_finite() || duration <= 0.0 {
+            return Err(format!(
+                "Invalid --duration: {}, expected a positive number of seconds",
+                duration
+            ));
+        }
+        let out = value("--out").ok_or_else(|| "Missing --out".to_string())?;
+        let language = match value("--lang") {
+            Some(lang) => serde_json::from_value(serde_json::Value::String(lang))
+                .map_err(|_| "Invalid --lang, expected zh-CN or en".to_string())?,
+            None => Language::default(),
+        };
+
+        Ok(Self {
+            ip,
+            duration,
+            // 无界面模式只录制原始数据
+            out: PathBuf::from(out).with_extension("raw"),
+            golden: value("--golden").map(PathBuf::from),
+            language,
+        })
+    }
+}
+
+/// 命令行包含 --headless 时执行无界面录制并返回退出码, 否则返回 None 启动界面
+pub fn run_from_args() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.iter().any(|a| a == "--headless") {
+        return None;
+    }
+
+    let result = HeadlessArgs::parse(&args).and_then(|args| run(&args));
+    Some(match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_ERROR
+        }
+    })
+}
+
+fn run(args: &HeadlessArgs) -> Result<i32, String> {
+    i18n::set_language(args.language);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to create runtime: {:?}", e))?;
+    let robot_ip = format!("{}:{}", args.ip, ROBOT_PORT);
+    let mut client = runtime
+        .block_on(RobotClient::connect(&robot_ip, ClientConfig::default()))
+        .map_err(|e| format!("Failed to create RobotClient: {:?}", e))?;
+
+    let file = File::create(&args.out)
+        .map_err(|e| format!("Failed to create {}: {:?}", args.out.display(), e))?;
+    let mut writer = BufWriter::new(file);
+
+    let cancel = CancellationToken::new();
+    // 只录制原始数据, 不需要观测 (图表数据)
+    let observer_running = Arc::new(AtomicBool::new(false));
+    let observe_params = Arc::new(RwLock::new(ObserveParams::default()));
+
+    let timer = cancel.clone();
+    let duration = Duration::from_secs_f64(args.duration);
+    runtime.spawn(async move {
+        tokio::time::sleep(duration).await;
+        timer.cancel();
+    });
+
+    println!(
+        "{}",
+        i18n::t_args(
+            "headless.recording",
+            &[
+                ("duration", args.duration.to_string()),
+                ("path", args.out.display().to_string()),
+            ],
+        )
+    );
+    runtime
+        .block_on(client.collect_data(
+            cancel,
+            observer_running,
+            observe_params,
+            |packet| writer.write_all(&packet.to_bytes()),
+            |_| Ok(()),
+        ))
+        .map_err(|e| format!("Data collection failed: {:?}", e))?;
+    let _ = runtime.block_on(client.disconnect());
+
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write {}: {:?}", args.out.display(), e))?;
+
+    let Some(golden) = &args.golden else {
+        return Ok(EXIT_PASS);
+    };
+
+    let report = GoldenRun::check_file(golden, &args.out)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).unwrap_or_default()
+    );
+
+    Ok(report.exit_code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<HeadlessArgs, String> {
+        let args: Vec<String> = args.split_whitespace().map(String::from).collect();
+        HeadlessArgs::parse(&args)
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let args =
+            parse("--headless --ip 127.0.0.1 --duration 1.5 --out run.csv --lang en").unwrap();
+        assert_eq!(args.duration, 1.5);
+        assert_eq!(args.out, PathBuf::from("run.raw"));
+        assert_eq!(args.language, Language::En);
+
+        for duration in ["0", "-3", "NaN", "inf", "abc"] {
+            let args = format!("--ip 127.0.0.1 --out run.raw --duration {}", duration);
+            assert!(parse(&args).is_err(), "{}", duration);
+        }
+    }
+}
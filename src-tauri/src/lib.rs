@@ -7,6 +7,7 @@ use crate::state::app_state::GLOBAL_APP_HANDLE;
 
 mod commands;
 mod desktops;
+pub mod headless;
 mod state;
 mod utils;
 
@@ -35,9 +36,66 @@ pub fn run() {
             commands::arm_service::disconnect_robot_server,
             commands::arm_service::start_assistant,
             commands::arm_service::stop_assistant,
+            commands::arm_service::get_connection_state,
             commands::arm_service::get_robot_axis,
+            commands::arm_service::get_device_status,
+            commands::arm_service::get_error_history,
+            commands::arm_service::clear_error_history,
+            commands::arm_service::list_error_codes,
             commands::arm_service::save_csv,
+            commands::arm_service::list_robots,
+            commands::arm_service::discover_robots,
+            commands::analysis::analyze_recording_cycles,
+            commands::analysis::start_cycle_tracking,
+            commands::analysis::stop_cycle_tracking,
+            commands::analysis::get_cycle_report,
+            commands::analysis::compare_recordings,
+            commands::analysis::save_golden_run,
+            commands::analysis::list_golden_runs,
+            commands::analysis::delete_golden_run,
+            commands::analysis::evaluate_golden_run,
+            commands::analysis::import_csv,
+            commands::analysis::list_imported_recordings,
+            commands::analysis::remove_imported_recording,
+            commands::analysis::get_imported_series,
+            commands::analysis::get_imported_frames,
+            commands::analysis::get_imported_statistics,
+            commands::sessions::list_sessions,
+            commands::sessions::get_session,
+            commands::sessions::rename_session,
+            commands::sessions::update_session,
+            commands::sessions::delete_session,
+            commands::sessions::export_session,
+            commands::sessions::list_orphaned_recordings,
+            commands::sessions::recover_orphaned_recording,
+            commands::sessions::discard_orphaned_recording,
+            commands::streaming::start_streaming_server,
+            commands::streaming::stop_streaming_server,
+            commands::streaming::get_streaming_server_status,
+            commands::streaming::get_udp_publisher_config,
+            commands::streaming::set_udp_publisher_config,
+            commands::streaming::get_udp_publisher_status,
+            commands::streaming::get_mqtt_config,
+            commands::streaming::set_mqtt_config,
+            commands::streaming::get_mqtt_status,
+            commands::streaming::get_metrics_config,
+            commands::streaming::set_metrics_config,
+            commands::streaming::get_metrics_status,
+            commands::rest_api::get_rest_api_config,
+            commands::rest_api::set_rest_api_config,
+            commands::rest_api::get_rest_api_status,
             commands::get_shared_state,
+            commands::settings::get_user_settings,
+            commands::settings::set_user_settings,
+            commands::settings::set_robot_nickname,
+            commands::settings::remove_recent_robot,
+            commands::presets::list_presets,
+            commands::presets::get_preset,
+            commands::presets::save_preset,
+            commands::presets::delete_preset,
+            commands::presets::import_preset,
+            commands::presets::export_preset,
+            commands::presets::start_assistant_with_preset,
             commands::debug::get_user_data_paths,
             greet
         ])
@@ -70,6 +128,8 @@ pub fn run() {
             handle.plugin(log_plugin)?;
 
             app.manage(app_state);
+            commands::streaming::restore_publishers(handle.clone());
+            commands::rest_api::restore_rest_api(handle);
             desktops::window::setup_desktop_window(handle)?;
 
             Ok(())
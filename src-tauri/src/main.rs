@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    if let Some(code) = xarm_assistants_lib::headless::run_from_args() {
+        std::process::exit(code);
+    }
+
     xarm_assistants_lib::run()
 }
@@ -1,14 +1,38 @@
 use crate::{
-    commands::arm_service::{csv_exporter::CsvExporter, robot_client::RobotClient, structs},
-    utils::user_data::UserDataPaths,
+    commands::{
+        analysis::{cycles::CycleDetector, imported::ImportedRecording},
+        arm_service::{
+            csv_exporter::CsvExporter,
+            device_status::DeviceStatusMonitor,
+            error_history::ErrorHistory,
+            lifecycle::{ConnectionActor, ConnectionState, LifecycleConfig},
+            session_backend::SessionBackend,
+            structs,
+        },
+        presets::runtime::PresetRuntime,
+        rest_api::RestApiServer,
+        sessions::{
+            library::SessionLibrary,
+            recovery::{self, OrphanedRecording, TEMP_RETENTION_DAYS},
+        },
+        streaming::{
+            self, metrics::MetricsServer, mqtt_publisher::MqttPublisher,
+            udp_publisher::UdpPublisher, update_connection_metric, ws_server::WsServer, PacketBus,
+        },
+    },
+    state::user_settings::{Language, UserSettings},
+    utils::{error::AppError, i18n, user_data::UserDataPaths},
 };
-use tauri::{AppHandle, Emitter}; // ← 这个是关键
+use tauri::{AppHandle, Emitter, Manager}; // ← 这个是关键
 
 use once_cell::sync::OnceCell;
 
 use std::{
-    sync::{atomic::AtomicBool, Arc, Mutex, RwLock},
-    thread,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock, Weak,
+    },
 };
 
 use reqwest::Client;
@@ -16,19 +40,25 @@ use serde::{Deserialize, Serialize};
 
 pub static GLOBAL_APP_HANDLE: OnceCell<std::sync::Arc<AppHandle<tauri::Wry>>> = OnceCell::new();
 
+/// 机械臂的采集数据与状态; 采集客户端由连接状态机 (RobotSession::lifecycle) 持有
 #[derive(Debug)]
 pub struct RobotServer {
     pub ip: String,
-    pub socket: Option<Arc<Mutex<RobotClient>>>,
-    pub handle: Option<thread::JoinHandle<Result<(), std::io::Error>>>,
     // csv导出
     pub csv_exporter: Arc<RwLock<Option<CsvExporter>>>,
+    // 实时周期检测
+    pub cycle_detector: Arc<RwLock<Option<CycleDetector>>>,
+    // 当前录制使用的观测预设 (显示滤波与报警)
+    pub preset: Arc<RwLock<Option<PresetRuntime>>>,
+    // 设备状态订阅 (18333 WebSocket), 连接期间运行
+    pub status_monitor: Option<DeviceStatusMonitor>,
+    // 本次连接的错误/警告历史
+    pub error_history: Arc<RwLock<ErrorHistory>>,
     // 运行状态
     pub observer_running: Arc<AtomicBool>,
-    // 连接状态
+    // 连接状态 (由状态机同步, 只读)
     pub connected: bool,
     pub observe_params: Arc<RwLock<structs::ObserveParams>>,
-    pub stop_flag: Arc<AtomicBool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -50,66 +80,301 @@ impl Default for SharedState {
     }
 }
 
+/// 机械臂事件载荷: 在原有字段中加入 robot_id
+#[derive(Debug, Clone, Serialize)]
+pub struct RobotEvent<'a, T> {
+    pub robot_id: &'a str,
+    #[serde(flatten)]
+    pub payload: T,
+}
+
+/// 单台机械臂的会话: 连接, 录制与共享状态, 以机械臂 ID (默认为 IP) 区分
 #[derive(Debug)]
-pub struct AppState {
-    // pub user_settings: Mutex<UserSettings>,
-    pub ws_ip: Arc<RwLock<String>>,
-    pub app: AppHandle,
-    pub client: Mutex<Client>,
+pub struct RobotSession {
+    pub id: String,
     pub robot_server: Arc<RwLock<RobotServer>>,
     pub shared_state: Arc<RwLock<SharedState>>,
-    pub user_data_paths: UserDataPaths,
+    // 连接状态机: 连接, 断开, 观测与重连都经由它按顺序执行
+    pub lifecycle: ConnectionActor,
+    app: AppHandle,
 }
 
-impl AppState {
-    pub fn new(app: AppHandle) -> Result<Self, String> {
-        // 初始化用户数据目录
-        let user_data_paths = UserDataPaths::new(&app)?;
-
-        Ok(Self {
-            ws_ip: Arc::new(RwLock::new("".to_string())),
-            client: Mutex::new(Client::new()),
-            robot_server: Arc::new(RwLock::new(RobotServer {
+impl RobotSession {
+    pub fn new(id: &str, app: AppHandle) -> Arc<Self> {
+        Arc::new_cyclic(|session: &Weak<RobotSession>| {
+            let robot_server = Arc::new(RwLock::new(RobotServer {
                 ip: "".to_string(),
-                socket: None,
-                handle: None,
                 observer_running: Arc::new(AtomicBool::new(false)),
                 connected: false,
                 observe_params: Arc::new(RwLock::new(structs::ObserveParams::default())),
-                stop_flag: Arc::new(AtomicBool::new(false)),
                 csv_exporter: Arc::new(RwLock::new(None)),
-            })),
-            shared_state: Arc::new(RwLock::new(SharedState::default())),
-            user_data_paths,
-            app,
+                cycle_detector: Arc::new(RwLock::new(None)),
+                preset: Arc::new(RwLock::new(None)),
+                status_monitor: None,
+                error_history: Arc::new(RwLock::new(ErrorHistory::default())),
+            }));
+
+            let backend =
+                SessionBackend::new(app.clone(), id, robot_server.clone(), session.clone());
+            let weak = session.clone();
+            let lifecycle =
+                ConnectionActor::spawn(backend, LifecycleConfig::default(), move |state| {
+                    if let Some(robot) = weak.upgrade() {
+                        robot.on_transition(state);
+                    }
+                });
+
+            Self {
+                id: id.to_string(),
+                robot_server,
+                shared_state: Arc::new(RwLock::new(SharedState::default())),
+                lifecycle,
+                app,
+            }
         })
     }
 
-    /// 推送共享状态到前端
-    pub fn push_shared_state(&self) -> Result<SharedState, String> {
+    /// 连接状态变化: 同步连接/观测标志与共享状态, 推送 ROBOT_CONNECTION_STATE 事件
+    fn on_transition(&self, state: &ConnectionState) {
+        if let Ok(mut robot_lock) = self.robot_server.write() {
+            robot_lock.connected = state.is_connected();
+            if *state != ConnectionState::Observing {
+                robot_lock.observer_running.store(false, Ordering::Relaxed);
+            }
+        }
+
+        let shared_state = match state {
+            ConnectionState::Disconnected => SharedState::default(),
+            _ => {
+                let mut shared_state = self
+                    .shared_state
+                    .read()
+                    .map(|s| s.clone())
+                    .unwrap_or_default();
+                shared_state.arm_conn = state.is_connected();
+                shared_state.observering = *state == ConnectionState::Observing;
+                shared_state
+            }
+        };
+        let _ = self.set_shared_state(shared_state);
+        let _ = self.push_shared_state();
+
+        let event = RobotEvent {
+            robot_id: &self.id,
+            payload: state,
+        };
+        let _ = self.app.emit("ROBOT_CONNECTION_STATE", &event);
+        update_connection_metric(&self.app.state::<AppState>());
+    }
+
+    /// 推送共享状态到前端 (APP_SHARED_STATE 事件带 robot_id)
+    pub fn push_shared_state(&self) -> Result<SharedState, AppError> {
         let shared_state = self
             .shared_state
             .try_read()
-            .map_err(|_| "Failed to acquire read lock on shared_state".to_string())?
+            .map_err(|_| AppError::LockPoisoned("shared_state"))?
             .clone();
 
-        self.app
-            .emit("APP_SHARED_STATE", &shared_state)
-            .map_err(|op| format!("Failed to emit APP_SHARED_STATE event: {:?}", op))?;
+        let event = RobotEvent {
+            robot_id: &self.id,
+            payload: &shared_state,
+        };
+        self.app.emit("APP_SHARED_STATE", &event).map_err(|op| {
+            AppError::Internal(format!("Failed to emit APP_SHARED_STATE event: {:?}", op))
+        })?;
 
         Ok(shared_state)
     }
 
     /// 设置共享状态
-    pub fn set_shared_state(&self, state: SharedState) -> Result<(), String> {
+    pub fn set_shared_state(&self, state: SharedState) -> Result<(), AppError> {
         let mut shared_state = self
             .shared_state
             .write()
-            .map_err(|_| format!("Failed to acquire write lock on shared_state"))?;
+            .map_err(|_| AppError::LockPoisoned("shared_state"))?;
 
         *shared_state = state;
         Ok(())
     }
+
+    /// 是否已连接
+    pub fn connected(&self) -> bool {
+        self.lifecycle.state().is_connected()
+    }
+}
+
+#[derive(Debug)]
+pub struct AppState {
+    pub user_settings: Mutex<UserSettings>,
+    pub app: AppHandle,
+    pub client: Mutex<Client>,
+    // 已连接的机械臂, 按机械臂 ID 索引
+    pub robots: RwLock<HashMap<String, Arc<RobotSession>>>,
+    pub session_library: Arc<Mutex<SessionLibrary>>,
+    // 启动时发现的遗留临时录制, 等待用户恢复或丢弃
+    pub orphaned_recordings: Mutex<Vec<OrphanedRecording>>,
+    // 导入的外部 CSV 录制 (仅内存)
+    pub imported_recordings: Mutex<Vec<ImportedRecording>>,
+    // 解码后的数据包总线, 供流式输出订阅
+    pub packet_bus: PacketBus,
+    // 本地 WebSocket 流式服务
+    pub streaming_server: Mutex<Option<WsServer>>,
+    // PlotJuggler UDP 发布
+    pub udp_publisher: Mutex<Option<UdpPublisher>>,
+    // MQTT 遥测发布
+    pub mqtt_publisher: Mutex<Option<MqttPublisher>>,
+    // Prometheus 指标端点
+    pub metrics_server: Mutex<Option<MetricsServer>>,
+    // 本地 REST 控制接口
+    pub rest_api: Mutex<Option<RestApiServer>>,
+    pub user_data_paths: UserDataPaths,
+}
+
+impl AppState {
+    pub fn new(app: AppHandle) -> Result<Self, String> {
+        // 初始化用户数据目录
+        let user_data_paths = UserDataPaths::new(&app)?;
+
+        // 会话库位于 CSV 数据目录
+        let session_library = SessionLibrary::open(user_data_paths.csv_data.clone())
+            .map_err(|e| format!("Failed to open session library: {:?}", e))?;
+
+        // 超过保留期限的临时文件直接清理, 其余作为遗留录制等待恢复
+        user_data_paths.cleanup_temp_files(TEMP_RETENTION_DAYS)?;
+        // 扫描失败不影响启动, 临时文件保留到下次启动
+        let orphaned_recordings =
+            recovery::scan_orphans(&user_data_paths.csv_temp, &session_library).unwrap_or_else(
+                |e| {
+                    eprintln!("Failed to scan orphaned recordings: {:?}", e);
+                    vec![]
+                },
+            );
+
+        // 用户设置 (需要时从旧版本迁移)
+        let user_settings = UserSettings::load(&user_data_paths.config);
+        i18n::set_language(user_settings.language);
+
+        Ok(Self {
+            user_settings: Mutex::new(user_settings),
+            client: Mutex::new(Client::new()),
+            robots: RwLock::new(HashMap::new()),
+            session_library: Arc::new(Mutex::new(session_library)),
+            orphaned_recordings: Mutex::new(orphaned_recordings),
+            imported_recordings: Mutex::new(vec![]),
+            packet_bus: streaming::packet_bus(),
+            streaming_server: Mutex::new(None),
+            udp_publisher: Mutex::new(None),
+            mqtt_publisher: Mutex::new(None),
+            metrics_server: Mutex::new(None),
+            rest_api: Mutex::new(None),
+            user_data_paths,
+            app,
+        })
+    }
+
+    /// 修改并保存用户设置, 推送 USER_SETTINGS_CHANGED 事件
+    pub fn update_settings<F>(&self, update: F) -> Result<UserSettings, AppError>
+    where
+        F: FnOnce(&mut UserSettings),
+    {
+        let settings = {
+            let mut settings = self
+                .user_settings
+                .lock()
+                .map_err(|_| AppError::LockPoisoned("user_settings"))?;
+            update(&mut settings);
+            settings
+                .save(&self.user_data_paths.config)
+                .map_err(|e| AppError::SettingsSave(format!("{:?}", e)))?;
+            settings.clone()
+        };
+        i18n::set_language(settings.language);
+
+        let _ = self.app.emit("USER_SETTINGS_CHANGED", &settings);
+        Ok(settings)
+    }
+
+    /// 当前用户设置
+    pub fn settings(&self) -> Result<UserSettings, AppError> {
+        self.user_settings
+            .lock()
+            .map(|settings| settings.clone())
+            .map_err(|_| AppError::LockPoisoned("user_settings"))
+    }
+
+    /// 当前界面语言
+    pub fn language(&self) -> Language {
+        self.user_settings
+            .lock()
+            .map(|settings| settings.language)
+            .unwrap_or_default()
+    }
+
+    /// 按 ID 查找机械臂会话; 未指定 ID 时只有一台机械臂才能省略
+    pub fn robot(&self, robot_id: Option<&str>) -> Result<Arc<RobotSession>, AppError> {
+        let robots = self
+            .robots
+            .read()
+            .map_err(|_| AppError::LockPoisoned("robots"))?;
+
+        match robot_id {
+            Some(id) => robots
+                .get(id)
+                .cloned()
+                .ok_or_else(|| AppError::RobotNotFound(id.to_string())),
+            None => match robots.len() {
+                0 => Err(AppError::RobotNotConnected),
+                1 => Ok(robots.values().next().cloned().unwrap()),
+                _ => Err(AppError::RobotIdRequired),
+            },
+        }
+    }
+
+    /// 已有的会话, 不存在时新建并加入列表; 同一 ID 并发调用时得到同一个会话
+    pub fn robot_entry(&self, robot_id: &str) -> Result<Arc<RobotSession>, AppError> {
+        let mut robots = self
+            .robots
+            .write()
+            .map_err(|_| AppError::LockPoisoned("robots"))?;
+
+        Ok(robots
+            .entry(robot_id.to_string())
+            .or_insert_with(|| RobotSession::new(robot_id, self.app.clone()))
+            .clone())
+    }
+
+    pub fn remove_robot(&self, robot_id: &str) -> Result<(), AppError> {
+        self.robots
+            .write()
+            .map_err(|_| AppError::LockPoisoned("robots"))?
+            .remove(robot_id);
+        Ok(())
+    }
+
+    /// 所有机械臂会话, 按 ID 排序
+    pub fn robot_sessions(&self) -> Vec<Arc<RobotSession>> {
+        let mut robots: Vec<_> = self
+            .robots
+            .read()
+            .map(|robots| robots.values().cloned().collect())
+            .unwrap_or_default();
+        robots.sort_by(|a: &Arc<RobotSession>, b| a.id.cmp(&b.id));
+        robots
+    }
+
+    /// 各机械臂的连接状态; 指定的机械臂不存在时视为未连接
+    pub fn robot_connections(&self, robot_id: Option<&str>) -> Vec<(String, bool)> {
+        let mut connections: Vec<_> = self
+            .robot_sessions()
+            .iter()
+            .filter(|robot| robot_id.is_none() || robot_id == Some(robot.id.as_str()))
+            .map(|robot| (robot.id.clone(), robot.connected()))
+            .collect();
+        if let Some(robot_id) = robot_id.filter(|_| connections.is_empty()) {
+            connections.push((robot_id.to_string(), false));
+        }
+        connections
+    }
 }
 
 // 获取全局的app 实例
@@ -1,4 +1,5 @@
 pub mod app_state; // 导入 app_state 模块
+pub mod user_settings;
 
 // pub fn init<R: Runtime>() -> TauriPlugin<R> {
 //     Builder::<R>::new("state")
@@ -0,0 +1,339 @@
+// user_settings.rs - 用户设置, 以带版本号的 JSON 保存在配置目录
+//
+// 读取时按版本号依次执行迁移, 再以默认值补全缺失字段; 新版本写入的设置
+// 由旧版本读取时忽略未知字段, 且不会被旧版本覆盖
+use crate::commands::{
+    arm_service::{
+        robot_client::ClientConfig,
+        structs::{ObserveParams, Unit},
+    },
+    rest_api::{self, RestApiConfig},
+    streaming::{
+        metrics::{self, MetricsConfig},
+        mqtt_publisher::{self, MqttConfig},
+        udp_publisher::{self, UdpPublisherConfig},
+    },
+};
+pub use crate::utils::i18n::Language;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    io::{self},
+    path::Path,
+};
+
+/// 设置文件名 (位于配置目录)
+pub const SETTINGS_FILE: &str = "settings.json";
+
+// 最近连接的机械臂保留数量
+const MAX_RECENT_ROBOTS: usize = 10;
+
+/// 迁移步骤: MIGRATIONS[i] 将版本 i 的设置迁移到版本 i + 1
+const MIGRATIONS: &[fn(&mut Value, &Path)] =
+    &[migrate_v0_streaming_files, migrate_v1_rest_api_file];
+
+/// 当前设置版本
+pub const SETTINGS_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// 最近连接的机械臂
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentRobot {
+    pub ip: String,
+    #[serde(default)]
+    pub nickname: String,
+    #[serde(default)]
+    pub last_connected: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+/// 流式输出集成的配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamingSettings {
+    pub udp: UdpPublisherConfig,
+    pub mqtt: MqttConfig,
+    pub metrics: MetricsConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserSettings {
+    pub version: u32,
+    pub recent_robots: Vec<RecentRobot>, // 最近连接在前
+    pub observe_params: ObserveParams,   // 默认观测参数
+    pub unit: Unit,                      // 显示单位
+    pub language: Language,
+    pub export_dir: Option<String>, // 默认导出目录
+    pub update_channel: UpdateChannel,
+    pub streaming: StreamingSettings,
+    pub connection: ClientConfig, // 实时数据连接的超时
+    pub rest_api: RestApiConfig,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            recent_robots: vec![],
+            observe_params: ObserveParams::default(),
+            unit: Unit::Angle,
+            language: Language::default(),
+            export_dir: None,
+            update_channel: UpdateChannel::default(),
+            streaming: StreamingSettings::default(),
+            connection: ClientConfig::default(),
+            rest_api: RestApiConfig::default(),
+        }
+    }
+}
+
+impl UserSettings {
+    /// 读取设置, 需要时迁移并写回; 文件损坏时备份为 .bak 并使用默认设置,
+    /// 个别字段无法解析时同样备份, 只有这些字段使用默认值
+    pub fn load(config_dir: &Path) -> Self {
+        let path = config_dir.join(SETTINGS_FILE);
+        let mut value = match std::fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str::<Value>(&content) {
+                Ok(value) if value.is_object() => value,
+                _ => {
+                    eprintln!("Invalid settings file, backing up and using defaults");
+                    let _ = std::fs::rename(&path, path.with_extension("json.bak"));
+                    json!({ "version": SETTINGS_VERSION })
+                }
+            },
+            // 首次运行 (或升级前的版本没有设置文件)
+            Err(_) => json!({}),
+        };
+
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+        for migrate in MIGRATIONS.iter().skip(version as usize) {
+            migrate(&mut value, config_dir);
+        }
+        if version < SETTINGS_VERSION {
+            value["version"] = json!(SETTINGS_VERSION);
+        }
+
+        let settings: Self = match serde_json::from_value(value.clone()) {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("Failed to parse settings, backing up: {:?}", e);
+                if path.exists() {
+                    let _ = std::fs::copy(&path, path.with_extension("json.bak"));
+                }
+                Self::from_valid_fields(value)
+            }
+        };
+        if version < SETTINGS_VERSION {
+            if let Err(e) = settings.save(config_dir) {
+                eprintln!("Failed to save migrated settings: {:?}", e);
+            }
+        }
+        settings
+    }
+
+    /// 逐字段读取, 无法解析的字段使用默认值
+    fn from_valid_fields(value: Value) -> Self {
+        let mut merged = serde_json::to_value(Self::default()).unwrap_or_else(|_| json!({}));
+        if let Value::Object(fields) = value {
+            for (key, field) in fields {
+                let mut candidate = merged.clone();
+                candidate[key.as_str()] = field;
+                if serde_json::from_value::<Self>(candidate.clone()).is_ok() {
+                    merged = candidate;
+                } else {
+                    eprintln!("Invalid setting {}, using default", key);
+                }
+            }
+        }
+        serde_json::from_value(merged).unwrap_or_default()
+    }
+
+    /// 保存设置; 设置文件由更新的版本写入时拒绝覆盖
+    pub fn save(&self, config_dir: &Path) -> io::Result<()> {
+        let path = config_dir.join(SETTINGS_FILE);
+        let stored_version = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .and_then(|value| value.get("version").and_then(|v| v.as_u64()));
+        if let Some(version) = stored_version.filter(|v| *v > SETTINGS_VERSION as u64) {
+            return Err(io::Error::other(format!(
+                "Settings file was written by a newer version (v{})",
+                version
+            )));
+        }
+
+        let content = serde_json::to_string_pretty(self)?;
+        // 先写临时文件再替换, 避免写入中断导致设置损坏
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, content)?;
+        std::fs::rename(&temp_path, &path)
+    }
+
+    /// 记录成功连接的机械臂, 已有的保留昵称并移到最前
+    pub fn remember_robot(&mut self, ip: &str) {
+        let nickname = self
+            .recent_robots
+            .iter()
+            .find(|robot| robot.ip == ip)
+            .map(|robot| robot.nickname.clone())
+            .unwrap_or_default();
+        self.recent_robots.retain(|robot| robot.ip != ip);
+        self.recent_robots.insert(
+            0,
+            RecentRobot {
+                ip: ip.to_string(),
+                nickname,
+                last_connected: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            },
+        );
+        self.recent_robots.truncate(MAX_RECENT_ROBOTS);
+    }
+}
+
+/// v0 -> v1: 流式输出配置原先各自保存在配置目录中的独立文件
+fn migrate_v0_streaming_files(value: &mut Value, config_dir: &Path) {
+    let mut streaming = serde_json::Map::new();
+    for (key, file) in [
+        ("udp", udp_publisher::CONFIG_FILE),
+        ("mqtt", mqtt_publisher::CONFIG_FILE),
+        ("metrics", metrics::CONFIG_FILE),
+    ] {
+        let config = std::fs::read_to_string(config_dir.join(file))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+        if let Some(config) = config {
+            streaming.insert(key.to_string(), config);
+        }
+    }
+    if !streaming.is_empty() {
+        value["streaming"] = Value::Object(streaming);
+    }
+}
+
+/// v1 -> v2: REST 接口配置原先保存在配置目录中的独立文件
+fn migrate_v1_rest_api_file(value: &mut Value, config_dir: &Path) {
+    let config = std::fs::read_to_string(config_dir.join(rest_api::CONFIG_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+    if let Some(config) = config {
+        value["rest_api"] = config;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ufactory_settings_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_migrate_legacy_streaming_files() {
+        let dir = temp_dir("migrate");
+        std::fs::write(
+            dir.join(udp_publisher::CONFIG_FILE),
+            r#"{"enabled":true,"port":9999}"#,
+        )
+        .unwrap();
+
+        let settings = UserSettings::load(&dir);
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert!(settings.streaming.udp.enabled);
+        assert_eq!(settings.streaming.udp.port, 9999);
+        assert!(!settings.streaming.mqtt.enabled);
+
+        // 迁移后写回, 再次读取不再依赖旧文件
+        std::fs::remove_file(dir.join(udp_publisher::CONFIG_FILE)).unwrap();
+        assert_eq!(UserSettings::load(&dir).streaming.udp.port, 9999);
+
+        // v1 的设置只迁移 REST 接口配置, 保留已有设置
+        std::fs::write(
+            dir.join(SETTINGS_FILE),
+            r#"{"version":1,"streaming":{"udp":{"enabled":true,"port":9999}}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join(rest_api::CONFIG_FILE),
+            r#"{"enabled":true,"port":18181,"token":"abc"}"#,
+        )
+        .unwrap();
+        let settings = UserSettings::load(&dir);
+        assert!(settings.rest_api.enabled);
+        assert_eq!(settings.rest_api.port, 18181);
+        assert_eq!(settings.rest_api.token, "abc");
+        assert_eq!(settings.streaming.udp.port, 9999);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_recent_robots_and_corrupt_file() {
+        let dir = temp_dir("recent");
+        let mut settings = UserSettings::load(&dir);
+        settings.remember_robot("192.168.1.10");
+        settings.recent_robots[0].nickname = "left arm".to_string();
+        settings.remember_robot("192.168.1.11");
+        settings.remember_robot("192.168.1.10");
+        settings.save(&dir).unwrap();
+
+        let settings = UserSettings::load(&dir);
+        let ips: Vec<&str> = settings
+            .recent_robots
+            .iter()
+            .map(|r| r.ip.as_str())
+            .collect();
+        assert_eq!(ips, ["192.168.1.10", "192.168.1.11"]);
+        assert_eq!(settings.recent_robots[0].nickname, "left arm");
+
+        std::fs::write(dir.join(SETTINGS_FILE), "{ not json").unwrap();
+        assert!(UserSettings::load(&dir).recent_robots.is_empty());
+        assert!(dir.join("settings.json.bak").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_invalid_field_and_newer_version() {
+        let dir = temp_dir("fields");
+
+        // 单个字段无法解析: 备份原文件, 其余字段保留
+        let content = format!(
+            r#"{{"version":{},"unit":"bogus","recent_robots":[{{"ip":"192.168.1.10"}}]}}"#,
+            SETTINGS_VERSION
+        );
+        std::fs::write(dir.join(SETTINGS_FILE), &content).unwrap();
+        let settings = UserSettings::load(&dir);
+        assert_eq!(settings.recent_robots[0].ip, "192.168.1.10");
+        assert_eq!(settings.unit, Unit::Angle);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("settings.json.bak")).unwrap(),
+            content
+        );
+
+        // 更新的版本写入的设置不被覆盖
+        let content = r#"{"version":99,"language":"en","future":true}"#;
+        std::fs::write(dir.join(SETTINGS_FILE), content).unwrap();
+        let settings = UserSettings::load(&dir);
+        assert_eq!(settings.language, Language::En);
+        assert!(settings.save(&dir).is_err());
+        assert_eq!(
+            std::fs::read_to_string(dir.join(SETTINGS_FILE)).unwrap(),
+            content
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,360 @@
+// error.rs - 后端错误: 稳定的数字错误码 + 国际化消息键与参数
+//
+// 错误码按模块分段, 发布后不再修改含义:
+//   1xxx 通用, 2xxx 机械臂连接与录制, 3xxx 网络请求与本地服务, 4xxx 软件更新, 5xxx 设置与状态,
+//   6xxx 会话库, 7xxx 录制分析, 8xxx 观测预设
+// 前端按 code 判断错误类型, 按 key + params 显示翻译后的消息; message 为当前界面语言的消息
+use crate::utils::i18n::{self, Language};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// 旧接口中的自由文本错误码
+pub const LEGACY_ERROR_CODE: i32 = -1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    // 自由文本 (尚未归类的错误)
+    Message(String),
+
+    // 通用
+    Internal(String),
+    LockPoisoned(&'static str), // 锁名
+    InvalidArgument {
+        name: String,
+        reason: String,
+    },
+    Io {
+        path: String,
+        reason: String,
+    },
+    UnknownChannel(String),
+
+    // 机械臂连接与录制
+    RobotNotConnected,
+    RobotNotFound(String), // robot_id
+    RobotIdRequired,
+    AlreadyConnected(String), // 已连接的 IP
+    ConnectFailed {
+        ip: String,
+        reason: String,
+    },
+    ControllerUnreachable(String), // 控制器 WebSocket 无法连接
+    ObserverRunning,
+    ObserverNotRunning,
+    RecorderNotReady,
+    DeviceStatusUnavailable(String),
+    ConnectionActorStopped,
+
+    // 网络请求与本地服务
+    HttpRequest(String),
+    HttpStatus(u16),
+    InvalidResponse(String),
+    ServiceStart {
+        service: &'static str, // WebSocket, UDP, MQTT, Prometheus, REST API
+        addr: String,
+        reason: String,
+    },
+    ServiceNotRunning(&'static str),
+
+    // 软件更新
+    UpdaterUrl(String),
+    UpdaterInit(String),
+    UpdateCheck(String),
+
+    // 设置与状态
+    SettingsSave(String),
+    RecentRobotNotFound(String), // IP
+    TokenGenerate(String),
+
+    // 会话库
+    SessionNotFound(String), // 会话 ID
+    SessionLibrary(String),
+    OrphanNotFound(String), // 遗留录制文件名
+    RecoverFailed(String),
+
+    // 录制分析
+    ImportedNotFound(String), // 导入录制 ID
+    ImportFailed {
+        path: String,
+        reason: String,
+    },
+    GoldenEvaluate(String),
+    CycleTrackingNotRunning,
+
+    // 观测预设
+    PresetLoad {
+        name: String,
+        reason: String,
+    },
+    PresetInvalid(String),
+}
+
+impl AppError {
+    /// 稳定的数字错误码
+    pub fn code(&self) -> i32 {
+        match self {
+            AppError::Message(_) => LEGACY_ERROR_CODE,
+            AppError::Internal(_) => 1000,
+            AppError::LockPoisoned(_) => 1001,
+            AppError::InvalidArgument { .. } => 1002,
+            AppError::Io { .. } => 1003,
+            AppError::UnknownChannel(_) => 1004,
+            AppError::RobotNotConnected => 2001,
+            AppError::RobotNotFound(_) => 2002,
+            AppError::RobotIdRequired => 2003,
+            AppError::AlreadyConnected(_) => 2004,
+            AppError::ConnectFailed { .. } => 2005,
+            AppError::ControllerUnreachable(_) => 2006,
+            AppError::ObserverRunning => 2007,
+            AppError::ObserverNotRunning => 2008,
+            AppError::RecorderNotReady => 2009,
+            AppError::DeviceStatusUnavailable(_) => 2010,
+            AppError::ConnectionActorStopped => 2011,
+            AppError::HttpRequest(_) => 3001,
+            AppError::HttpStatus(_) => 3002,
+            AppError::InvalidResponse(_) => 3003,
+            AppError::ServiceStart { .. } => 3004,
+            AppError::ServiceNotRunning(_) => 3005,
+            AppError::UpdaterUrl(_) => 4001,
+            AppError::UpdaterInit(_) => 4002,
+            AppError::UpdateCheck(_) => 4003,
+            AppError::SettingsSave(_) => 5001,
+            AppError::RecentRobotNotFound(_) => 5002,
+            AppError::TokenGenerate(_) => 5003,
+            AppError::SessionNotFound(_) => 6001,
+            AppError::SessionLibrary(_) => 6002,
+            AppError::OrphanNotFound(_) => 6003,
+            AppError::RecoverFailed(_) => 6004,
+            AppError::ImportedNotFound(_) => 7001,
+            AppError::ImportFailed { .. } => 7002,
+            AppError::GoldenEvaluate(_) => 7003,
+            AppError::CycleTrackingNotRunning => 7004,
+            AppError::PresetLoad { .. } => 8001,
+            AppError::PresetInvalid(_) => 8002,
+        }
+    }
+
+    /// 国际化消息键
+    pub fn key(&self) -> &'static str {
+        match self {
+            AppError::Message(_) => "error.message",
+            AppError::Internal(_) => "error.internal",
+            AppError::LockPoisoned(_) => "error.lock_poisoned",
+            AppError::InvalidArgument { .. } => "error.invalid_argument",
+            AppError::Io { .. } => "error.io",
+            AppError::UnknownChannel(_) => "error.unknown_channel",
+            AppError::RobotNotConnected => "robot.not_connected",
+            AppError::RobotNotFound(_) => "robot.not_found",
+            AppError::RobotIdRequired => "robot.id_required",
+            AppError::AlreadyConnected(_) => "robot.already_connected",
+            AppError::ConnectFailed { .. } => "robot.connect_failed",
+            AppError::ControllerUnreachable(_) => "robot.controller_unreachable",
+            AppError::ObserverRunning => "observer.running",
+            AppError::ObserverNotRunning => "observer.not_running",
+            AppError::RecorderNotReady => "observer.recorder_not_ready",
+            AppError::DeviceStatusUnavailable(_) => "robot.device_status_unavailable",
+            AppError::ConnectionActorStopped => "robot.connection_stopped",
+            AppError::HttpRequest(_) => "request.failed",
+            AppError::HttpStatus(_) => "request.http_status",
+            AppError::InvalidResponse(_) => "request.invalid_response",
+            AppError::ServiceStart { .. } => "service.start_failed",
+            AppError::ServiceNotRunning(_) => "service.not_running",
+            AppError::UpdaterUrl(_) => "updater.invalid_url",
+            AppError::UpdaterInit(_) => "updater.init_failed",
+            AppError::UpdateCheck(_) => "updater.check_failed",
+            AppError::SettingsSave(_) => "settings.save_failed",
+            AppError::RecentRobotNotFound(_) => "settings.recent_robot_not_found",
+            AppError::TokenGenerate(_) => "settings.token_failed",
+            AppError::SessionNotFound(_) => "session.not_found",
+            AppError::SessionLibrary(_) => "session.library_failed",
+            AppError::OrphanNotFound(_) => "session.orphan_not_found",
+            AppError::RecoverFailed(_) => "session.recover_failed",
+            AppError::ImportedNotFound(_) => "analysis.imported_not_found",
+            AppError::ImportFailed { .. } => "analysis.import_failed",
+            AppError::GoldenEvaluate(_) => "analysis.golden_failed",
+            AppError::CycleTrackingNotRunning => "analysis.cycle_not_running",
+            AppError::PresetLoad { .. } => "preset.load_failed",
+            AppError::PresetInvalid(_) => "preset.invalid",
+        }
+    }
+
+    /// 消息参数, 与翻译文本中的 {name} 占位符对应
+    pub fn params(&self) -> Map<String, Value> {
+        let pairs: Vec<(&str, Value)> = match self {
+            AppError::Message(message) => vec![("message", message.as_str().into())],
+            AppError::LockPoisoned(lock) => vec![("lock", (*lock).into())],
+            AppError::InvalidArgument { name, reason } => vec![
+                ("name", name.as_str().into()),
+                ("reason", reason.as_str().into()),
+            ],
+            AppError::Io { path, reason } | AppError::ImportFailed { path, reason } => vec![
+                ("path", path.as_str().into()),
+                ("reason", reason.as_str().into()),
+            ],
+            AppError::UnknownChannel(channel) => vec![("channel", channel.as_str().into())],
+            AppError::ServiceStart {
+                service,
+                addr,
+                reason,
+            } => vec![
+                ("service", (*service).into()),
+                ("addr", addr.as_str().into()),
+                ("reason", reason.as_str().into()),
+            ],
+            AppError::ServiceNotRunning(service) => vec![("service", (*service).into())],
+            AppError::SessionNotFound(id) | AppError::ImportedNotFound(id) => {
+                vec![("id", id.as_str().into())]
+            }
+            AppError::OrphanNotFound(name) => vec![("name", name.as_str().into())],
+            AppError::PresetLoad { name, reason } => vec![
+                ("name", name.as_str().into()),
+                ("reason", reason.as_str().into()),
+            ],
+            AppError::RobotNotFound(robot_id) => vec![("robot_id", robot_id.as_str().into())],
+            AppError::AlreadyConnected(ip)
+            | AppError::ControllerUnreachable(ip)
+            | AppError::RecentRobotNotFound(ip) => vec![("ip", ip.as_str().into())],
+            AppError::ConnectFailed { ip, reason } => vec![
+                ("ip", ip.as_str().into()),
+                ("reason", reason.as_str().into()),
+            ],
+            AppError::HttpStatus(status) => vec![("status", (*status).into())],
+            AppError::Internal(reason)
+            | AppError::DeviceStatusUnavailable(reason)
+            | AppError::HttpRequest(reason)
+            | AppError::InvalidResponse(reason)
+            | AppError::UpdaterUrl(reason)
+            | AppError::UpdaterInit(reason)
+            | AppError::UpdateCheck(reason)
+            | AppError::SettingsSave(reason)
+            | AppError::TokenGenerate(reason)
+            | AppError::SessionLibrary(reason)
+            | AppError::RecoverFailed(reason)
+            | AppError::GoldenEvaluate(reason)
+            | AppError::PresetInvalid(reason) => vec![("reason", reason.as_str().into())],
+            AppError::RobotNotConnected
+            | AppError::RobotIdRequired
+            | AppError::ObserverRunning
+            | AppError::ObserverNotRunning
+            | AppError::RecorderNotReady
+            | AppError::ConnectionActorStopped
+            | AppError::CycleTrackingNotRunning => vec![],
+        };
+        pairs
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+
+    /// 当前界面语言的消息
+    pub fn localized(&self) -> String {
+        i18n::render(i18n::language(), self.key(), &self.params())
+    }
+}
+
+// 英文消息, 与旧接口的错误文本保持一致
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = i18n::render(Language::En, self.key(), &self.params());
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Message(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Message(message.to_string())
+    }
+}
+
+// 返回 String 错误的旧接口可以直接用 ? 传递
+impl From<AppError> for String {
+    fn from(e: AppError) -> Self {
+        e.localized()
+    }
+}
+
+// 作为命令的错误返回值时序列化为 { code, key, params, message }
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("code", &self.code())?;
+        state.serialize_field("key", self.key())?;
+        state.serialize_field("params", &self.params())?;
+        state.serialize_field("message", &self.localized())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_and_serialization() {
+        let e = AppError::RobotNotFound("arm-1".to_string());
+        assert_eq!(e.code(), 2002);
+        assert_eq!(e.key(), "robot.not_found");
+        assert_eq!(e.to_string(), "Robot not found: arm-1");
+
+        let value = serde_json::to_value(&e).unwrap();
+        assert_eq!(value["code"], 2002);
+        assert_eq!(value["params"]["robot_id"], "arm-1");
+
+        // 旧的字符串错误保持 -1
+        let legacy: AppError = "请先连接机器人服务器".into();
+        assert_eq!(legacy.code(), LEGACY_ERROR_CODE);
+        assert_eq!(String::from(legacy), "请先连接机器人服务器");
+        assert!(AppError::RobotNotConnected.params().is_empty());
+    }
+
+    #[test]
+    fn test_keys_have_translations() {
+        let errors = [
+            AppError::UnknownChannel("x".to_string()),
+            AppError::ServiceStart {
+                service: "UDP",
+                addr: "127.0.0.1:9870".to_string(),
+                reason: "in use".to_string(),
+            },
+            AppError::ServiceNotRunning("WebSocket"),
+            AppError::RecentRobotNotFound("192.168.1.10".to_string()),
+            AppError::TokenGenerate("x".to_string()),
+            AppError::SessionNotFound("1".to_string()),
+            AppError::SessionLibrary("x".to_string()),
+            AppError::OrphanNotFound("x".to_string()),
+            AppError::RecoverFailed("x".to_string()),
+            AppError::ImportedNotFound("1".to_string()),
+            AppError::ImportFailed {
+                path: "a.csv".to_string(),
+                reason: "x".to_string(),
+            },
+            AppError::GoldenEvaluate("x".to_string()),
+            AppError::CycleTrackingNotRunning,
+            AppError::PresetLoad {
+                name: "a".to_string(),
+                reason: "x".to_string(),
+            },
+            AppError::PresetInvalid("x".to_string()),
+        ];
+        for e in errors {
+            for language in [Language::ZhCn, Language::En] {
+                assert!(i18n::lookup(language, e.key()).is_some(), "{}", e.key());
+            }
+            // 参数全部填入消息
+            assert!(!e.to_string().contains('{'), "{}", e);
+        }
+        assert_eq!(
+            AppError::SessionNotFound("20250101".to_string()).to_string(),
+            "Session not found: 20250101"
+        );
+    }
+}
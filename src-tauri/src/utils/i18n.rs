@@ -0,0 +1,440 @@
+// i18n.rs - 后端文本的多语言目录 (zh-CN / en), 跟随用户设置中的界面语言
+//
+// 文本中的 {name} 为参数占位符; 当前语言缺少某个键时使用英文, 仍缺少时返回键本身
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 界面语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+    #[serde(rename = "en")]
+    En,
+}
+
+impl Language {
+    /// 控制器 WebSocket 的 lang 参数
+    pub fn ws_lang(&self) -> &'static str {
+        match self {
+            Language::ZhCn => "cn",
+            Language::En => "en",
+        }
+    }
+
+    fn catalogue(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::ZhCn => ZH_CN,
+            Language::En => EN,
+        }
+    }
+}
+
+// 当前语言, 读取设置与修改设置时更新
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+/// 设置当前语言
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+/// 当前语言
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::En,
+        _ => Language::ZhCn,
+    }
+}
+
+/// 按语言查找文本
+pub fn lookup(language: Language, key: &str) -> Option<&'static str> {
+    let find = |language: Language| {
+        language
+            .catalogue()
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, text)| *text)
+    };
+    find(language).or_else(|| find(Language::En))
+}
+
+/// 按语言生成文本, 参数替换 {name} 占位符
+pub fn render(language: Language, key: &str, params: &Map<String, Value>) -> String {
+    let Some(template) = lookup(language, key) else {
+        return key.to_string();
+    };
+    fill(template, |name| {
+        params.get(name).map(|value| match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    })
+}
+
+/// 当前语言的文本
+pub fn t(key: &str) -> String {
+    lookup(language(), key).unwrap_or(key).to_string()
+}
+
+/// 当前语言的文本, 参数替换 {name} 占位符
+pub fn t_args(key: &str, args: &[(&str, String)]) -> String {
+    t_args_in(language(), key, args)
+}
+
+/// 指定语言的文本, 参数替换 {name} 占位符
+pub fn t_args_in(language: Language, key: &str, args: &[(&str, String)]) -> String {
+    let Some(template) = lookup(language, key) else {
+        return key.to_string();
+    };
+    fill(template, |name| {
+        args.iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| value.clone())
+    })
+}
+
+fn fill(template: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + len];
+        match value(name) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 导出文件中的通道名称: {类型}_{序号} 显示为 "{类型名称} {序号}", 没有名称的通道保持原样
+pub fn channel_label(language: Language, name: &str) -> String {
+    let label = |name: &str| lookup(language, &format!("channel.{}", name));
+    if let Some(label) = label(name) {
+        return label.to_string();
+    }
+    match name.rsplit_once('_') {
+        Some((prefix, index)) if index.parse::<usize>().is_ok() => match label(prefix) {
+            Some(label) => format!("{} {}", label, index),
+            None => name.to_string(),
+        },
+        _ => name.to_string(),
+    }
+}
+
+/// 由任一语言的通道名称还原通道名, 用于读取导出的CSV
+pub fn channel_name(label: &str) -> Option<String> {
+    let find = |label: &str| {
+        [ZH_CN, EN].iter().find_map(|catalogue| {
+            catalogue.iter().find_map(|(key, text)| {
+                key.strip_prefix("channel.")
+                    .filter(|_| *text == label)
+                    .map(|name| name.to_string())
+            })
+        })
+    };
+    if let Some(name) = find(label) {
+        return Some(name);
+    }
+    let (prefix, index) = label.rsplit_once(' ')?;
+    index.parse::<usize>().ok()?;
+    find(prefix).map(|name| format!("{}_{}", name, index))
+}
+
+const ZH_CN: &[(&str, &str)] = &[
+    // 错误 (与 AppError::key 对应)
+    ("error.message", "{message}"),
+    ("error.internal", "内部错误: {reason}"),
+    ("error.lock_poisoned", "获取 {lock} 锁失败"),
+    ("error.invalid_argument", "参数 {name} 无效: {reason}"),
+    ("error.io", "访问 {path} 失败: {reason}"),
+    ("robot.not_connected", "请先连接机器人服务器"),
+    ("robot.not_found", "未找到机械臂: {robot_id}"),
+    ("robot.id_required", "已连接多台机械臂, 请指定 robot_id"),
+    ("robot.already_connected", "已连接到 {ip}"),
+    ("robot.connect_failed", "连接 {ip} 失败: {reason}"),
+    ("robot.controller_unreachable", "无法连接控制器 WebSocket: {ip}"),
+    ("robot.device_status_unavailable", "获取设备状态失败: {reason}"),
+    ("robot.connection_stopped", "连接管理已停止"),
+    ("observer.running", "观测已在运行"),
+    ("observer.not_running", "观测未运行"),
+    ("observer.recorder_not_ready", "录制文件未初始化"),
+    ("request.failed", "请求失败: {reason}"),
+    ("request.http_status", "HTTP 错误: {status}"),
+    ("request.invalid_response", "响应无效: {reason}"),
+    ("updater.invalid_url", "解析更新URL失败: {reason}"),
+    ("updater.init_failed", "无法初始化更新器: {reason}"),
+    ("updater.check_failed", "检查更新失败: {reason}"),
+    ("settings.save_failed", "保存设置失败: {reason}"),
+    ("error.unknown_channel", "未知通道: {channel}"),
+    ("service.start_failed", "启动 {service} 服务失败 ({addr}): {reason}"),
+    ("service.not_running", "{service} 服务未运行"),
+    ("settings.recent_robot_not_found", "连接历史中没有该机械臂: {ip}"),
+    ("settings.token_failed", "生成令牌失败: {reason}"),
+    ("session.not_found", "未找到会话: {id}"),
+    ("session.library_failed", "会话库操作失败: {reason}"),
+    ("session.orphan_not_found", "未找到遗留录制: {name}"),
+    ("session.recover_failed", "恢复录制失败: {reason}"),
+    ("analysis.imported_not_found", "未找到导入的录制: {id}"),
+    ("analysis.import_failed", "导入 {path} 失败: {reason}"),
+    ("analysis.golden_failed", "标准录制检查失败: {reason}"),
+    ("analysis.cycle_not_running", "周期追踪未运行"),
+    ("preset.load_failed", "读取预设 {name} 失败: {reason}"),
+    ("preset.invalid", "预设无效: {reason}"),
+    // 实时数据连接
+    ("robot.connect_timeout", "连接机器人超时: {ip}"),
+    ("robot.read_timeout", "{ms}ms 内未收到机器人数据"),
+    ("robot.stream_stalled", "数据流停滞: {ms}ms 内未收到完整数据包"),
+    ("robot.closed_by_peer", "连接已被机器人关闭"),
+    ("robot.not_connected_client", "未连接到机器人"),
+    ("robot.invalid_observe_params", "无法读取观测参数"),
+    ("robot.connection_lost", "与机械臂的连接已断开: {reason}"),
+    // 控制器错误/警告记录
+    ("device.error", "错误 {code}: {description}"),
+    ("device.error_cleared", "错误 {code} 已清除: {description}"),
+    ("device.warning", "警告 {code}: {description}"),
+    ("device.warning_cleared", "警告 {code} 已清除: {description}"),
+    // 标准录制检查报告
+    (
+        "golden.passed",
+        "{program} 检查通过: 共检查 {checked} 个通道, 时间偏移 {offset} s, 时长差 {duration_diff} s",
+    ),
+    (
+        "golden.failed",
+        "{program} 检查未通过: {violations} 个通道超差, 缺失 {missing} 个通道, 时长差 {duration_diff} s",
+    ),
+    // 无界面录制
+    ("headless.recording", "录制 {duration}s 到 {path}"),
+    // 导出文件中的通道名称
+    ("channel.timestamp", "时间戳 (ms)"),
+    ("channel.time", "时间 (s)"),
+    ("channel.t", "时间 t (s)"),
+    ("channel.motion_state", "运动状态"),
+    ("channel.target_joint_positions", "规划关节位置"),
+    ("channel.target_joint_velocities", "规划关节速度"),
+    ("channel.target_joint_accelerations", "规划关节加速度"),
+    ("channel.actual_joint_positions", "实际关节位置"),
+    ("channel.actual_joint_velocities", "实际关节速度"),
+    ("channel.actual_joint_accelerations", "实际关节加速度"),
+    ("channel.actual_joint_currents", "实际关节电流"),
+    ("channel.target_tcp_pose", "规划TCP位姿"),
+    ("channel.actual_tcp_pose", "实际TCP位姿"),
+    ("channel.target_tcp_velocity", "规划TCP速度"),
+    ("channel.actual_tcp_velocity", "实际TCP速度"),
+    ("channel.estimated_tcp_torque", "估计TCP力矩"),
+    ("channel.estimated_joint_torque", "估计关节力矩"),
+    ("channel.target_tcp_accelerations", "规划TCP加速度"),
+    ("channel.actual_tcp_accelerations", "实际TCP加速度"),
+    ("channel.data_torque_sensor", "力矩传感器原始数据"),
+    ("channel.filtered_data_torque_sensor", "力矩传感器滤波数据"),
+    ("channel.analysis_joint_positions", "分析关节位置"),
+    ("channel.analysis_joint_velocities", "分析关节速度"),
+    ("channel.analysis_joint_accelerations", "分析关节加速度"),
+    ("channel.analysis_tcp_positions", "分析TCP位置"),
+    ("channel.analysis_tcp_velocities", "分析TCP速度"),
+    ("channel.analysis_tcp_accelerations", "分析TCP加速度"),
+    ("channel.difference_data", "差值数据"),
+];
+
+const EN: &[(&str, &str)] = &[
+    ("error.message", "{message}"),
+    ("error.internal", "Internal error: {reason}"),
+    ("error.lock_poisoned", "Failed to acquire {lock} lock"),
+    ("error.invalid_argument", "Invalid {name}: {reason}"),
+    ("error.io", "Failed to access {path}: {reason}"),
+    ("robot.not_connected", "Server is not running"),
+    ("robot.not_found", "Robot not found: {robot_id}"),
+    (
+        "robot.id_required",
+        "Multiple robots connected, robot_id is required",
+    ),
+    ("robot.already_connected", "Already connected to {ip}"),
+    ("robot.connect_failed", "Failed to connect to {ip}: {reason}"),
+    (
+        "robot.controller_unreachable",
+        "Controller websocket is unreachable: {ip}",
+    ),
+    (
+        "robot.device_status_unavailable",
+        "Failed to get device status: {reason}",
+    ),
+    ("robot.connection_stopped", "Connection actor stopped"),
+    ("observer.running", "Assistant is already running"),
+    ("observer.not_running", "Assistant is not running"),
+    ("observer.recorder_not_ready", "CSV exporter not initialized"),
+    ("request.failed", "Request failed: {reason}"),
+    ("request.http_status", "HTTP error: {status}"),
+    ("request.invalid_response", "Invalid response: {reason}"),
+    ("updater.invalid_url", "Invalid update URL: {reason}"),
+    ("updater.init_failed", "Failed to initialize updater: {reason}"),
+    ("updater.check_failed", "Failed to check for updates: {reason}"),
+    ("settings.save_failed", "Failed to save settings: {reason}"),
+    ("error.unknown_channel", "Unknown channel: {channel}"),
+    (
+        "service.start_failed",
+        "Failed to start {service} service on {addr}: {reason}",
+    ),
+    ("service.not_running", "{service} service is not running"),
+    (
+        "settings.recent_robot_not_found",
+        "Robot not found in history: {ip}",
+    ),
+    ("settings.token_failed", "Failed to generate token: {reason}"),
+    ("session.not_found", "Session not found: {id}"),
+    ("session.library_failed", "Session library error: {reason}"),
+    ("session.orphan_not_found", "Orphaned recording not found: {name}"),
+    ("session.recover_failed", "Failed to recover recording: {reason}"),
+    ("analysis.imported_not_found", "Imported recording not found: {id}"),
+    ("analysis.import_failed", "Failed to import {path}: {reason}"),
+    (
+        "analysis.golden_failed",
+        "Failed to evaluate golden run: {reason}",
+    ),
+    ("analysis.cycle_not_running", "Cycle tracking is not running"),
+    ("preset.load_failed", "Failed to load preset {name}: {reason}"),
+    ("preset.invalid", "Invalid preset: {reason}"),
+    ("robot.connect_timeout", "Timed out connecting to robot: {ip}"),
+    ("robot.read_timeout", "No data received from robot within {ms}ms"),
+    (
+        "robot.stream_stalled",
+        "Data stream stalled: no complete packet within {ms}ms",
+    ),
+    ("robot.closed_by_peer", "Connection closed by robot"),
+    ("robot.not_connected_client", "Not connected to robot"),
+    (
+        "robot.invalid_observe_params",
+        "Failed to read observe parameters",
+    ),
+    ("robot.connection_lost", "Connection to robot lost: {reason}"),
+    ("device.error", "error {code}: {description}"),
+    ("device.error_cleared", "error {code} cleared: {description}"),
+    ("device.warning", "warning {code}: {description}"),
+    ("device.warning_cleared", "warning {code} cleared: {description}"),
+    (
+        "golden.passed",
+        "{program} passed: {checked} channels checked, offset {offset} s, duration diff {duration_diff} s",
+    ),
+    (
+        "golden.failed",
+        "{program} failed: {violations} channels out of tolerance, {missing} channels missing, duration diff {duration_diff} s",
+    ),
+    ("headless.recording", "Recording {duration}s to {path}"),
+    ("channel.timestamp", "Timestamp (ms)"),
+    ("channel.time", "Time (s)"),
+    ("channel.t", "Time t (s)"),
+    ("channel.motion_state", "Motion state"),
+    ("channel.target_joint_positions", "Target joint position"),
+    ("channel.target_joint_velocities", "Target joint velocity"),
+    (
+        "channel.target_joint_accelerations",
+        "Target joint acceleration",
+    ),
+    ("channel.actual_joint_positions", "Actual joint position"),
+    ("channel.actual_joint_velocities", "Actual joint velocity"),
+    (
+        "channel.actual_joint_accelerations",
+        "Actual joint acceleration",
+    ),
+    ("channel.actual_joint_currents", "Actual joint current"),
+    ("channel.target_tcp_pose", "Target TCP pose"),
+    ("channel.actual_tcp_pose", "Actual TCP pose"),
+    ("channel.target_tcp_velocity", "Target TCP velocity"),
+    ("channel.actual_tcp_velocity", "Actual TCP velocity"),
+    ("channel.estimated_tcp_torque", "Estimated TCP torque"),
+    ("channel.estimated_joint_torque", "Estimated joint torque"),
+    ("channel.target_tcp_accelerations", "Target TCP acceleration"),
+    ("channel.actual_tcp_accelerations", "Actual TCP acceleration"),
+    ("channel.data_torque_sensor", "Force/torque sensor raw"),
+    (
+        "channel.filtered_data_torque_sensor",
+        "Force/torque sensor filtered",
+    ),
+    ("channel.analysis_joint_positions", "Analysis joint position"),
+    ("channel.analysis_joint_velocities", "Analysis joint velocity"),
+    (
+        "channel.analysis_joint_accelerations",
+        "Analysis joint acceleration",
+    ),
+    ("channel.analysis_tcp_positions", "Analysis TCP position"),
+    ("channel.analysis_tcp_velocities", "Analysis TCP velocity"),
+    (
+        "channel.analysis_tcp_accelerations",
+        "Analysis TCP acceleration",
+    ),
+    ("channel.difference_data", "Difference"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogues_match() {
+        // 两种语言的键一致
+        for (key, _) in ZH_CN {
+            assert!(EN.iter().any(|(k, _)| k == key), "missing en: {}", key);
+        }
+        for (key, _) in EN {
+            assert!(
+                ZH_CN.iter().any(|(k, _)| k == key),
+                "missing zh-CN: {}",
+                key
+            );
+        }
+
+        let params: Map<String, Value> =
+            serde_json::from_value(serde_json::json!({ "status": 404 })).unwrap();
+        assert_eq!(
+            render(Language::En, "request.http_status", &params),
+            "HTTP error: 404"
+        );
+        assert_eq!(
+            render(Language::ZhCn, "request.http_status", &params),
+            "HTTP 错误: 404"
+        );
+        assert_eq!(render(Language::En, "no.such_key", &params), "no.such_key");
+    }
+
+    #[test]
+    fn test_channel_labels_round_trip() {
+        for language in [Language::ZhCn, Language::En] {
+            for name in [
+                "timestamp",
+                "time",
+                "t",
+                "actual_joint_positions_3",
+                "motion_state",
+            ] {
+                let label = channel_label(language, name);
+                assert_ne!(label, name);
+                assert_eq!(channel_name(&label).as_deref(), Some(name));
+            }
+        }
+        assert_eq!(
+            channel_label(Language::ZhCn, "actual_tcp_pose_2"),
+            "实际TCP位姿 2"
+        );
+        // 未知通道保持原样
+        assert_eq!(channel_label(Language::En, "col_1"), "col_1");
+        assert_eq!(channel_name("col_1"), None);
+
+        // 同一语言中通道名称不重复, 否则无法还原通道名
+        for catalogue in [ZH_CN, EN] {
+            let labels: Vec<&str> = catalogue
+                .iter()
+                .filter(|(key, _)| key.starts_with("channel."))
+                .map(|(_, text)| *text)
+                .collect();
+            for (i, label) in labels.iter().enumerate() {
+                assert!(!labels[i + 1..].contains(label), "duplicate: {}", label);
+            }
+        }
+    }
+}
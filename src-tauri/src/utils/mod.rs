@@ -1,3 +1,5 @@
+pub mod error;
+pub mod i18n;
 pub mod response;
 pub mod system;
 pub mod user_data;
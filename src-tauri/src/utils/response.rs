@@ -1,10 +1,23 @@
 use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::utils::error::{AppError, LEGACY_ERROR_CODE};
 
 #[derive(Serialize)]
 pub struct Response<T> {
     pub code: i32,
     pub data: Option<T>,
     pub message: String,
+    // 结构化错误 (国际化消息键与参数), 自由文本错误时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorDetail>,
+}
+
+/// 错误的国际化消息键与参数
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorDetail {
+    pub key: &'static str,
+    pub params: Map<String, Value>,
 }
 
 impl<T> Response<T> {
@@ -13,14 +26,16 @@ impl<T> Response<T> {
             code: 0,
             data: Some(data),
             message: "success".to_string(),
+            error: None,
         }
     }
 
     pub fn error(msg: impl Into<String>) -> Self {
         Self {
-            code: -1,
+            code: LEGACY_ERROR_CODE,
             data: None,
             message: msg.into(),
+            error: None,
         }
     }
 
@@ -31,32 +46,47 @@ impl<T> Response<T> {
             code,
             data,
             message: msg.into(),
+            error: None,
         }
     }
 }
 
-use std::fmt::Display;
+impl<T> From<AppError> for Response<T> {
+    fn from(e: AppError) -> Self {
+        let error = match e {
+            AppError::Message(_) => None,
+            _ => Some(ErrorDetail {
+                key: e.key(),
+                params: e.params(),
+            }),
+        };
+        Self {
+            code: e.code(),
+            data: None,
+            message: e.localized(),
+            error,
+        }
+    }
+}
 
-impl<T, E: Display> From<Result<T, E>> for Response<T> {
+// String 错误转换为自由文本错误 (-1), AppError 保留错误码
+impl<T, E: Into<AppError>> From<Result<T, E>> for Response<T> {
     fn from(result: Result<T, E>) -> Self {
         match result {
             Ok(data) => Response::success(data),
-            Err(e) => Response::error(e.to_string()),
+            Err(e) => {
+                let e: AppError = e.into();
+                e.into()
+            }
         }
     }
 }
 
 // 改为同步函数，接收已完成的 Result
-pub fn wrap_result<T, E: std::fmt::Debug>(
+pub fn wrap_result<T, E: Into<AppError>>(
     result: Result<T, E>,
-) -> Result<Response<T>, Response<E>> {
-    match result {
-        Ok(data) => Ok(Response::success(data)),
-        Err(err) => {
-            let msg = format!("{:?}", err);
-            Ok(Response::error(msg))
-        }
-    }
+) -> Result<Response<T>, Response<String>> {
+    Ok(result.into())
 }
 
 // 宏保持不变，但使用时需要确保传入的是已完成的 Result
@@ -23,6 +23,9 @@ pub struct UserDataPaths {
 
     /// 配置目录: {root}/config
     pub config: PathBuf,
+
+    /// 标准录制目录: {root}/golden
+    pub golden: PathBuf,
 }
 
 impl UserDataPaths {
@@ -42,6 +45,7 @@ impl UserDataPaths {
         let csv_data = root.join("csv_data");
         let csv_temp = root.join("csv_temp");
         let config = root.join("config");
+        let golden = root.join("golden");
 
         let paths = Self {
             root,
@@ -49,6 +53,7 @@ impl UserDataPaths {
             csv_data,
             csv_temp,
             config,
+            golden,
         };
 
         // 创建所有必要的目录
@@ -59,7 +64,13 @@ impl UserDataPaths {
 
     /// 确保所有目录存在
     fn ensure_dirs_exist(&self) -> Result<(), String> {
-        for dir in [&self.logs, &self.csv_data, &self.csv_temp, &self.config] {
+        for dir in [
+            &self.logs,
+            &self.csv_data,
+            &self.csv_temp,
+            &self.config,
+            &self.golden,
+        ] {
             std::fs::create_dir_all(dir)
                 .map_err(|_| format!("Failed to create directory: {}", dir.display()))?;
         }
@@ -78,8 +89,7 @@ impl UserDataPaths {
         self.logs.join(format!("{date}.log"))
     }
 
-    /// 清理旧的临时文件 (保留最近N天)
-    #[allow(dead_code)]
+    /// 清理旧的临时文件 (保留最近N天内修改过的)
     pub fn cleanup_temp_files(&self, keep_days: u32) -> Result<(), String> {
         use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -93,9 +103,10 @@ impl UserDataPaths {
         if let Ok(entries) = std::fs::read_dir(&self.csv_temp) {
             for entry in entries.flatten() {
                 if let Ok(metadata) = entry.metadata() {
-                    if let Ok(created) = metadata.created() {
-                        if let Ok(created_secs) = created.duration_since(UNIX_EPOCH) {
-                            if created_secs.as_secs() < cutoff {
+                    // 按最后修改时间判断, 部分文件系统不支持创建时间
+                    if let Ok(modified) = metadata.modified() {
+                        if let Ok(modified_secs) = modified.duration_since(UNIX_EPOCH) {
+                            if modified_secs.as_secs() < cutoff {
                                 let _ = std::fs::remove_file(entry.path());
                             }
                         }
@@ -121,6 +132,7 @@ mod tests {
             csv_data: PathBuf::from("/test/csv_data"),
             csv_temp: PathBuf::from("/test/csv_temp"),
             config: PathBuf::from("/test/config"),
+            golden: PathBuf::from("/test/golden"),
         };
 
         let temp_file = paths.csv_temp_file("20250107_120000");